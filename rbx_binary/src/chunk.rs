@@ -20,33 +20,76 @@ pub struct Chunk {
 
 impl Chunk {
     /// Reads and decodes a `Chunk` from the given reader.
-    pub fn decode<R: Read>(mut reader: R) -> io::Result<Chunk> {
+    pub fn decode<R: Read>(reader: R) -> io::Result<Chunk> {
+        RawChunk::decode(reader)?.decompress()
+    }
+}
+
+/// A chunk that has been read from a binary model file but not decompressed
+/// yet. Splitting these steps lets us tell a chunk that was cut off apart
+/// from one whose contents are corrupt.
+#[derive(Debug)]
+pub struct RawChunk {
+    header: ChunkHeader,
+    data: Vec<u8>,
+}
+
+impl RawChunk {
+    /// Reads a chunk's header and data from the given reader, returning an
+    /// error of kind `UnexpectedEof` if the reader ends before the chunk does.
+    pub fn decode<R: Read>(mut reader: R) -> io::Result<RawChunk> {
         let header = decode_chunk_header(&mut reader)?;
 
         log::trace!("{header}");
 
+        let stored_len = if header.compressed_len == 0 {
+            header.len
+        } else {
+            header.compressed_len
+        };
+
+        let mut data = Vec::with_capacity(stored_len as usize);
+        reader.take(stored_len as u64).read_to_end(&mut data)?;
+
+        if data.len() != stored_len as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("{header} ended after {} of {stored_len} bytes", data.len()),
+            ));
+        }
+
+        Ok(RawChunk { header, data })
+    }
+
+    /// The 4-byte name of the chunk, like `INST` or `PROP`.
+    pub fn name(&self) -> [u8; 4] {
+        self.header.name
+    }
+
+    /// Decompresses the chunk's data, returning an error of kind
+    /// `InvalidData` if it's corrupt.
+    pub fn decompress(self) -> io::Result<Chunk> {
+        let RawChunk { header, data } = self;
+
         let data = if header.compressed_len == 0 {
             log::trace!("No compression");
-            let mut data = Vec::with_capacity(header.len as usize);
-            reader.take(header.len as u64).read_to_end(&mut data)?;
             data
+        } else if data.starts_with(ZSTD_MAGIC_NUMBER) {
+            log::trace!("ZSTD compression");
+            zstd::bulk::decompress(&data, header.len as usize)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
         } else {
-            let mut compressed_data = Vec::with_capacity(header.compressed_len as usize);
-            reader
-                .take(header.compressed_len as u64)
-                .read_to_end(&mut compressed_data)?;
-
-            if &compressed_data[0..4] == ZSTD_MAGIC_NUMBER {
-                log::trace!("ZSTD compression");
-                zstd::bulk::decompress(&compressed_data, header.len as usize)?
-            } else {
-                log::trace!("LZ4 compression");
-                lz4_flex::block::decompress(&compressed_data, header.len as usize)
-                    .map_err(io::Error::other)?
-            }
+            log::trace!("LZ4 compression");
+            lz4_flex::block::decompress(&data, header.len as usize)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
         };
 
-        assert_eq!(data.len(), header.len as usize);
+        if data.len() != header.len as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{header} decompressed to {} bytes", data.len()),
+            ));
+        }
 
         Ok(Chunk {
             name: header.name,
@@ -62,7 +105,7 @@ impl Chunk {
 /// automatically.
 #[must_use]
 pub struct ChunkBuilder {
-    chunk_name: &'static [u8],
+    chunk_name: [u8; 4],
     compression: CompressionType,
     buffer: Vec<u8>,
 }
@@ -70,9 +113,9 @@ pub struct ChunkBuilder {
 impl ChunkBuilder {
     /// Creates a new `ChunkBuilder` with the given name and compression
     /// setting.
-    pub fn new(chunk_name: &'static [u8], compression: CompressionType) -> Self {
+    pub fn new(chunk_name: &[u8; 4], compression: CompressionType) -> Self {
         ChunkBuilder {
-            chunk_name,
+            chunk_name: *chunk_name,
             compression,
             buffer: Vec::new(),
         }
@@ -87,7 +130,7 @@ impl ChunkBuilder {
 
     /// Consume the chunk and write it to the given writer.
     pub fn dump<W: Write>(self, mut writer: W) -> io::Result<()> {
-        writer.write_all(self.chunk_name)?;
+        writer.write_all(&self.chunk_name)?;
 
         match self.compression {
             CompressionType::Lz4 => {
@@ -175,10 +218,12 @@ fn decode_chunk_header<R: Read>(source: &mut R) -> io::Result<ChunkHeader> {
     let reserved = source.read_le_u32()?;
 
     if reserved != 0 {
-        panic!(
-            "Chunk reserved space was not zero, it was {}. This chunk may be malformed.",
-            reserved
-        );
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Chunk reserved space was not zero, it was {reserved}. This chunk may be malformed."
+            ),
+        ));
     }
 
     Ok(ChunkHeader {
@@ -0,0 +1,240 @@
+use std::fmt;
+
+use rbx_dom_weak::types::{Ref, VariantType};
+
+/// Something unusual about a file that the deserializer was able to recover
+/// from, returned by
+/// [`Deserializer::deserialize_with_diagnostics`][crate::Deserializer::deserialize_with_diagnostics].
+///
+/// In [strict mode][crate::Deserializer::strict], the ones that lose data
+/// are errors instead. See [`DiagnosticKind::loses_data`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    kind: DiagnosticKind,
+    location: Option<DiagnosticLocation>,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(kind: DiagnosticKind, location: Option<DiagnosticLocation>) -> Self {
+        Self { kind, location }
+    }
+
+    /// What happened.
+    pub fn kind(&self) -> &DiagnosticKind {
+        &self.kind
+    }
+
+    /// Where in the file this was found, if it came from a single chunk.
+    pub fn location(&self) -> Option<DiagnosticLocation> {
+        self.location
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(formatter, "{location}: {}", self.kind),
+            None => write!(formatter, "{}", self.kind),
+        }
+    }
+}
+
+/// Where a [`Diagnostic`] was found in a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DiagnosticLocation {
+    /// The 0-based index of the chunk the diagnostic was found in, counting
+    /// every chunk after the file header.
+    pub chunk: usize,
+}
+
+impl fmt::Display for DiagnosticLocation {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "chunk {}", self.chunk)
+    }
+}
+
+/// The kinds of [`Diagnostic`] that can be reported while deserializing.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+    /// A property was skipped because rbx_binary doesn't know how to decode
+    /// its type.
+    UnsupportedPropertyType {
+        /// The class the property is on.
+        class_name: String,
+        /// The name the property was serialized with.
+        property_name: String,
+        /// The binary type ID of the property.
+        type_id: u8,
+    },
+
+    /// A property was skipped because the reflection database says that it
+    /// shouldn't be serialized.
+    NonSerializedProperty {
+        /// The class the property is on.
+        class_name: String,
+        /// The name the property was serialized with.
+        property_name: String,
+    },
+
+    /// A property was skipped because its PROP chunk didn't contain a type.
+    /// Roblox ignores these chunks too.
+    UntypedProperty {
+        /// The class the property is on.
+        class_name: String,
+        /// The name the property was serialized with.
+        property_name: String,
+    },
+
+    /// A property couldn't be decoded as the type that the reflection
+    /// database expects, so it was decoded as another type instead.
+    TypeMismatch {
+        /// The class the property is on.
+        class_name: String,
+        /// The name of the property.
+        property_name: String,
+        /// The type the reflection database expects.
+        expected: VariantType,
+        /// The type the property was decoded as.
+        actual: VariantType,
+    },
+
+    /// A string property wasn't valid UTF-8, so invalid sequences were
+    /// replaced with `U+FFFD`.
+    InvalidUtf8 {
+        /// The class the property is on.
+        class_name: String,
+        /// The name of the property.
+        property_name: String,
+    },
+
+    /// A property was migrated to one or more newer properties, like `Font`
+    /// to `FontFace`.
+    MigrationApplied {
+        /// The class the property is on.
+        class_name: String,
+        /// The name of the property that was migrated.
+        property_name: String,
+        /// The names of the properties it was migrated to.
+        new_property_names: Vec<String>,
+    },
+
+    /// Migrating a property failed on some instances, so its value was
+    /// dropped from them.
+    MigrationFailed {
+        /// The class the property is on.
+        class_name: String,
+        /// The name of the property that couldn't be migrated.
+        property_name: String,
+        /// How many instances the migration failed on.
+        count: usize,
+    },
+
+    /// An instance wasn't given a name by the file, so it was named after its
+    /// class.
+    UnnamedInstance {
+        /// The class of the instance.
+        class_name: String,
+        /// The instance in the decoded `WeakDom`.
+        referent: Ref,
+    },
+
+    /// The file contained a chunk that rbx_binary doesn't understand. It's
+    /// kept in the file's [`FileMetadata`][crate::FileMetadata].
+    UnknownChunk {
+        /// The name of the chunk.
+        name: String,
+    },
+}
+
+impl DiagnosticKind {
+    /// Tells whether some of the file's data was dropped or changed, like a
+    /// property that was skipped or a value that couldn't be decoded as-is.
+    /// Only these are errors in [strict mode][crate::Deserializer::strict].
+    ///
+    /// Migrations, unnamed instances, and unknown chunks don't lose anything,
+    /// since they happen all the time in files saved by Roblox Studio.
+    pub fn loses_data(&self) -> bool {
+        match self {
+            DiagnosticKind::UnsupportedPropertyType { .. }
+            | DiagnosticKind::NonSerializedProperty { .. }
+            | DiagnosticKind::UntypedProperty { .. }
+            | DiagnosticKind::TypeMismatch { .. }
+            | DiagnosticKind::InvalidUtf8 { .. }
+            | DiagnosticKind::MigrationFailed { .. } => true,
+
+            DiagnosticKind::MigrationApplied { .. }
+            | DiagnosticKind::UnnamedInstance { .. }
+            | DiagnosticKind::UnknownChunk { .. } => false,
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticKind::UnsupportedPropertyType {
+                class_name,
+                property_name,
+                type_id,
+            } => write!(
+                formatter,
+                "Property {class_name}.{property_name} has unsupported type ID {type_id:#04x} and was skipped"
+            ),
+            DiagnosticKind::NonSerializedProperty {
+                class_name,
+                property_name,
+            } => write!(
+                formatter,
+                "Property {class_name}.{property_name} should not be serialized and was skipped"
+            ),
+            DiagnosticKind::UntypedProperty {
+                class_name,
+                property_name,
+            } => write!(
+                formatter,
+                "Property {class_name}.{property_name} has no type and was skipped"
+            ),
+            DiagnosticKind::TypeMismatch {
+                class_name,
+                property_name,
+                expected,
+                actual,
+            } => write!(
+                formatter,
+                "Property {class_name}.{property_name} should be {expected:?} but was decoded as {actual:?}"
+            ),
+            DiagnosticKind::InvalidUtf8 {
+                class_name,
+                property_name,
+            } => write!(
+                formatter,
+                "Property {class_name}.{property_name} is not valid UTF-8 and was converted lossily"
+            ),
+            DiagnosticKind::MigrationApplied {
+                class_name,
+                property_name,
+                new_property_names,
+            } => write!(
+                formatter,
+                "Property {class_name}.{property_name} was migrated to {}",
+                new_property_names.join(", ")
+            ),
+            DiagnosticKind::MigrationFailed {
+                class_name,
+                property_name,
+                count,
+            } => write!(
+                formatter,
+                "Property {class_name}.{property_name} could not be migrated on {count} instance(s) and was dropped"
+            ),
+            DiagnosticKind::UnnamedInstance { class_name, .. } => {
+                write!(formatter, "An instance of {class_name} has no name")
+            }
+            DiagnosticKind::UnknownChunk { name } => {
+                write!(formatter, "Unknown chunk {name} was preserved")
+            }
+        }
+    }
+}
@@ -4,6 +4,8 @@ use thiserror::Error;
 
 use crate::types::InvalidTypeError;
 
+use super::diagnostics::Diagnostic;
+
 /// Represents an error that occurred during deserialization.
 #[derive(Debug, Error)]
 #[error(transparent)]
@@ -91,4 +93,7 @@ pub(crate) enum InnerError {
 
     #[error("Malformed file: INST Chunk with type_id {type_id} has already appeared in the file")]
     DuplicateInstChunk { type_id: u32 },
+
+    #[error("Strict mode: {diagnostic}")]
+    Strict { diagnostic: Diagnostic },
 }
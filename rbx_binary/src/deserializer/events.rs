@@ -0,0 +1,181 @@
+use std::{collections::VecDeque, io::Read};
+
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    Ustr,
+};
+
+use crate::{core::RbxReadExt, metadata::FileMetadata};
+
+use super::{error::InnerError, state::DeserializerState, Error};
+
+/// A single piece of information decoded from a binary model or place, as
+/// produced by [`DeserializeEvents`].
+///
+/// Referents in events are the same [`Ref`] values that the instances would
+/// be given by [`Deserializer::deserialize`][crate::Deserializer::deserialize],
+/// including the values of `Ref` properties.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DeserializeEvent {
+    /// A class was declared by the file, along with how many instances of it
+    /// the file contains. Always followed by one
+    /// [`InstanceCreated`][DeserializeEvent::InstanceCreated] event per
+    /// instance.
+    TypeDeclared {
+        /// The file-defined ID of this type.
+        type_id: u32,
+        /// The name of the class, like `Part` or `Workspace`.
+        class: Ustr,
+        /// The number of instances of this class in the file.
+        count: usize,
+    },
+
+    /// An instance was declared by the file. Its properties and parent are
+    /// reported by later events.
+    InstanceCreated {
+        /// The referent the instance would have in a `WeakDom`.
+        referent: Ref,
+        /// The class of the instance.
+        class: Ustr,
+    },
+
+    /// A property was decoded for every instance of a class that has it.
+    ///
+    /// Instance names are reported through this event as a `Name` property
+    /// containing a `String`.
+    PropertyDecoded {
+        /// The class that the property belongs to.
+        class: Ustr,
+        /// The canonical name of the property.
+        name: Ustr,
+        /// The value of the property for each instance.
+        values: Vec<(Ref, Variant)>,
+    },
+
+    /// An instance was given a parent. Instances at the top level of the file
+    /// have a parent of `Ref::none()`.
+    ParentSet {
+        /// The instance being parented.
+        child: Ref,
+        /// The new parent of the instance.
+        parent: Ref,
+    },
+}
+
+/// A pull-based reader over the contents of a binary model or place, created
+/// with [`Deserializer::events`][crate::Deserializer::events].
+///
+/// Chunks are read from the underlying stream only as events are requested.
+/// Property values are handed off to the caller as soon as they're decoded
+/// and are never collected into a tree.
+///
+/// Property values aren't kept after they're reported, but a small record of
+/// every instance, holding its referent and class, is kept until the reader
+/// is dropped so that later chunks can refer back to it. Memory use still
+/// grows with the number of instances in the file.
+///
+/// After an error is returned, the iterator is exhausted.
+pub struct DeserializeEvents<'db, R> {
+    state: DeserializerState<'db, R>,
+    pending: VecDeque<DeserializeEvent>,
+    finished: bool,
+}
+
+impl<'db, R: Read> DeserializeEvents<'db, R> {
+    pub(super) fn new(state: DeserializerState<'db, R>) -> Self {
+        Self {
+            state,
+            pending: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    /// Returns the `META` entries and unknown chunks read from the file so
+    /// far. Once the iterator is exhausted without an error, this is the same
+    /// [`FileMetadata`] that
+    /// [`Deserializer::deserialize_with_metadata`][crate::Deserializer::deserialize_with_metadata]
+    /// returns.
+    pub fn metadata(&self) -> &FileMetadata {
+        self.state.metadata()
+    }
+
+    fn decode_next_chunk(&mut self) -> Result<(), InnerError> {
+        let chunk = self.state.next_chunk()?;
+
+        match &chunk.name {
+            b"META" => self.state.decode_meta_chunk(&chunk.data)?,
+            b"SSTR" => self.state.decode_sstr_chunk(&chunk.data)?,
+            b"INST" => {
+                self.state.decode_inst_chunk(&chunk.data)?;
+
+                let type_id = (&chunk.data[..]).read_le_u32()?;
+                if let Some((class, referents)) = self.state.declared_type(type_id) {
+                    self.pending.push_back(DeserializeEvent::TypeDeclared {
+                        type_id,
+                        class,
+                        count: referents.len(),
+                    });
+                    self.pending.extend(
+                        referents
+                            .into_iter()
+                            .map(|referent| DeserializeEvent::InstanceCreated { referent, class }),
+                    );
+                }
+            }
+            b"PROP" => {
+                self.state.decode_prop_chunk(&chunk.data)?;
+
+                let mut header = &chunk.data[..];
+                let type_id = header.read_le_u32()?;
+                let is_name = header.read_string()? == "Name";
+
+                if let Some((class, _)) = self.state.declared_type(type_id) {
+                    for (name, values) in self.state.take_property_columns(type_id, is_name) {
+                        self.pending.push_back(DeserializeEvent::PropertyDecoded {
+                            class,
+                            name,
+                            values,
+                        });
+                    }
+                }
+            }
+            b"PRNT" => {
+                let links = self.state.resolve_prnt_chunk(&chunk.data)?;
+                self.pending.extend(
+                    links
+                        .into_iter()
+                        .map(|(child, parent)| DeserializeEvent::ParentSet { child, parent }),
+                );
+            }
+            b"END\0" => {
+                self.state.decode_end_chunk(&chunk.data)?;
+                self.finished = true;
+            }
+            _ => self.state.preserve_unknown_chunk(chunk)?,
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for DeserializeEvents<'_, R> {
+    type Item = Result<DeserializeEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+
+            if self.finished {
+                return None;
+            }
+
+            if let Err(err) = self.decode_next_chunk() {
+                self.finished = true;
+                return Some(Err(err.into()));
+            }
+        }
+    }
+}
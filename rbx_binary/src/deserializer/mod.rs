@@ -1,18 +1,28 @@
+mod diagnostics;
 mod error;
+mod events;
 mod header;
+mod salvage;
 mod state;
 
-use std::{io::Read, str};
+use std::io::Read;
 
-use rbx_dom_weak::WeakDom;
+use rbx_dom_weak::{Ustr, UstrSet, WeakDom};
 use rbx_reflection::ReflectionDatabase;
 
+use crate::metadata::FileMetadata;
+
 use self::state::DeserializerState;
 
 #[cfg(any(test, feature = "unstable_text_format"))]
 pub(crate) use self::header::FileHeader;
 
-pub use self::error::Error;
+pub use self::{
+    diagnostics::{Diagnostic, DiagnosticKind, DiagnosticLocation},
+    error::Error,
+    events::{DeserializeEvent, DeserializeEvents},
+    salvage::{LostChunk, SalvageReport},
+};
 
 /// A configurable deserializer for Roblox binary models and places.
 ///
@@ -45,10 +55,30 @@ pub use self::error::Error;
 /// A custom [`ReflectionDatabase`][ReflectionDatabase] can be specified via
 /// [`reflection_database`][reflection_database].
 ///
+/// Decoding can be limited to part of a file with
+/// [`only_classes`][only_classes] and [`skip_properties`][skip_properties].
+/// Property data that is filtered out is skipped without being decoded.
+///
+/// Problems that the deserializer can recover from, like properties with
+/// unknown types, are returned by
+/// [`deserialize_with_diagnostics`][deserialize_with_diagnostics]. The ones
+/// that lose data can instead be turned into errors with [`strict`][strict].
+///
+/// Files that were damaged, like by being cut off partway through, can be
+/// partially recovered with [`salvage`][salvage].
+///
 /// [ReflectionDatabase]: rbx_reflection::ReflectionDatabase
 /// [reflection_database]: Deserializer#method.reflection_database
+/// [only_classes]: Deserializer#method.only_classes
+/// [skip_properties]: Deserializer#method.skip_properties
+/// [deserialize_with_diagnostics]: Deserializer#method.deserialize_with_diagnostics
+/// [strict]: Deserializer#method.strict
+/// [salvage]: Deserializer#method.salvage
 pub struct Deserializer<'db> {
     database: &'db ReflectionDatabase<'db>,
+    only_classes: Option<UstrSet>,
+    skipped_properties: UstrSet,
+    strict: bool,
 }
 
 impl<'db> Deserializer<'db> {
@@ -56,13 +86,57 @@ impl<'db> Deserializer<'db> {
     pub fn new() -> Self {
         Self {
             database: rbx_reflection_database::get().unwrap(),
+            only_classes: None,
+            skipped_properties: UstrSet::default(),
+            strict: false,
         }
     }
 
     /// Sets what reflection database for the deserializer to use.
     #[inline]
     pub fn reflection_database(self, database: &'db ReflectionDatabase<'db>) -> Self {
-        Self { database }
+        Self { database, ..self }
+    }
+
+    /// Only decode instances of the given classes, or of classes that inherit
+    /// from them according to the reflection database.
+    ///
+    /// Ancestors of those instances are kept so that the shape of the tree is
+    /// preserved, but only their names are decoded. Every other instance is
+    /// left out of the resulting `WeakDom`, and `Ref` properties pointing to
+    /// them are set to `Ref::none()`.
+    pub fn only_classes<I, S>(self, classes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Ustr>,
+    {
+        Self {
+            only_classes: Some(classes.into_iter().map(Into::into).collect()),
+            ..self
+        }
+    }
+
+    /// Never decode properties with any of the given names on any class. Both
+    /// the name a property is serialized with and its canonical name are
+    /// checked.
+    pub fn skip_properties<I, S>(self, properties: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Ustr>,
+    {
+        Self {
+            skipped_properties: properties.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    /// Sets whether every [`Diagnostic`] that loses data should be returned as
+    /// an error instead of being recovered from. Defaults to `false`.
+    ///
+    /// See [`DiagnosticKind::loses_data`] for which ones do.
+    #[inline]
+    pub fn strict(self, strict: bool) -> Self {
+        Self { strict, ..self }
     }
 
     /// Deserialize a Roblox binary model or place from the given stream using
@@ -70,6 +144,118 @@ impl<'db> Deserializer<'db> {
     pub fn deserialize<R: Read>(&self, reader: R) -> Result<WeakDom, Error> {
         profiling::scope!("rbx_binary::deserialize");
 
+        let (dom, _, _) = self.deserialize_inner(reader)?;
+        Ok(dom)
+    }
+
+    /// Deserialize a Roblox binary model or place from the given stream using
+    /// this deserializer, also returning the file's [`FileMetadata`].
+    ///
+    /// Passing the metadata to
+    /// [`Serializer::serialize_with_metadata`][crate::Serializer::serialize_with_metadata]
+    /// preserves `META` entries and any chunks that rbx_binary doesn't
+    /// understand when writing the file back out.
+    pub fn deserialize_with_metadata<R: Read>(
+        &self,
+        reader: R,
+    ) -> Result<(WeakDom, FileMetadata), Error> {
+        profiling::scope!("rbx_binary::deserialize");
+
+        let (dom, metadata, _) = self.deserialize_inner(reader)?;
+        Ok((dom, metadata))
+    }
+
+    /// Deserialize a Roblox binary model or place from the given stream using
+    /// this deserializer, also returning a [`Diagnostic`] for every problem
+    /// with the file that was recovered from, in the order they were found.
+    pub fn deserialize_with_diagnostics<R: Read>(
+        &self,
+        reader: R,
+    ) -> Result<(WeakDom, Vec<Diagnostic>), Error> {
+        profiling::scope!("rbx_binary::deserialize");
+
+        let (dom, _, diagnostics) = self.deserialize_inner(reader)?;
+        Ok((dom, diagnostics))
+    }
+
+    /// Recover as much as possible from a Roblox binary model or place that's
+    /// been damaged, like by being cut off partway through or by having
+    /// chunks that can't be decompressed.
+    ///
+    /// Every chunk that can be decoded is kept, and every other chunk is
+    /// skipped, including PROP chunks that were only partly decoded.
+    /// Instances that the file never gave a parent to are put in a `Folder`
+    /// named `Salvaged`. The returned [`SalvageReport`] describes what was
+    /// lost.
+    ///
+    /// An error is only returned if the file header can't be read.
+    pub fn salvage<R: Read>(&self, reader: R) -> Result<(WeakDom, SalvageReport), Error> {
+        profiling::scope!("rbx_binary::salvage");
+
+        let mut deserializer = DeserializerState::new(self, reader)?;
+        let mut report = SalvageReport::default();
+
+        loop {
+            let raw_chunk = match deserializer.next_raw_chunk() {
+                Ok(raw_chunk) => raw_chunk,
+                Err(err) => {
+                    // We can't tell where the next chunk starts after a chunk
+                    // that couldn't be read, so this is as far as we get.
+                    log::warn!("Stopped salvaging because a chunk couldn't be read: {err}");
+                    report.truncated = true;
+                    break;
+                }
+            };
+
+            let name = raw_chunk.name();
+            let chunk = match raw_chunk.decompress() {
+                Ok(chunk) => chunk,
+                Err(err) => {
+                    let reason = err.to_string();
+                    report
+                        .lost_chunks
+                        .push(deserializer.lost_chunk(name, &[], reason));
+                    continue;
+                }
+            };
+
+            let result = match &chunk.name {
+                b"META" => deserializer.decode_meta_chunk(&chunk.data),
+                b"SSTR" => deserializer.decode_sstr_chunk(&chunk.data),
+                b"INST" => deserializer.salvage_inst_chunk(&chunk.data),
+                b"PROP" => deserializer.salvage_prop_chunk(&chunk.data),
+                b"PRNT" => deserializer.decode_prnt_chunk(&chunk.data),
+                b"END\0" => break,
+                _ => {
+                    if let Err(err) = deserializer.preserve_unknown_chunk(chunk) {
+                        let reason = err.to_string();
+                        report
+                            .lost_chunks
+                            .push(deserializer.lost_chunk(name, &[], reason));
+                    }
+                    continue;
+                }
+            };
+
+            if let Err(err) = result {
+                let reason = err.to_string();
+                report
+                    .lost_chunks
+                    .push(deserializer.lost_chunk(name, &chunk.data, reason));
+            }
+        }
+
+        report.orphans = deserializer.collect_orphans();
+        report.diagnostics = deserializer.take_diagnostics();
+        let (dom, _) = deserializer.finish();
+
+        Ok((dom, report))
+    }
+
+    fn deserialize_inner<R: Read>(
+        &self,
+        reader: R,
+    ) -> Result<(WeakDom, FileMetadata, Vec<Diagnostic>), Error> {
         let mut deserializer = DeserializerState::new(self, reader)?;
 
         loop {
@@ -85,14 +271,58 @@ impl<'db> Deserializer<'db> {
                     deserializer.decode_end_chunk(&chunk.data)?;
                     break;
                 }
-                _ => match str::from_utf8(&chunk.name) {
-                    Ok(name) => log::info!("Unknown binary chunk name {name}"),
-                    Err(_) => log::info!("Unknown binary chunk name {:?}", chunk.name),
-                },
+                _ => deserializer.preserve_unknown_chunk(chunk)?,
             }
         }
 
-        Ok(deserializer.finish())
+        deserializer.report_unnamed_instances()?;
+        let diagnostics = deserializer.take_diagnostics();
+        let (dom, metadata) = deserializer.finish();
+
+        Ok((dom, metadata, diagnostics))
+    }
+
+    /// Read a Roblox binary model or place from the given stream as a sequence
+    /// of [`DeserializeEvent`]s instead of building a `WeakDom`.
+    ///
+    /// This is useful for scanning large files or extracting a small part of
+    /// them without keeping every property of every instance in memory. Only
+    /// the file header is read before this method returns.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    ///
+    /// use rbx_binary::{DeserializeEvent, Deserializer};
+    ///
+    /// let input = BufReader::new(File::open("Place.rbxl")?);
+    ///
+    /// let deserializer = Deserializer::new();
+    /// for event in deserializer.events(input)? {
+    ///     if let DeserializeEvent::PropertyDecoded { class, name, values } = event? {
+    ///         println!("{class}.{name}: {} values", values.len());
+    ///     }
+    /// }
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn events<R: Read>(&self, reader: R) -> Result<DeserializeEvents<'_, R>, Error> {
+        Ok(DeserializeEvents::new(DeserializerState::new(
+            self, reader,
+        )?))
+    }
+}
+
+impl Deserializer<'_> {
+    /// Tells whether this deserializer was configured to skip properties with
+    /// the given name.
+    fn is_property_skipped(&self, prop_name: &str) -> bool {
+        // Names that were never interned can't be in the set, so we avoid
+        // interning every property name we come across.
+        !self.skipped_properties.is_empty()
+            && Ustr::from_existing(prop_name)
+                .is_some_and(|name| self.skipped_properties.contains(&name))
     }
 }
 
@@ -0,0 +1,52 @@
+use rbx_dom_weak::types::Ref;
+
+use super::diagnostics::Diagnostic;
+
+/// Describes what couldn't be recovered from a damaged file, returned by
+/// [`Deserializer::salvage`][crate::Deserializer::salvage].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct SalvageReport {
+    /// Every chunk that was read from the file but couldn't be decoded, in
+    /// the order they appear in the file.
+    pub lost_chunks: Vec<LostChunk>,
+
+    /// Whether the file ended, or a chunk header couldn't be read, before its
+    /// `END` chunk. Everything after the last chunk that was read is lost.
+    pub truncated: bool,
+
+    /// Instances that the file never gave a parent to, usually because its
+    /// PRNT chunk was lost. They're put in a `Folder` named `Salvaged` at the
+    /// top level of the tree.
+    pub orphans: Vec<Ref>,
+
+    /// Problems that were recovered from in the parts of the file that were
+    /// decoded, like with
+    /// [`Deserializer::deserialize_with_diagnostics`][crate::Deserializer::deserialize_with_diagnostics].
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl SalvageReport {
+    /// Tells whether the whole file was recovered.
+    pub fn is_complete(&self) -> bool {
+        self.lost_chunks.is_empty() && !self.truncated && self.orphans.is_empty()
+    }
+}
+
+/// A chunk that was skipped while salvaging a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LostChunk {
+    /// The name of the chunk, like `INST` or `PROP`.
+    pub name: String,
+
+    /// The class that an `INST` or `PROP` chunk was for, if it could be
+    /// determined.
+    pub class_name: Option<String>,
+
+    /// The property that a `PROP` chunk was for, if it could be determined.
+    pub property_name: Option<String>,
+
+    /// Why the chunk couldn't be decoded.
+    pub reason: String,
+}
@@ -1,4 +1,4 @@
-use std::{collections::VecDeque, convert::TryInto, io::Read};
+use std::{collections::VecDeque, convert::TryInto, io::Read, str};
 
 use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
 use rbx_dom_weak::{
@@ -15,12 +15,19 @@ use rbx_dom_weak::{
 use rbx_reflection::{ClassDescriptor, PropertyKind, PropertySerialization, ReflectionDatabase};
 
 use crate::{
-    chunk::Chunk,
+    chunk::{Chunk, RawChunk},
     core::{find_property_descriptors, RbxReadExt},
+    metadata::{FileMetadata, UnknownChunk},
     types::Type,
 };
 
-use super::{error::InnerError, header::FileHeader, Deserializer};
+use super::{
+    diagnostics::{Diagnostic, DiagnosticKind, DiagnosticLocation},
+    error::InnerError,
+    header::FileHeader,
+    salvage::LostChunk,
+    Deserializer,
+};
 
 pub(super) struct DeserializerState<'db, R> {
     /// The user-provided configuration that we should use.
@@ -34,8 +41,9 @@ pub(super) struct DeserializerState<'db, R> {
     tree: WeakDom,
 
     /// The metadata contained in the file, which affects how some constructs
-    /// are interpreted by Roblox.
-    metadata: HashMap<String, String>,
+    /// are interpreted by Roblox, along with any chunks we don't understand.
+    /// Eventually returned to the user.
+    metadata: FileMetadata,
 
     /// The SharedStrings contained in the file, if any, in the order that they
     /// appear in the file.
@@ -56,10 +64,22 @@ pub(super) struct DeserializerState<'db, R> {
     /// in the file.
     root_instance_refs: Vec<i32>,
 
+    /// Referents for instances that the file never gave a parent to, which
+    /// are only kept when salvaging a damaged file.
+    orphan_refs: Vec<i32>,
+
     /// Contains a set of unknown type IDs that we've encountered so far while
     /// deserializing this file. We use this map in order to ensure we only
     /// print one warning per unknown type ID when deserializing a file.
     unknown_type_ids: HashSet<u8>,
+
+    /// Everything unusual about the file that we've recovered from so far.
+    /// Eventually returned to the user if they asked for it.
+    diagnostics: Vec<Diagnostic>,
+
+    /// How many chunks have been read from the file so far, which tells us
+    /// which chunk diagnostics come from.
+    chunks_read: usize,
 }
 
 /// Represents a unique instance class. Binary models define all their instance
@@ -74,6 +94,13 @@ struct TypeInfo<'db> {
     /// A reference to the type's class descriptor from rbx_reflection, if this
     /// is a known class.
     class_descriptor: Option<&'db ClassDescriptor<'db>>,
+
+    /// Whether instances of this type were selected by the deserializer's
+    /// class filter. Only the names of unselected instances are decoded.
+    selected: bool,
+
+    /// Whether a `Name` PROP chunk has been decoded for this type.
+    named: bool,
 }
 
 /// A key into an array of instances which also contains the instance ref
@@ -92,6 +119,10 @@ struct Instance {
 
     /// Document-defined IDs for the children of this instance.
     children: Vec<i32>,
+
+    /// Whether a PRNT chunk has given this instance a parent, including no
+    /// parent at all.
+    parented: bool,
 }
 
 /// Properties may be serialized under different names or types than
@@ -106,12 +137,15 @@ struct CanonicalProperty<'db> {
     migration: Option<&'db PropertySerialization<'db>>,
 }
 
+/// Finds the name and type that a property should be decoded as. If the
+/// property should be skipped, returns the diagnostic explaining why.
 fn find_canonical_property<'de>(
     database: &'de ReflectionDatabase,
     binary_type: Type,
+    type_name: &str,
     class_descriptor: Option<&'de ClassDescriptor<'de>>,
     prop_name: &str,
-) -> Option<CanonicalProperty<'de>> {
+) -> Result<CanonicalProperty<'de>, DiagnosticKind> {
     match find_property_descriptors(database, class_descriptor, prop_name) {
         Some((_, descriptors)) => {
             // If this descriptor is known but wasn't supposed to be
@@ -135,7 +169,10 @@ fn find_canonical_property<'de>(
                         "Skipping property {} as it is canonical and should not serialize.",
                         descriptors.canonical.name
                     );
-                    return None;
+                    return Err(DiagnosticKind::NonSerializedProperty {
+                        class_name: type_name.to_owned(),
+                        property_name: prop_name.to_owned(),
+                    });
                 }
             }
 
@@ -153,7 +190,7 @@ fn find_canonical_property<'de>(
                 "Known prop, canonical name {canonical_name} and type {canonical_type:?}, with {migration:?} migration",
             );
 
-            Some(CanonicalProperty {
+            Ok(CanonicalProperty {
                 name: canonical_name.into(),
                 ty: canonical_type,
                 migration,
@@ -164,13 +201,17 @@ fn find_canonical_property<'de>(
                 Some(rbx_type) => rbx_type,
                 None => {
                     log::warn!("Unsupported prop type {binary_type:?}, skipping property");
-                    return None;
+                    return Err(DiagnosticKind::UnsupportedPropertyType {
+                        class_name: type_name.to_owned(),
+                        property_name: prop_name.to_owned(),
+                        type_id: binary_type as u8,
+                    });
                 }
             };
 
             log::trace!("Unknown prop, using type {canonical_type:?}");
 
-            Some(CanonicalProperty {
+            Ok(CanonicalProperty {
                 name: prop_name.into(),
                 ty: canonical_type,
                 migration: None,
@@ -204,10 +245,59 @@ fn add_property(instance: &mut Instance, canonical_property: &CanonicalProperty,
     } else {
         instance
             .builder
-            .add_property(canonical_property.name, value)
+            .add_property(canonical_property.name, value);
     }
 }
 
+/// Replaces any `Ref` or object `Content` values pointing to instances that
+/// were left out of the tree with empty values.
+fn clear_dropped_refs(builder: &mut InstanceBuilder, retained_refs: &HashSet<Ref>) {
+    let properties: Vec<_> = builder
+        .drain_properties()
+        .map(|(name, value)| {
+            let value = match value {
+                Variant::Ref(referent) if !retained_refs.contains(&referent) => {
+                    Variant::Ref(Ref::none())
+                }
+                Variant::Content(content)
+                    if content
+                        .as_object()
+                        .is_some_and(|referent| !retained_refs.contains(&referent)) =>
+                {
+                    Variant::Content(Content::none())
+                }
+                value => value,
+            };
+
+            (name, value)
+        })
+        .collect();
+
+    builder.add_properties(properties);
+}
+
+/// Reads the header of a PRNT chunk and returns pairs of document-defined IDs
+/// for each child and its parent.
+fn read_prnt_chunk(mut chunk: &[u8]) -> Result<impl Iterator<Item = (i32, i32)> + '_, InnerError> {
+    let version = chunk.read_u8()?;
+
+    if version != 0 {
+        return Err(InnerError::UnknownChunkVersion {
+            chunk_name: "PRNT",
+            version: version as u32,
+        });
+    }
+
+    let number_objects = chunk.read_le_u32()?;
+
+    log::trace!("PRNT chunk ({number_objects} instances)");
+
+    let subjects = chunk.read_referent_array(number_objects as usize)?;
+    let parents = chunk.read_referent_array(number_objects as usize)?;
+
+    Ok(subjects.zip(parents))
+}
+
 impl<'db, R: Read> DeserializerState<'db, R> {
     pub(super) fn new(
         deserializer: &'db Deserializer<'db>,
@@ -227,30 +317,42 @@ impl<'db, R: Read> DeserializerState<'db, R> {
             deserializer,
             input,
             tree,
-            metadata: HashMap::new(),
+            metadata: FileMetadata::default(),
             shared_strings: Vec::new(),
             type_infos,
             instance_key_by_ref,
             instances,
             root_instance_refs: Vec::new(),
+            orphan_refs: Vec::new(),
             unknown_type_ids: HashSet::new(),
+            diagnostics: Vec::new(),
+            chunks_read: 0,
         })
     }
 
     pub(super) fn next_chunk(&mut self) -> Result<Chunk, InnerError> {
-        Ok(Chunk::decode(&mut self.input)?)
+        let chunk = Chunk::decode(&mut self.input)?;
+        self.chunks_read += 1;
+        Ok(chunk)
+    }
+
+    /// Reads the next chunk without decompressing it.
+    pub(super) fn next_raw_chunk(&mut self) -> Result<RawChunk, InnerError> {
+        let chunk = RawChunk::decode(&mut self.input)?;
+        self.chunks_read += 1;
+        Ok(chunk)
     }
 
     #[profiling::function]
     pub(super) fn decode_meta_chunk(&mut self, mut chunk: &[u8]) -> Result<(), InnerError> {
         let len = chunk.read_le_u32()?;
-        self.metadata.reserve(len as usize);
+        self.metadata.entries.reserve(len as usize);
 
         for _ in 0..len {
             let key = chunk.read_string()?;
             let value = chunk.read_string()?;
 
-            self.metadata.insert(key, value);
+            self.metadata.entries.push((key, value));
         }
 
         Ok(())
@@ -298,6 +400,18 @@ impl<'db, R: Read> DeserializerState<'db, R> {
                 (None, 0)
             };
 
+        let selected = match &self.deserializer.only_classes {
+            Some(classes) => match class_descriptor {
+                Some(descriptor) => self
+                    .deserializer
+                    .database
+                    .superclasses_iter(descriptor)
+                    .any(|class| classes.contains(&Ustr::from(class.name))),
+                None => classes.contains(&Ustr::from(type_name.as_str())),
+            },
+            None => true,
+        };
+
         // TODO: Check object_format and check for service markers if it's 1?
 
         let start = self.instances.len();
@@ -321,6 +435,7 @@ impl<'db, R: Read> DeserializerState<'db, R> {
             self.instances.push(Instance {
                 builder,
                 children: Vec::new(),
+                parented: false,
             });
         }
         let end = self.instances.len();
@@ -331,6 +446,8 @@ impl<'db, R: Read> DeserializerState<'db, R> {
                 type_name: type_name.into(),
                 instances: start..end,
                 class_descriptor,
+                selected,
+                named: false,
             },
         );
 
@@ -351,10 +468,23 @@ impl<'db, R: Read> DeserializerState<'db, R> {
             type_name,
             ref instances,
             class_descriptor,
+            selected,
+            ..
         } = self
             .type_infos
             .get(&type_id)
             .ok_or(InnerError::InvalidTypeId { type_id })?;
+        let range = instances.clone();
+
+        if !selected && prop_name != "Name" {
+            log::trace!("Skipping PROP chunk {type_name}.{prop_name}, class was not selected");
+            return Ok(());
+        }
+
+        if self.deserializer.is_property_skipped(&prop_name) {
+            log::trace!("Skipping PROP chunk {type_name}.{prop_name}, property was skipped");
+            return Ok(());
+        }
 
         // PROP chunks that contain no type byte are ignored by Roblox. This can
         // happen when a new type is introduced.
@@ -365,7 +495,12 @@ impl<'db, R: Read> DeserializerState<'db, R> {
         // that end immediately after the prop name, so we do the same.
         let binary_type_byte = match chunk.read_u8() {
             Ok(byte) => byte,
-            Err(_) => return Ok(()),
+            Err(_) => {
+                return self.report(DiagnosticKind::UntypedProperty {
+                    class_name: type_name.to_string(),
+                    property_name: prop_name,
+                })
+            }
         };
 
         let binary_type: Type = match binary_type_byte.try_into() {
@@ -381,7 +516,11 @@ impl<'db, R: Read> DeserializerState<'db, R> {
                     );
                 }
 
-                return Ok(());
+                return self.report(DiagnosticKind::UnsupportedPropertyType {
+                    class_name: type_name.to_string(),
+                    property_name: prop_name,
+                    type_id: binary_type_byte,
+                });
             }
         };
 
@@ -392,20 +531,23 @@ impl<'db, R: Read> DeserializerState<'db, R> {
             type_id
         );
 
-        let instances = &mut self.instances[instances.start..instances.end];
+        let instances = &mut self.instances[range.clone()];
 
         // The `Name` prop is special and is routed to a different spot for
         // rbx_dom_weak, so we handle it specially here.
         if prop_name == "Name" {
-            // TODO: If an instance is never assigned a name through this code
-            // path, we should use the reflection database to figure out its
-            // default name. This should be rare: effectively never!
+            let mut lossy = false;
 
-            for instance in instances {
+            // Names replace whatever an earlier chunk set rather than being
+            // appended like other properties, so they're only applied once the
+            // whole chunk has been read.
+            let mut names = Vec::with_capacity(instances.len());
+            for _ in 0..instances.len() {
                 let binary_string = chunk.read_binary_string()?;
                 let value = match std::str::from_utf8(&binary_string) {
                     Ok(value) => value.to_owned(),
                     Err(_) => {
+                        lossy = true;
                         log::warn!(
                             "Performing lossy string conversion on property {}.{} because it did not contain UTF-8.
 This may cause unexpected or broken behavior in your final results if you rely on this property being non UTF-8.",
@@ -416,25 +558,52 @@ This may cause unexpected or broken behavior in your final results if you rely o
                         String::from_utf8_lossy(binary_string.as_ref()).into_owned()
                     }
                 };
-                instance.builder.set_name(value);
+                names.push(value);
+            }
+
+            for (instance, name) in instances.iter_mut().zip(names) {
+                instance.builder.set_name(name);
+            }
+
+            if let Some(type_info) = self.type_infos.get_mut(&type_id) {
+                type_info.named = true;
+            }
+
+            if lossy {
+                self.report(DiagnosticKind::InvalidUtf8 {
+                    class_name: type_name.to_string(),
+                    property_name: prop_name,
+                })?;
             }
 
             return Ok(());
         }
 
-        let property = if let Some(property) = find_canonical_property(
+        let property = match find_canonical_property(
             self.deserializer.database,
             binary_type,
+            &type_name,
             class_descriptor,
             &prop_name,
         ) {
-            property
-        } else {
-            return Ok(());
+            Ok(property) => property,
+            Err(diagnostic) => return self.report(diagnostic),
         };
 
+        if property.name != prop_name.as_str()
+            && self.deserializer.is_property_skipped(&property.name)
+        {
+            log::trace!("Skipping PROP chunk {type_name}.{prop_name}, property was skipped");
+            return Ok(());
+        }
+
         let canonical_type = property.ty;
 
+        // Problems that come up while decoding values are reported once the
+        // whole chunk has been decoded.
+        let mut lossy = false;
+        let mut fallback_type = None;
+
         match binary_type {
             Type::String => match canonical_type {
                 VariantType::String => {
@@ -443,6 +612,7 @@ This may cause unexpected or broken behavior in your final results if you rely o
                         let value = match std::str::from_utf8(&binary_string) {
                             Ok(value) => value.to_owned(),
                             Err(_) => {
+                                lossy = true;
                                 log::warn!(
                             "Performing lossy string conversion on property {}.{} because it did not contain UTF-8.
 This may cause unexpected or broken behavior in your final results if you rely on this property being non UTF-8.",
@@ -494,6 +664,7 @@ This may cause unexpected or broken behavior in your final results if you rely o
                                 add_property(instance, &property, value.into());
                             }
                             Err(err) => {
+                                fallback_type = Some(VariantType::BinaryString);
                                 log::warn!(
                                     "Failed to parse Attributes on {} because {:?}; falling back to BinaryString.
 
@@ -517,6 +688,7 @@ rbx-dom may require changes to fully support this property. Please open an issue
                         match MaterialColors::decode(&buffer) {
                             Ok(value) => add_property(instance, &property, value.into()),
                             Err(err) => {
+                                fallback_type = Some(VariantType::BinaryString);
                                 log::warn!(
                                     "Failed to parse MaterialColors on {} because {:?}; falling back to BinaryString.
 
@@ -1466,39 +1638,288 @@ rbx-dom may require changes to fully support this property. Please open an issue
             },
         }
 
+        if lossy {
+            self.report(DiagnosticKind::InvalidUtf8 {
+                class_name: type_name.to_string(),
+                property_name: property.name.to_string(),
+            })?;
+        }
+
+        if let Some(actual) = fallback_type {
+            self.report(DiagnosticKind::TypeMismatch {
+                class_name: type_name.to_string(),
+                property_name: property.name.to_string(),
+                expected: canonical_type,
+                actual,
+            })?;
+        }
+
+        if let Some(PropertySerialization::Migrate(migration)) = property.migration {
+            let new_property_names = migration.new_property_names();
+
+            // Migrated values are only added to instances that don't already
+            // have them, so any instance without one has lost its value.
+            let failed = match new_property_names.first() {
+                Some(&new_property_name) => self.instances[range.clone()]
+                    .iter()
+                    .filter(|instance| !instance.builder.has_property(new_property_name))
+                    .count(),
+                None => 0,
+            };
+
+            if failed < range.len() {
+                self.report(DiagnosticKind::MigrationApplied {
+                    class_name: type_name.to_string(),
+                    property_name: property.name.to_string(),
+                    new_property_names: new_property_names
+                        .iter()
+                        .map(|&name| name.to_owned())
+                        .collect(),
+                })?;
+            }
+
+            if failed > 0 {
+                self.report(DiagnosticKind::MigrationFailed {
+                    class_name: type_name.to_string(),
+                    property_name: property.name.to_string(),
+                    count: failed,
+                })?;
+            }
+        }
+
         Ok(())
     }
 
     #[profiling::function]
-    pub(super) fn decode_prnt_chunk(&mut self, mut chunk: &[u8]) -> Result<(), InnerError> {
-        let version = chunk.read_u8()?;
+    pub(super) fn decode_prnt_chunk(&mut self, chunk: &[u8]) -> Result<(), InnerError> {
+        for (id, parent_ref) in read_prnt_chunk(chunk)? {
+            let child_key = match self.instance_key_by_ref.get(&id) {
+                Some(key) => key.key,
+                None => {
+                    log::warn!("PRNT chunk referred to undeclared referent {id}");
+                    continue;
+                }
+            };
 
-        if version != 0 {
-            return Err(InnerError::UnknownChunkVersion {
-                chunk_name: "PRNT",
-                version: version as u32,
-            });
+            if parent_ref == -1 {
+                self.root_instance_refs.push(id);
+            } else if let Some(parent_key) = self.instance_key_by_ref.get(&parent_ref) {
+                self.instances[parent_key.key].children.push(id);
+            } else {
+                log::warn!("PRNT chunk referred to undeclared referent {parent_ref}");
+                continue;
+            }
+
+            self.instances[child_key].parented = true;
         }
 
-        let number_objects = chunk.read_le_u32()?;
+        Ok(())
+    }
 
-        log::trace!("PRNT chunk ({number_objects} instances)");
+    /// Keeps a chunk that we don't know how to decode so that it can be
+    /// written back out later.
+    pub(super) fn preserve_unknown_chunk(&mut self, chunk: Chunk) -> Result<(), InnerError> {
+        let name = match str::from_utf8(&chunk.name) {
+            Ok(name) => name.to_owned(),
+            Err(_) => format!("{:?}", chunk.name),
+        };
+        log::info!("Unknown binary chunk name {name}");
 
-        let subjects = chunk.read_referent_array(number_objects as usize)?;
-        let parents = chunk.read_referent_array(number_objects as usize)?;
+        self.metadata.unknown_chunks.push(UnknownChunk {
+            name: chunk.name,
+            data: chunk.data,
+        });
 
-        for (id, parent_ref) in subjects.zip(parents) {
-            if parent_ref == -1 {
-                self.root_instance_refs.push(id);
-            } else {
-                let instance_key = self.instance_key_by_ref[&parent_ref].key;
-                self.instances[instance_key].children.push(id);
+        self.report(DiagnosticKind::UnknownChunk { name })
+    }
+
+    /// Records a problem with the chunk that was read last that we were able
+    /// to recover from, or returns it as an error if the deserializer is in
+    /// strict mode.
+    fn report(&mut self, kind: DiagnosticKind) -> Result<(), InnerError> {
+        let location = self
+            .chunks_read
+            .checked_sub(1)
+            .map(|chunk| DiagnosticLocation { chunk });
+
+        self.push_diagnostic(Diagnostic::new(kind, location))
+    }
+
+    /// Records a problem that we were able to recover from, or returns it as
+    /// an error if the deserializer is in strict mode and data was lost.
+    fn push_diagnostic(&mut self, diagnostic: Diagnostic) -> Result<(), InnerError> {
+        if self.deserializer.strict && diagnostic.kind().loses_data() {
+            return Err(InnerError::Strict { diagnostic });
+        }
+
+        log::debug!("{diagnostic}");
+        self.diagnostics.push(diagnostic);
+
+        Ok(())
+    }
+
+    /// Reports every instance that will end up in the tree without having
+    /// been given a name by the file.
+    ///
+    /// Names that the deserializer was told to skip are never decoded, so
+    /// nothing is reported in that case.
+    pub(super) fn report_unnamed_instances(&mut self) -> Result<(), InnerError> {
+        if self.deserializer.is_property_skipped("Name") {
+            return Ok(());
+        }
+
+        let retained = self.retained_instances();
+        let mut unnamed = Vec::new();
+
+        for type_info in self.type_infos.values().filter(|info| !info.named) {
+            for key in type_info.instances.clone() {
+                if let Some(retained) = &retained {
+                    if !retained[key] {
+                        continue;
+                    }
+                }
+
+                unnamed.push((key, type_info.type_name));
             }
         }
 
+        // Type IDs are visited in an arbitrary order, so we sort to keep
+        // diagnostics in the order instances appear in the file.
+        unnamed.sort_unstable_by_key(|&(key, _)| key);
+
+        for (key, class_name) in unnamed {
+            let referent = self.instances[key].builder.referent();
+            // Names come from the file as a whole, not from any one chunk.
+            let kind = DiagnosticKind::UnnamedInstance {
+                class_name: class_name.to_string(),
+                referent,
+            };
+            self.push_diagnostic(Diagnostic::new(kind, None))?;
+        }
+
         Ok(())
     }
 
+    /// Decodes an INST chunk like `decode_inst_chunk`, but checks for
+    /// duplicate type IDs and referents before adding anything so that a
+    /// rejected chunk doesn't leave some of its instances behind.
+    pub(super) fn salvage_inst_chunk(&mut self, chunk: &[u8]) -> Result<(), InnerError> {
+        let mut header = chunk;
+        let type_id = header.read_le_u32()?;
+        header.read_string()?;
+        header.read_u8()?;
+        let number_instances = header.read_le_u32()?;
+
+        if self.type_infos.contains_key(&type_id) {
+            return Err(InnerError::DuplicateInstChunk { type_id });
+        }
+
+        let mut seen = HashSet::new();
+        for referent in header.read_referent_array(number_instances as usize)? {
+            if self.instance_key_by_ref.contains_key(&referent) || !seen.insert(referent) {
+                return Err(InnerError::DuplicateReferent { referent });
+            }
+        }
+
+        self.decode_inst_chunk(chunk)
+    }
+
+    /// Decodes a PROP chunk like `decode_prop_chunk`, but if decoding fails,
+    /// every value that the chunk added is removed again so that a damaged
+    /// chunk doesn't leave some instances with its property and some without.
+    /// Values are only ever appended to an instance's builder, so removing them
+    /// brings back anything an earlier chunk set for the same property. Names
+    /// are the exception, which is why `decode_prop_chunk` stages them.
+    pub(super) fn salvage_prop_chunk(&mut self, chunk: &[u8]) -> Result<(), InnerError> {
+        let mut header = chunk;
+        let type_id = header.read_le_u32()?;
+
+        let range = match self.type_infos.get(&type_id) {
+            Some(type_info) => type_info.instances.clone(),
+            None => return self.decode_prop_chunk(chunk),
+        };
+
+        let property_counts: Vec<usize> = self.instances[range.clone()]
+            .iter()
+            .map(|instance| instance.builder.property_count())
+            .collect();
+
+        let result = self.decode_prop_chunk(chunk);
+
+        if result.is_err() {
+            for (instance, &count) in self.instances[range].iter_mut().zip(&property_counts) {
+                if instance.builder.property_count() > count {
+                    let kept: Vec<_> = instance.builder.drain_properties().take(count).collect();
+                    instance.builder.add_properties(kept);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Describes a chunk that couldn't be decoded, using the class and
+    /// property it's for when we can tell.
+    pub(super) fn lost_chunk(&self, name: [u8; 4], mut data: &[u8], reason: String) -> LostChunk {
+        let (class_name, property_name) = match &name {
+            b"INST" => (
+                data.read_le_u32()
+                    .ok()
+                    .and_then(|_| data.read_string().ok()),
+                None,
+            ),
+            b"PROP" => match data.read_le_u32() {
+                Ok(type_id) => (
+                    self.type_infos
+                        .get(&type_id)
+                        .map(|type_info| type_info.type_name.to_string()),
+                    data.read_string().ok(),
+                ),
+                Err(_) => (None, None),
+            },
+            _ => (None, None),
+        };
+
+        LostChunk {
+            name: String::from_utf8_lossy(&name).into_owned(),
+            class_name,
+            property_name,
+            reason,
+        }
+    }
+
+    /// Finds every instance that will be kept but was never given a parent,
+    /// which happens when a PRNT chunk is missing or damaged. They're put in a
+    /// `Salvaged` folder when the tree is built.
+    pub(super) fn collect_orphans(&mut self) -> Vec<Ref> {
+        let retained = self.retained_instances();
+
+        let mut orphans: Vec<(usize, i32)> = self
+            .instance_key_by_ref
+            .iter()
+            .filter(|(_, key)| !self.instances[key.key].parented)
+            .filter(|(_, key)| match &retained {
+                Some(retained) => retained[key.key],
+                None => true,
+            })
+            .map(|(&id, key)| (key.key, id))
+            .collect();
+
+        // Keep orphans in the order they appear in the file.
+        orphans.sort_unstable();
+
+        self.orphan_refs = orphans.iter().map(|&(_, id)| id).collect();
+        orphans
+            .iter()
+            .map(|&(key, _)| self.instances[key].builder.referent())
+            .collect()
+    }
+
+    /// Returns the diagnostics collected so far, leaving none behind.
+    pub(super) fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
     #[profiling::function]
     pub(super) fn decode_end_chunk(&mut self, _chunk: &[u8]) -> Result<(), InnerError> {
         log::trace!("END chunk");
@@ -1510,10 +1931,126 @@ rbx-dom may require changes to fully support this property. Please open an issue
         Ok(())
     }
 
+    /// Returns the metadata decoded from the file so far.
+    pub(super) fn metadata(&self) -> &FileMetadata {
+        &self.metadata
+    }
+
+    /// Returns the class name and the referents of every instance declared by
+    /// the INST chunk with the given type ID.
+    pub(super) fn declared_type(&self, type_id: u32) -> Option<(Ustr, Vec<Ref>)> {
+        let type_info = self.type_infos.get(&type_id)?;
+        let referents = self.instances[type_info.instances.clone()]
+            .iter()
+            .map(|instance| instance.builder.referent())
+            .collect();
+
+        Some((type_info.type_name, referents))
+    }
+
+    /// Moves every property value decoded so far for instances of the given
+    /// type out of their builders, grouped by property name in the order they
+    /// were first seen.
+    ///
+    /// When `names` is set, instance names are moved out as well and reported
+    /// as a `Name` property.
+    pub(super) fn take_property_columns(
+        &mut self,
+        type_id: u32,
+        names: bool,
+    ) -> Vec<(Ustr, Vec<(Ref, Variant)>)> {
+        let mut columns: Vec<(Ustr, Vec<(Ref, Variant)>)> = Vec::new();
+
+        let range = match self.type_infos.get(&type_id) {
+            Some(type_info) => type_info.instances.clone(),
+            None => return columns,
+        };
+
+        // Names are kept out of the builder's properties, so they have to be
+        // filtered here like `decode_prop_chunk` filters everything else.
+        if names && !self.deserializer.is_property_skipped("Name") {
+            let name_column = self.instances[range.clone()]
+                .iter_mut()
+                .map(|instance| {
+                    let name = instance.builder.name().to_owned();
+                    instance.builder.set_name(String::new());
+                    (instance.builder.referent(), Variant::String(name))
+                })
+                .collect();
+
+            columns.push(("Name".into(), name_column));
+        }
+
+        for instance in &mut self.instances[range] {
+            let referent = instance.builder.referent();
+
+            for (name, value) in instance.builder.drain_properties() {
+                match columns.iter_mut().find(|(column, _)| *column == name) {
+                    Some((_, values)) => values.push((referent, value)),
+                    None => columns.push((name, vec![(referent, value)])),
+                }
+            }
+        }
+
+        columns
+    }
+
+    /// Decodes a PRNT chunk into pairs of child and parent referents as they
+    /// will appear in the final tree. Instances without a parent in the file
+    /// are given a parent of `Ref::none()`.
+    pub(super) fn resolve_prnt_chunk(&self, chunk: &[u8]) -> Result<Vec<(Ref, Ref)>, InnerError> {
+        let resolve = |id: i32| {
+            self.instance_key_by_ref
+                .get(&id)
+                .map(|key| key.referent)
+                .unwrap_or_else(Ref::none)
+        };
+
+        Ok(read_prnt_chunk(chunk)?
+            .map(|(id, parent_ref)| (resolve(id), resolve(parent_ref)))
+            .collect())
+    }
+
+    /// If the deserializer only selected some classes, finds which instances
+    /// should end up in the tree: every selected instance and all of their
+    /// ancestors. The result is indexed the same way as `instances`.
+    ///
+    /// Returns `None` if every instance should be kept.
+    fn retained_instances(&self) -> Option<Vec<bool>> {
+        self.deserializer.only_classes.as_ref()?;
+
+        let mut parent_keys = vec![None; self.instances.len()];
+        for (key, instance) in self.instances.iter().enumerate() {
+            for child in &instance.children {
+                if let Some(child_key) = self.instance_key_by_ref.get(child) {
+                    parent_keys[child_key.key] = Some(key);
+                }
+            }
+        }
+
+        let mut retained = vec![false; self.instances.len()];
+        for type_info in self.type_infos.values().filter(|info| info.selected) {
+            for mut key in type_info.instances.clone() {
+                // Stop early once we reach an instance whose ancestors have
+                // already been visited.
+                while !retained[key] {
+                    retained[key] = true;
+
+                    match parent_keys[key] {
+                        Some(parent_key) => key = parent_key,
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        Some(retained)
+    }
+
     /// Combines together all the decoded information to build and emplace
     /// instances in our tree.
     #[profiling::function]
-    pub(super) fn finish(mut self) -> WeakDom {
+    pub(super) fn finish(mut self) -> (WeakDom, FileMetadata) {
         log::trace!("Constructing tree from deserialized data");
 
         // Track all the instances we need to construct. Order of construction
@@ -1529,6 +2066,38 @@ rbx-dom may require changes to fully support this property. Please open an issue
             instances_to_construct.push_back((referent, root_ref));
         }
 
+        // When only some classes were selected, everything else that isn't an
+        // ancestor of a selected instance is left out of the tree.
+        let retained = self.retained_instances();
+        let retained_refs: Option<HashSet<Ref>> = retained.as_ref().map(|retained| {
+            self.instances
+                .iter()
+                .zip(retained)
+                .filter(|(_, &retained)| retained)
+                .map(|(instance, _)| instance.builder.referent())
+                .collect()
+        });
+
+        if let Some(retained) = &retained {
+            let instance_key_by_ref = &self.instance_key_by_ref;
+            instances_to_construct.retain(|(referent, _)| {
+                instance_key_by_ref
+                    .get(referent)
+                    .is_some_and(|key| retained[key.key])
+            });
+        }
+
+        if !self.orphan_refs.is_empty() {
+            let folder = self.tree.insert(
+                root_ref,
+                InstanceBuilder::new("Folder").with_name("Salvaged"),
+            );
+
+            for &referent in &self.orphan_refs {
+                instances_to_construct.push_back((referent, folder));
+            }
+        }
+
         // Ensure we hit the global ustr lock array only once
         let empty_ustr = Ustr::default();
 
@@ -1536,19 +2105,38 @@ rbx-dom may require changes to fully support this property. Please open an issue
             // We need to drain the instances Vec in a random order without
             // disturbing the indices. Replace each instance with an impostor!
             // We guarantee this is done once by removing the key from `instance_key_by_ref`.
-            let instance_key = self.instance_key_by_ref.remove(&referent).unwrap().key;
+            //
+            // A file that gives an instance more than one parent could lead us
+            // here twice, so we only take the first.
+            let instance_key = match self.instance_key_by_ref.remove(&referent) {
+                Some(key) => key.key,
+                None => continue,
+            };
             let impostor = Instance {
                 builder: InstanceBuilder::new(empty_ustr),
                 children: Vec::new(),
+                parented: false,
             };
-            let instance = core::mem::replace(&mut self.instances[instance_key], impostor);
+            let mut instance = core::mem::replace(&mut self.instances[instance_key], impostor);
+
+            if let Some(retained_refs) = &retained_refs {
+                clear_dropped_refs(&mut instance.builder, retained_refs);
+            }
+
             let id = self.tree.insert(parent_ref, instance.builder);
 
             for referent in instance.children {
+                if let Some(retained) = &retained {
+                    match self.instance_key_by_ref.get(&referent) {
+                        Some(key) if retained[key.key] => {}
+                        _ => continue,
+                    }
+                }
+
                 instances_to_construct.push_back((referent, id));
             }
         }
 
-        self.tree
+        (self.tree, self.metadata)
     }
 }
@@ -56,6 +56,7 @@ rbx_binary::to_writer(output, &dom, &[dom.root_ref()])?;
 mod chunk;
 mod core;
 mod deserializer;
+mod metadata;
 mod serializer;
 mod types;
 
@@ -76,7 +77,11 @@ pub mod text_format {
 }
 
 pub use crate::{
-    deserializer::{Deserializer, Error as DecodeError},
+    deserializer::{
+        DeserializeEvent, DeserializeEvents, Deserializer, Diagnostic, DiagnosticKind,
+        DiagnosticLocation, Error as DecodeError, LostChunk, SalvageReport,
+    },
+    metadata::{FileMetadata, UnknownChunk},
     serializer::{CompressionType, Error as EncodeError, Serializer},
 };
 
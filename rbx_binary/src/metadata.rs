@@ -0,0 +1,58 @@
+use std::{fmt, str};
+
+/// Information contained in a binary model or place that isn't part of any
+/// instance.
+///
+/// `WeakDom` has no place to store this information, so it's returned
+/// alongside the DOM by
+/// [`Deserializer::deserialize_with_metadata`][crate::Deserializer::deserialize_with_metadata]
+/// and can be written back out by
+/// [`Serializer::serialize_with_metadata`][crate::Serializer::serialize_with_metadata].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileMetadata {
+    /// The entries of the file's `META` chunk, in the order they appeared.
+    /// Roblox uses these to store things like `ExplicitAutoJoints`.
+    pub entries: Vec<(String, String)>,
+
+    /// Chunks that rbx_binary doesn't understand, in the order they appeared.
+    ///
+    /// Where these chunks were in the file isn't kept. They're always written
+    /// after every chunk that rbx_binary understands, right before the `END`
+    /// chunk, so a chunk that came before an instance's properties in the
+    /// original file will come after them in the new one.
+    pub unknown_chunks: Vec<UnknownChunk>,
+}
+
+impl FileMetadata {
+    /// Returns the value of the `META` entry with the given key, if present.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A chunk from a binary model or place that rbx_binary doesn't know how to
+/// decode. Its contents are kept exactly as they were after decompression.
+#[derive(Clone, PartialEq, Eq)]
+pub struct UnknownChunk {
+    /// The four-byte name of the chunk, like `SIGN`.
+    pub name: [u8; 4],
+
+    /// The uncompressed contents of the chunk.
+    pub data: Vec<u8>,
+}
+
+impl fmt::Debug for UnknownChunk {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = formatter.debug_struct("UnknownChunk");
+
+        match str::from_utf8(&self.name) {
+            Ok(name) => debug.field("name", &name),
+            Err(_) => debug.field("name", &self.name),
+        };
+
+        debug.field("len", &self.data.len()).finish()
+    }
+}
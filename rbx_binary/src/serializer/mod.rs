@@ -6,6 +6,8 @@ use std::io::Write;
 use rbx_dom_weak::{types::Ref, WeakDom};
 use rbx_reflection::ReflectionDatabase;
 
+use crate::metadata::FileMetadata;
+
 use self::state::SerializerState;
 
 pub use self::error::Error;
@@ -37,9 +39,13 @@ pub use self::error::Error;
 /// By default, the Serializer uses LZ4 compression, mimicking Roblox. This can
 /// be changed via [`compression_type`][compression_type].
 ///
+/// Referents are numbered in the order instances are written unless
+/// [`stable_referents`][stable_referents] is enabled.
+///
 /// [ReflectionDatabase]: rbx_reflection::ReflectionDatabase
 /// [reflection_database]: Serializer#method.reflection_database
 /// [compression_type]: Serializer#method.compression_type
+/// [stable_referents]: Serializer#method.stable_referents
 //
 // future settings:
 // * recursive: bool = true
@@ -47,6 +53,8 @@ pub use self::error::Error;
 pub struct Serializer<'db> {
     database: &'db ReflectionDatabase<'db>,
     compression: CompressionType,
+    stable_referents: bool,
+    omit_defaults: bool,
 }
 
 impl<'db> Serializer<'db> {
@@ -55,6 +63,8 @@ impl<'db> Serializer<'db> {
         Serializer {
             database: rbx_reflection_database::get().unwrap(),
             compression: CompressionType::default(),
+            stable_referents: false,
+            omit_defaults: false,
         }
     }
 
@@ -73,21 +83,79 @@ impl<'db> Serializer<'db> {
         }
     }
 
+    /// Sets whether the serializer derives referents from each instance's
+    /// `UniqueId`, or from its path if it doesn't have one, instead of the
+    /// order instances are written in.
+    ///
+    /// With this enabled, instances keep the same referent when other
+    /// instances are added or removed, so Ref properties only change in the
+    /// output when what they point to does.
+    #[inline]
+    pub fn stable_referents(self, stable_referents: bool) -> Self {
+        Self {
+            stable_referents,
+            ..self
+        }
+    }
+
+    /// Sets whether the serializer skips properties that have their class's
+    /// default value from the reflection database.
+    ///
+    /// Properties are stored per class in binary files, so a property is only
+    /// skipped when every instance of the class has the default value for
+    /// it. Roblox uses the default value for properties missing from a file.
+    #[inline]
+    pub fn omit_defaults(self, omit_defaults: bool) -> Self {
+        Self {
+            omit_defaults,
+            ..self
+        }
+    }
+
     /// Serialize a Roblox binary model or place into the given stream using
     /// this serializer.
     pub fn serialize<W: Write>(&self, writer: W, dom: &WeakDom, refs: &[Ref]) -> Result<(), Error> {
         profiling::scope!("rbx_binary::seserialize");
 
+        self.serialize_inner(writer, dom, refs, &FileMetadata::default())
+    }
+
+    /// Serialize a Roblox binary model or place into the given stream using
+    /// this serializer, writing out the given [`FileMetadata`] as well.
+    ///
+    /// `META` entries are written in the order they're listed. Unknown chunks
+    /// are written just before the end of the file, after every chunk that
+    /// rbx_binary generates, using this serializer's compression type.
+    pub fn serialize_with_metadata<W: Write>(
+        &self,
+        writer: W,
+        dom: &WeakDom,
+        refs: &[Ref],
+        metadata: &FileMetadata,
+    ) -> Result<(), Error> {
+        profiling::scope!("rbx_binary::seserialize");
+
+        self.serialize_inner(writer, dom, refs, metadata)
+    }
+
+    fn serialize_inner<W: Write>(
+        &self,
+        writer: W,
+        dom: &WeakDom,
+        refs: &[Ref],
+        metadata: &FileMetadata,
+    ) -> Result<(), Error> {
         let mut serializer = SerializerState::new(self, dom, writer);
 
         serializer.add_instances(refs)?;
-        serializer.generate_referents();
+        serializer.generate_referents(refs);
         serializer.write_header()?;
-        serializer.serialize_metadata()?;
+        serializer.serialize_metadata(metadata)?;
         serializer.serialize_shared_strings()?;
         serializer.serialize_instances()?;
         serializer.serialize_properties()?;
         serializer.serialize_parents()?;
+        serializer.serialize_unknown_chunks(metadata)?;
         serializer.serialize_end()?;
 
         Ok(())
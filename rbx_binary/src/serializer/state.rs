@@ -27,6 +27,7 @@ use crate::{
         find_property_descriptors, PropertyDescriptors, RbxWriteExt, FILE_MAGIC_HEADER,
         FILE_SIGNATURE, FILE_VERSION,
     },
+    metadata::FileMetadata,
     types::Type,
     Serializer,
 };
@@ -205,6 +206,35 @@ impl<'dom> PropInfo<'dom> {
         self.values.push(value);
     }
 
+    /// Returns whether every instance has the class's default value for this
+    /// property in the reflection database. Roblox fills in the same values
+    /// when the property is missing from a file, so there's no need to write
+    /// it.
+    fn is_all_default(
+        &self,
+        class_descriptor: Option<&ClassDescriptor>,
+        database: &ReflectionDatabase,
+        desired_len: usize,
+    ) -> bool {
+        // Migrated values are only known once they're written.
+        if self.migration.is_some() {
+            return false;
+        }
+
+        let default_value = match class_descriptor
+            .and_then(|class| database.find_default_property(class, &self.canonical_name))
+        {
+            Some(default_value) => default_value,
+            None => return false,
+        };
+
+        // Instances without a value will be given `self.default_value`.
+        let missing_are_default =
+            self.values.len() >= desired_len || self.default_value == default_value;
+
+        missing_are_default && self.values.iter().all(|&value| value == default_value)
+    }
+
     /// Set the migration
     fn set_migration(&mut self, m_new: &'dom PropertyMigration) {
         if let Some(m_old) = self.migration {
@@ -689,10 +719,15 @@ impl<'dom, 'db: 'dom, W: Write> SerializerState<'dom, 'db, W> {
     /// Populate the map from rbx-dom's instance ID space to the IDs that we'll
     /// be serializing to the model.
     #[profiling::function]
-    pub fn generate_referents(&mut self) {
+    pub fn generate_referents(&mut self, roots: &[Ref]) {
         // This performs the same check as next_referent.try_into().unwrap() but only once.
         assert!(self.relevant_instances.len() <= i32::MAX as usize);
 
+        if self.serializer.stable_referents {
+            self.generate_stable_referents(roots);
+            return;
+        }
+
         self.id_to_referent.extend(
             self.relevant_instances
                 .iter()
@@ -703,6 +738,15 @@ impl<'dom, 'db: 'dom, W: Write> SerializerState<'dom, 'db, W> {
         log::debug!("Collected {} referents", self.id_to_referent.len());
     }
 
+    /// Derives each instance's referent from its `UniqueId` or its path with
+    /// [`WeakDom::stable_ids`].
+    fn generate_stable_referents(&mut self, roots: &[Ref]) {
+        self.id_to_referent
+            .extend(self.dom.stable_ids(roots, stable_referent));
+
+        log::debug!("Collected {} stable referents", self.id_to_referent.len());
+    }
+
     pub fn write_header(&mut self) -> Result<(), InnerError> {
         log::trace!("Writing header");
 
@@ -720,9 +764,25 @@ impl<'dom, 'db: 'dom, W: Write> SerializerState<'dom, 'db, W> {
     }
 
     /// Write out any metadata about this file, stored in a chunk named META.
-    pub fn serialize_metadata(&mut self) -> Result<(), InnerError> {
-        log::trace!("Writing metadata (currently no-op)");
-        // TODO: There is no concept of metadata in a dom yet.
+    #[profiling::function]
+    pub fn serialize_metadata(&mut self, metadata: &FileMetadata) -> Result<(), InnerError> {
+        log::trace!("Writing metadata");
+
+        if metadata.entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut chunk = ChunkBuilder::new(b"META", self.serializer.compression);
+
+        chunk.write_le_u32(metadata.entries.len() as u32)?;
+
+        for (key, value) in &metadata.entries {
+            chunk.write_string(key)?;
+            chunk.write_string(value)?;
+        }
+
+        chunk.dump(&mut self.output)?;
+
         Ok(())
     }
 
@@ -814,7 +874,17 @@ impl<'dom, 'db: 'dom, W: Write> SerializerState<'dom, 'db, W> {
         log::trace!("Writing properties");
 
         let name_ustr = rbx_dom_weak::ustr("Name");
+        let omit_defaults = self.serializer.omit_defaults;
+        let database = self.serializer.database;
+
         for (type_name, type_info) in &mut self.type_infos.values {
+            let class_descriptor = type_info.class_descriptor;
+            let instance_count = type_info.instances.len();
+            let is_written = |prop_info: &&mut PropInfo| {
+                !(omit_defaults
+                    && prop_info.is_all_default(class_descriptor, database, instance_count))
+            };
+
             // Sort logical properties by canonical name
             type_info
                 .properties
@@ -840,7 +910,7 @@ impl<'dom, 'db: 'dom, W: Write> SerializerState<'dom, 'db, W> {
                 }
             };
 
-            for prop_info in properties_before_name {
+            for prop_info in properties_before_name.iter_mut().filter(is_written) {
                 let mut chunk = ChunkBuilder::new(b"PROP", self.serializer.compression);
 
                 chunk.write_le_u32(type_info.type_id)?;
@@ -875,7 +945,7 @@ impl<'dom, 'db: 'dom, W: Write> SerializerState<'dom, 'db, W> {
                 chunk.dump(&mut self.output)?;
             }
 
-            for prop_info in properties_after_name {
+            for prop_info in properties_after_name.iter_mut().filter(is_written) {
                 let mut chunk = ChunkBuilder::new(b"PROP", self.serializer.compression);
 
                 chunk.write_le_u32(type_info.type_id)?;
@@ -1667,6 +1737,21 @@ impl<'dom, 'db: 'dom, W: Write> SerializerState<'dom, 'db, W> {
         Ok(())
     }
 
+    /// Write out any chunks that were read from a file but not understood, in
+    /// the same order that they were read.
+    #[profiling::function]
+    pub fn serialize_unknown_chunks(&mut self, metadata: &FileMetadata) -> Result<(), InnerError> {
+        for unknown_chunk in &metadata.unknown_chunks {
+            log::trace!("Writing unknown chunk {:?}", unknown_chunk);
+
+            let mut chunk = ChunkBuilder::new(&unknown_chunk.name, self.serializer.compression);
+            chunk.write_all(&unknown_chunk.data)?;
+            chunk.dump(&mut self.output)?;
+        }
+
+        Ok(())
+    }
+
     /// Write the fixed, uncompressed end chunk used to verify that the file
     /// hasn't been truncated mistakenly. This chunk is named END\0, with a zero
     /// byte at the end.
@@ -1681,6 +1766,21 @@ impl<'dom, 'db: 'dom, W: Write> SerializerState<'dom, 'db, W> {
         Ok(())
     }
 }
+
+/// Hashes a key with 64-bit FNV-1a, which never changes between versions, and
+/// folds it into a non-negative referent. Negative referents are avoided
+/// because `-1` means a Ref property is empty.
+fn stable_referent(key: &str) -> i32 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x00000100000001b3;
+
+    let hash = key.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    });
+
+    ((hash ^ (hash >> 32)) & 0x7fff_ffff) as i32
+}
+
 fn fallback_default_value(rbx_type: VariantType) -> Option<&'static Variant> {
     use std::sync::LazyLock;
     static DEFAULT_STRING: Variant = Variant::String(String::new());
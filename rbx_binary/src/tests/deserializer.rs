@@ -0,0 +1,469 @@
+use std::convert::TryInto;
+
+use rbx_dom_weak::{
+    types::{BinaryString, Ref, UDim, Variant},
+    InstanceBuilder, Ustr, WeakDom,
+};
+use rbx_reflection::ReflectionDatabase;
+
+use crate::{
+    to_writer, CompressionType, DeserializeEvent, Deserializer, Diagnostic, DiagnosticKind,
+    FileMetadata, Serializer, UnknownChunk,
+};
+
+fn encode(tree: &WeakDom) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, tree, tree.root().children()).expect("failed to encode model");
+    buffer
+}
+
+fn find<'a>(dom: &'a WeakDom, name: &str) -> Option<&'a rbx_dom_weak::Instance> {
+    dom.descendants().find(|instance| instance.name == name)
+}
+
+fn sample_tree() -> WeakDom {
+    let part = InstanceBuilder::new("Part")
+        .with_name("Part")
+        .with_property("Anchored", true);
+    let part_ref = part.referent();
+
+    WeakDom::new(
+        InstanceBuilder::new("DataModel").with_child(
+            InstanceBuilder::new("Folder")
+                .with_name("Root")
+                .with_children([
+                    InstanceBuilder::new("Model")
+                        .with_name("Map")
+                        .with_children([
+                            part,
+                            InstanceBuilder::new("StringValue")
+                                .with_name("Label")
+                                .with_property("Value", "Hello"),
+                        ]),
+                    InstanceBuilder::new("MeshPart").with_name("Mesh"),
+                    InstanceBuilder::new("ObjectValue")
+                        .with_name("Pointer")
+                        .with_property("Value", part_ref),
+                ]),
+        ),
+    )
+}
+
+/// Only selected classes and their ancestors should be decoded.
+#[test]
+fn only_classes() {
+    let buffer = encode(&sample_tree());
+
+    let dom = Deserializer::new()
+        .only_classes(["StringValue"])
+        .deserialize(buffer.as_slice())
+        .unwrap();
+
+    let label = find(&dom, "Label").expect("StringValue should be kept");
+    assert_eq!(
+        label.properties.get(&"Value".into()),
+        Some(&Variant::String("Hello".to_owned()))
+    );
+
+    let map = find(&dom, "Map").expect("ancestors should be kept");
+    assert_eq!(map.parent(), find(&dom, "Root").unwrap().referent());
+    assert!(map.properties.is_empty());
+
+    assert!(find(&dom, "Part").is_none());
+    assert!(find(&dom, "Mesh").is_none());
+    assert!(find(&dom, "Pointer").is_none());
+}
+
+/// Class filters should include classes that inherit from selected classes.
+#[test]
+fn only_classes_superclass() {
+    let buffer = encode(&sample_tree());
+
+    let dom = Deserializer::new()
+        .only_classes(["BasePart"])
+        .deserialize(buffer.as_slice())
+        .unwrap();
+
+    assert!(find(&dom, "Part").is_some());
+    assert!(find(&dom, "Mesh").is_some());
+    assert!(find(&dom, "Label").is_none());
+}
+
+/// Refs to instances that were filtered out should be cleared instead of
+/// left dangling.
+#[test]
+fn only_classes_clears_refs() {
+    let buffer = encode(&sample_tree());
+
+    let dom = Deserializer::new()
+        .only_classes(["ObjectValue"])
+        .deserialize(buffer.as_slice())
+        .unwrap();
+
+    let pointer = find(&dom, "Pointer").unwrap();
+    assert_eq!(
+        pointer.properties.get(&"Value".into()),
+        Some(&Variant::Ref(Ref::none()))
+    );
+}
+
+/// Skipped properties should not appear on any instance.
+#[test]
+fn skip_properties() {
+    let buffer = encode(&sample_tree());
+
+    let dom = Deserializer::new()
+        .skip_properties(["Value"])
+        .deserialize(buffer.as_slice())
+        .unwrap();
+
+    assert!(!find(&dom, "Label")
+        .unwrap()
+        .properties
+        .contains_key(&"Value".into()));
+    assert!(!find(&dom, "Pointer")
+        .unwrap()
+        .properties
+        .contains_key(&"Value".into()));
+    assert!(find(&dom, "Part")
+        .unwrap()
+        .properties
+        .contains_key(&"Anchored".into()));
+
+    // Names are decoded separately from other properties, but should be
+    // skipped the same way when reading events.
+    let skipped: Vec<_> = Deserializer::new()
+        .skip_properties(["Name", "Value"])
+        .events(buffer.as_slice())
+        .unwrap()
+        .map(Result::unwrap)
+        .filter_map(|event| match event {
+            DeserializeEvent::PropertyDecoded { name, .. } => Some(name),
+            _ => None,
+        })
+        .filter(|name| name == "Name" || name == "Value")
+        .collect();
+    assert_eq!(skipped, Vec::<Ustr>::new());
+}
+
+/// META entries and chunks we don't understand should survive being read and
+/// written back out.
+#[test]
+fn metadata_round_trip() {
+    let tree = sample_tree();
+    let metadata = FileMetadata {
+        entries: vec![
+            ("ExplicitAutoJoints".to_owned(), "true".to_owned()),
+            ("SomethingNew".to_owned(), "1".to_owned()),
+        ],
+        unknown_chunks: vec![UnknownChunk {
+            name: *b"SIGN",
+            data: vec![0, 1, 2, 3, 255],
+        }],
+    };
+
+    let mut buffer = Vec::new();
+    Serializer::new()
+        .serialize_with_metadata(&mut buffer, &tree, tree.root().children(), &metadata)
+        .unwrap();
+
+    let (dom, decoded_metadata) = Deserializer::new()
+        .deserialize_with_metadata(buffer.as_slice())
+        .unwrap();
+    assert_eq!(decoded_metadata, metadata);
+    assert_eq!(decoded_metadata.get("ExplicitAutoJoints"), Some("true"));
+
+    let mut second_buffer = Vec::new();
+    Serializer::new()
+        .serialize_with_metadata(
+            &mut second_buffer,
+            &dom,
+            dom.root().children(),
+            &decoded_metadata,
+        )
+        .unwrap();
+    assert_eq!(buffer, second_buffer);
+}
+
+/// Problems that were recovered from should be reported, and should become
+/// errors in strict mode.
+#[test]
+fn diagnostics() {
+    let (_, diagnostics) = Deserializer::new()
+        .deserialize_with_diagnostics(encode(&sample_tree()).as_slice())
+        .unwrap();
+    assert_eq!(diagnostics, Vec::new());
+
+    let tree = WeakDom::new(InstanceBuilder::new("DataModel").with_child(
+        InstanceBuilder::new("UICorner").with_property("CornerRadius", UDim::new(0.5, 12)),
+    ));
+    let metadata = FileMetadata {
+        entries: Vec::new(),
+        unknown_chunks: vec![UnknownChunk {
+            name: *b"SIGN",
+            data: vec![0, 1, 2, 3],
+        }],
+    };
+
+    // Without a reflection database, CornerRadius is written as-is instead of
+    // being migrated.
+    let database = ReflectionDatabase::new();
+    let mut buffer = Vec::new();
+    Serializer::new()
+        .reflection_database(&database)
+        .serialize_with_metadata(&mut buffer, &tree, tree.root().children(), &metadata)
+        .unwrap();
+
+    let (dom, diagnostics) = Deserializer::new()
+        .deserialize_with_diagnostics(buffer.as_slice())
+        .unwrap();
+    assert_eq!(dom.descendants().count(), 2);
+
+    let kinds: Vec<_> = diagnostics.iter().map(Diagnostic::kind).cloned().collect();
+    assert_eq!(
+        kinds,
+        vec![
+            DiagnosticKind::MigrationApplied {
+                class_name: "UICorner".to_owned(),
+                property_name: "CornerRadius".to_owned(),
+                new_property_names: vec![
+                    "BottomLeftRadius".to_owned(),
+                    "BottomRightRadius".to_owned(),
+                    "TopLeftRadius".to_owned(),
+                    "TopRightRadius".to_owned(),
+                ],
+            },
+            DiagnosticKind::UnknownChunk {
+                name: "SIGN".to_owned(),
+            },
+        ]
+    );
+
+    // Both were found in a chunk, and the SIGN chunk is written after the
+    // chunk with the property in it.
+    let chunks: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.location().unwrap().chunk)
+        .collect();
+    assert!(chunks[0] < chunks[1]);
+    assert!(diagnostics[1]
+        .to_string()
+        .starts_with(&format!("chunk {}: ", chunks[1])));
+
+    // Migrations and unknown chunks don't lose anything, so strict mode
+    // still accepts them.
+    let (_, strict_diagnostics) = Deserializer::new()
+        .strict(true)
+        .deserialize_with_diagnostics(buffer.as_slice())
+        .unwrap();
+    assert_eq!(strict_diagnostics, diagnostics);
+}
+
+/// Strict mode should fail when a value can't be decoded as-is.
+#[test]
+fn strict_data_loss() {
+    let tree = WeakDom::new(
+        InstanceBuilder::new("DataModel").with_child(
+            InstanceBuilder::new("Folder")
+                .with_property("AttributesSerialize", BinaryString::from(vec![0xff, 0xff])),
+        ),
+    );
+
+    // Without a reflection database, these bytes are written even though
+    // they aren't valid attributes.
+    let database = ReflectionDatabase::new();
+    let mut buffer = Vec::new();
+    Serializer::new()
+        .reflection_database(&database)
+        .serialize(&mut buffer, &tree, tree.root().children())
+        .unwrap();
+
+    let (_, diagnostics) = Deserializer::new()
+        .deserialize_with_diagnostics(buffer.as_slice())
+        .unwrap();
+    assert!(matches!(
+        diagnostics[0].kind(),
+        DiagnosticKind::TypeMismatch { .. }
+    ));
+    assert!(diagnostics[0].kind().loses_data());
+
+    let error = Deserializer::new()
+        .strict(true)
+        .deserialize(buffer.as_slice())
+        .unwrap_err();
+    assert!(error.to_string().contains("Folder.Attributes"));
+}
+
+/// Skipping names shouldn't make every instance look unnamed, even in strict
+/// mode.
+#[test]
+fn skipped_names_are_not_diagnostics() {
+    let (dom, diagnostics) = Deserializer::new()
+        .skip_properties(["Name"])
+        .strict(true)
+        .deserialize_with_diagnostics(encode(&sample_tree()).as_slice())
+        .unwrap();
+
+    assert!(dom.descendants().count() > 1);
+    assert_eq!(diagnostics, Vec::new());
+}
+
+/// Finds where the first chunk with the given name starts.
+fn chunk_offset(buffer: &[u8], name: &[u8; 4]) -> usize {
+    buffer
+        .windows(4)
+        .position(|window| window == name)
+        .expect("chunk should be in the file")
+}
+
+/// Files that were cut off should keep everything that was read, with
+/// instances that lost their parent put in a folder.
+#[test]
+fn salvage_truncated() {
+    let buffer = encode(&sample_tree());
+    let truncated = &buffer[..chunk_offset(&buffer, b"PRNT") + 10];
+
+    assert!(Deserializer::new().deserialize(truncated).is_err());
+
+    let (dom, report) = Deserializer::new().salvage(truncated).unwrap();
+    assert!(report.truncated);
+    assert!(report.lost_chunks.is_empty());
+    assert_eq!(report.orphans.len(), 6);
+    assert!(!report.is_complete());
+
+    let salvaged = dom.get_by_ref(dom.root().children()[0]).unwrap();
+    assert_eq!(salvaged.name, "Salvaged");
+    assert_eq!(salvaged.children(), report.orphans.as_slice());
+
+    let label = find(&dom, "Label").unwrap();
+    assert_eq!(label.parent(), salvaged.referent());
+    assert_eq!(
+        label.properties.get(&"Value".into()),
+        Some(&Variant::String("Hello".to_owned()))
+    );
+
+    let pointer = find(&dom, "Pointer").unwrap();
+    assert_eq!(
+        pointer.properties.get(&"Value".into()),
+        Some(&Variant::Ref(find(&dom, "Part").unwrap().referent()))
+    );
+}
+
+/// Chunks that can't be decompressed should be skipped without losing the
+/// rest of the file.
+#[test]
+fn salvage_corrupt_chunk() {
+    let mut buffer = encode(&sample_tree());
+    let prop = chunk_offset(&buffer, b"PROP");
+    let compressed_len = u32::from_le_bytes(buffer[prop + 4..prop + 8].try_into().unwrap());
+    assert_ne!(compressed_len, 0, "PROP chunk should be compressed");
+
+    for byte in &mut buffer[prop + 16..prop + 16 + compressed_len as usize] {
+        *byte = 0xff;
+    }
+
+    assert!(Deserializer::new().deserialize(buffer.as_slice()).is_err());
+
+    let (dom, report) = Deserializer::new().salvage(buffer.as_slice()).unwrap();
+    assert!(!report.truncated);
+    assert!(report.orphans.is_empty());
+    assert_eq!(report.lost_chunks.len(), 1);
+    assert_eq!(report.lost_chunks[0].name, "PROP");
+
+    // Every instance and every other property is still there, in the same
+    // places.
+    assert_eq!(dom.descendants().count(), 7);
+    let label = dom
+        .descendants()
+        .find(|instance| instance.class == "StringValue")
+        .unwrap();
+    assert_eq!(
+        label.properties.get(&"Value".into()),
+        Some(&Variant::String("Hello".to_owned()))
+    );
+    assert_eq!(dom.get_by_ref(label.parent()).unwrap().class, "Model");
+
+    let (_, intact) = Deserializer::new()
+        .salvage(encode(&sample_tree()).as_slice())
+        .unwrap();
+    assert!(intact.is_complete());
+}
+
+/// INST chunks that repeat a type ID or referent should be dropped without
+/// adding any of their instances.
+#[test]
+fn salvage_duplicate_inst_chunk() {
+    let mut buffer = encode(&sample_tree());
+    let inst = chunk_offset(&buffer, b"INST");
+    let compressed_len = u32::from_le_bytes(buffer[inst + 4..inst + 8].try_into().unwrap());
+    let len = u32::from_le_bytes(buffer[inst + 8..inst + 12].try_into().unwrap());
+    let data_len = if compressed_len == 0 {
+        len
+    } else {
+        compressed_len
+    };
+
+    let chunk = buffer[inst..inst + 16 + data_len as usize].to_vec();
+    buffer.splice(inst..inst, chunk);
+
+    let (dom, report) = Deserializer::new().salvage(buffer.as_slice()).unwrap();
+    assert_eq!(report.lost_chunks.len(), 1);
+    assert_eq!(report.lost_chunks[0].name, "INST");
+    assert!(report.orphans.is_empty());
+
+    let (intact, _) = Deserializer::new()
+        .salvage(encode(&sample_tree()).as_slice())
+        .unwrap();
+    let names = |dom: &WeakDom| -> Vec<String> {
+        dom.descendants()
+            .map(|instance| instance.name.clone())
+            .collect()
+    };
+    assert_eq!(names(&dom), names(&intact));
+    assert_eq!(
+        find(&dom, "Label").unwrap().properties,
+        find(&intact, "Label").unwrap().properties
+    );
+}
+
+/// A PROP chunk that repeats an earlier one but is cut off shouldn't change
+/// any of the values the earlier one set.
+#[test]
+fn salvage_truncated_duplicate_prop_chunk() {
+    let tree = sample_tree();
+    let mut buffer = Vec::new();
+    Serializer::new()
+        .compression_type(CompressionType::None)
+        .serialize(&mut buffer, &tree, tree.root().children())
+        .unwrap();
+
+    // Without compression, each chunk is its header followed by its data.
+    let mut damaged = buffer[..32].to_vec();
+    let mut offset = 32;
+    while offset < buffer.len() {
+        let len = u32::from_le_bytes(buffer[offset + 8..offset + 12].try_into().unwrap());
+        let chunk = &buffer[offset..offset + 16 + len as usize];
+        damaged.extend_from_slice(chunk);
+
+        let data = &chunk[16..];
+        let is_label = |value: &[u8]| data.windows(value.len()).any(|window| window == value);
+        if &chunk[..4] == b"PROP" && (is_label(b"Label") || is_label(b"Hello")) {
+            // Cut the copy off inside the length prefix of its only value.
+            let cut = "Hello".len() as u32 + 2;
+            let mut copy = chunk[..chunk.len() - cut as usize].to_vec();
+            copy[8..12].copy_from_slice(&(len - cut).to_le_bytes());
+            damaged.extend_from_slice(&copy);
+        }
+
+        offset += chunk.len();
+    }
+
+    let (dom, report) = Deserializer::new().salvage(damaged.as_slice()).unwrap();
+    assert_eq!(report.lost_chunks.len(), 2);
+
+    let label = find(&dom, "Label").expect("the name should be kept");
+    assert_eq!(
+        label.properties.get(&"Value".into()),
+        Some(&Variant::String("Hello".to_owned()))
+    );
+}
@@ -0,0 +1,158 @@
+use rbx_dom_weak::{
+    types::{Ref, Variant},
+    InstanceBuilder, WeakDom,
+};
+
+use crate::{to_writer, DeserializeEvent, Deserializer, FileMetadata, Serializer, UnknownChunk};
+
+fn encode(tree: &WeakDom) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, tree, tree.root().children()).expect("failed to encode model");
+    buffer
+}
+
+/// Reading a file as events should report the same information as
+/// deserializing it into a tree.
+#[test]
+fn events_match_tree() {
+    let target = InstanceBuilder::new("Part").with_name("Target");
+    let target_ref = target.referent();
+
+    let tree = WeakDom::new(
+        InstanceBuilder::new("DataModel").with_child(
+            InstanceBuilder::new("Folder")
+                .with_name("Root")
+                .with_child(target)
+                .with_child(
+                    InstanceBuilder::new("ObjectValue")
+                        .with_name("Pointer")
+                        .with_property("Value", target_ref),
+                )
+                .with_child(InstanceBuilder::new("StringValue").with_property("Value", "Hello")),
+        ),
+    );
+    let buffer = encode(&tree);
+
+    let decoded = Deserializer::new().deserialize(buffer.as_slice()).unwrap();
+    let events: Vec<_> = Deserializer::new()
+        .events(buffer.as_slice())
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    let created: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            DeserializeEvent::InstanceCreated { referent, class } => Some((*referent, *class)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(created.len(), 4);
+
+    let declared: usize = events
+        .iter()
+        .filter_map(|event| match event {
+            DeserializeEvent::TypeDeclared { count, .. } => Some(count),
+            _ => None,
+        })
+        .sum();
+    assert_eq!(declared, 4);
+
+    let name_of = |referent: Ref| {
+        events.iter().find_map(|event| match event {
+            DeserializeEvent::PropertyDecoded { name, values, .. } if name == "Name" => values
+                .iter()
+                .find(|(r, _)| *r == referent)
+                .map(|(_, value)| value.clone()),
+            _ => None,
+        })
+    };
+    let ref_by_name = |wanted: &str| {
+        created
+            .iter()
+            .map(|(referent, _)| *referent)
+            .find(|referent| name_of(*referent) == Some(Variant::String(wanted.to_owned())))
+            .unwrap()
+    };
+
+    let root = ref_by_name("Root");
+    let target = ref_by_name("Target");
+    let pointer = ref_by_name("Pointer");
+
+    let parents: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            DeserializeEvent::ParentSet { child, parent } => Some((*child, *parent)),
+            _ => None,
+        })
+        .collect();
+    assert!(parents.contains(&(root, Ref::none())));
+    assert!(parents.contains(&(target, root)));
+    assert!(parents.contains(&(pointer, root)));
+
+    let pointer_value = events.iter().find_map(|event| match event {
+        DeserializeEvent::PropertyDecoded {
+            class,
+            name,
+            values,
+        } if class == "ObjectValue" && name == "Value" => Some(values.clone()),
+        _ => None,
+    });
+    assert_eq!(pointer_value, Some(vec![(pointer, Variant::Ref(target))]));
+
+    let string_value = events.iter().find_map(|event| match event {
+        DeserializeEvent::PropertyDecoded {
+            class,
+            name,
+            values,
+        } if class == "StringValue" && name == "Value" => Some(values[0].1.clone()),
+        _ => None,
+    });
+    let decoded_string_value = decoded
+        .descendants()
+        .find(|instance| instance.class == "StringValue")
+        .unwrap()
+        .properties
+        .get(&"Value".into())
+        .cloned();
+    assert_eq!(string_value, decoded_string_value);
+}
+
+/// A truncated file should produce an error and then stop.
+#[test]
+fn events_truncated() {
+    let tree =
+        WeakDom::new(InstanceBuilder::new("DataModel").with_child(InstanceBuilder::new("Folder")));
+    let buffer = encode(&tree);
+    let truncated = &buffer[..buffer.len() - 20];
+
+    let deserializer = Deserializer::new();
+    let mut events = deserializer.events(truncated).unwrap();
+    assert!(events.by_ref().any(|event| event.is_err()));
+    assert!(events.next().is_none());
+}
+
+/// META entries and unknown chunks should be kept when reading events, the
+/// same as when deserializing with metadata.
+#[test]
+fn events_metadata() {
+    let tree =
+        WeakDom::new(InstanceBuilder::new("DataModel").with_child(InstanceBuilder::new("Folder")));
+    let metadata = FileMetadata {
+        entries: vec![("ExplicitAutoJoints".to_owned(), "true".to_owned())],
+        unknown_chunks: vec![UnknownChunk {
+            name: *b"SIGN",
+            data: vec![0, 1, 2, 3],
+        }],
+    };
+
+    let mut buffer = Vec::new();
+    Serializer::new()
+        .serialize_with_metadata(&mut buffer, &tree, tree.root().children(), &metadata)
+        .unwrap();
+
+    let deserializer = Deserializer::new();
+    let mut events = deserializer.events(buffer.as_slice()).unwrap();
+    assert!(events.by_ref().all(|event| event.is_ok()));
+    assert_eq!(events.metadata(), &metadata);
+}
@@ -1,4 +1,6 @@
 mod core_read_write;
+mod deserializer;
+mod events;
 mod models;
 mod places;
 mod serializer;
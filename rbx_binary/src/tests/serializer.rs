@@ -1,12 +1,15 @@
 use rbx_dom_weak::{
     types::{
         Attributes, BrickColor, CFrame, Color3, Color3uint8, Enum, Font, Ref, Region3,
-        SharedString, UDim, Variant, Vector3,
+        SharedString, UDim, UniqueId, Variant, Vector3,
     },
     InstanceBuilder, WeakDom,
 };
 
-use crate::{text_deserializer::DecodedModel, to_writer, Deserializer, Serializer};
+use crate::{
+    text_deserializer::{DecodedChunk, DecodedModel},
+    to_writer, Deserializer, Serializer,
+};
 
 /// A basic test to make sure we can serialize the simplest instance: a Folder.
 #[test]
@@ -329,3 +332,111 @@ fn does_not_serialize() {
     let decoded = DecodedModel::from_reader(buf.as_slice());
     insta::assert_yaml_snapshot!(decoded);
 }
+
+/// Ensures that stable referents don't change when other instances are added
+/// and that Ref properties still point at the right instance.
+#[test]
+fn stable_referents() {
+    fn dom(extra: bool) -> WeakDom {
+        let target = InstanceBuilder::new("Model").with_name("Target");
+        let value = InstanceBuilder::new("ObjectValue").with_property("Value", target.referent());
+
+        let mut children = Vec::new();
+        if extra {
+            children.push(InstanceBuilder::new("Part").with_name("First"));
+        }
+        children.push(target);
+        children.push(
+            InstanceBuilder::new("Configuration").with_property("UniqueId", UniqueId::new(1, 2, 3)),
+        );
+        children.push(value);
+
+        WeakDom::new(InstanceBuilder::new("Folder").with_children(children))
+    }
+
+    fn referents(dom: &WeakDom) -> Vec<(String, Vec<i32>)> {
+        let mut buffer = Vec::new();
+        Serializer::new()
+            .stable_referents(true)
+            .serialize(&mut buffer, dom, &[dom.root_ref()])
+            .expect("failed to encode model");
+
+        let decoded = Deserializer::new()
+            .deserialize(buffer.as_slice())
+            .expect("failed to decode model");
+        let value = decoded
+            .descendants()
+            .find(|instance| instance.class == "ObjectValue")
+            .unwrap();
+        let target = match value.properties.get(&"Value".into()) {
+            Some(Variant::Ref(target)) => decoded.get_by_ref(*target).unwrap(),
+            other => panic!("unexpected value {:?}", other),
+        };
+        assert_eq!(target.class, "Model");
+
+        DecodedModel::from_reader(buffer.as_slice())
+            .chunks
+            .into_iter()
+            .filter_map(|chunk| match chunk {
+                DecodedChunk::Inst {
+                    type_name,
+                    referents,
+                    ..
+                } => Some((type_name, referents)),
+                _ => None,
+            })
+            .filter(|(type_name, _)| type_name != "Part")
+            .collect()
+    }
+
+    let before = referents(&dom(false));
+    assert_eq!(before, referents(&dom(false)));
+    assert_eq!(before, referents(&dom(true)));
+    assert!(before
+        .iter()
+        .flat_map(|(_, referents)| referents)
+        .all(|referent| *referent >= 0));
+}
+
+/// Ensures that properties are only skipped when every instance of a class
+/// has the default value for them.
+#[test]
+fn omit_defaults() {
+    let tree = WeakDom::new(
+        InstanceBuilder::new("Folder").with_children([
+            InstanceBuilder::new("Part")
+                .with_property("Anchored", false)
+                .with_property("CastShadow", true)
+                .with_property("Transparency", 0.0f32),
+            InstanceBuilder::new("Part")
+                .with_property("Anchored", false)
+                .with_property("CastShadow", true)
+                .with_property("Transparency", 0.5f32),
+        ]),
+    );
+
+    let prop_names = |serializer: Serializer| {
+        let mut buffer = Vec::new();
+        serializer
+            .serialize(&mut buffer, &tree, &[tree.root_ref()])
+            .expect("failed to encode model");
+
+        DecodedModel::from_reader(buffer.as_slice())
+            .chunks
+            .into_iter()
+            .filter_map(|chunk| match chunk {
+                DecodedChunk::Prop { prop_name, .. } => Some(prop_name),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let full = prop_names(Serializer::new());
+    assert!(full.iter().any(|name| name == "Anchored"));
+
+    let minimal = prop_names(Serializer::new().omit_defaults(true));
+    assert!(!minimal.iter().any(|name| name == "Anchored"));
+    assert!(!minimal.iter().any(|name| name == "CastShadow"));
+    assert!(minimal.iter().any(|name| name == "Transparency"));
+    assert!(minimal.iter().any(|name| name == "Name"));
+}
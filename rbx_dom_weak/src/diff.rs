@@ -0,0 +1,630 @@
+use std::collections::VecDeque;
+
+use ahash::{AHashMap, AHashSet};
+use rbx_types::{ContentType, Ref, UniqueId, Variant};
+use ustr::Ustr;
+
+use crate::{Instance, WeakDom};
+
+/// Describes how instances in two [`WeakDom`]s are paired up by [`Diff::new`].
+///
+/// The root instances of both DOMs are always paired with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchStrategy {
+    /// Instances are paired if they have the same `UniqueId` property.
+    /// Instances without a `UniqueId` are never paired. Instances that share a
+    /// `UniqueId`, like ones copied in Studio, are paired in the order they
+    /// appear in each DOM.
+    UniqueId,
+
+    /// Instances are paired if they have the same referent. This is useful for
+    /// comparing a DOM against an edited copy of itself.
+    Referent,
+
+    /// Instances are paired if they have the same path of names from the root.
+    /// Siblings with the same name are paired in the order they appear.
+    NamePath,
+}
+
+/// A single difference between two [`WeakDom`]s, found by [`Diff::new`].
+///
+/// Referents named `old` belong to the old DOM and referents named `new`
+/// belong to the new DOM.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Change {
+    /// An instance exists only in the new DOM.
+    Added {
+        /// The added instance.
+        new: Ref,
+        /// The parent of the added instance in the new DOM.
+        new_parent: Ref,
+    },
+
+    /// An instance exists only in the old DOM.
+    Removed {
+        /// The removed instance.
+        old: Ref,
+        /// The parent of the removed instance in the old DOM.
+        old_parent: Ref,
+    },
+
+    /// A paired instance has a different parent in the new DOM.
+    Moved {
+        /// The instance in the old DOM.
+        old: Ref,
+        /// The instance in the new DOM.
+        new: Ref,
+        /// The parent of the instance in the old DOM.
+        old_parent: Ref,
+        /// The parent of the instance in the new DOM.
+        new_parent: Ref,
+    },
+
+    /// A paired instance has a different name in the new DOM.
+    Renamed {
+        /// The instance in the old DOM.
+        old: Ref,
+        /// The instance in the new DOM.
+        new: Ref,
+        /// The name of the instance in the old DOM.
+        old_name: String,
+        /// The name of the instance in the new DOM.
+        new_name: String,
+    },
+
+    /// A paired instance has a different class in the new DOM.
+    ClassChanged {
+        /// The instance in the old DOM.
+        old: Ref,
+        /// The instance in the new DOM.
+        new: Ref,
+        /// The class of the instance in the old DOM.
+        old_class: Ustr,
+        /// The class of the instance in the new DOM.
+        new_class: Ustr,
+    },
+
+    /// A property on a paired instance was added, removed, or changed.
+    PropertyChanged {
+        /// The instance in the old DOM.
+        old: Ref,
+        /// The instance in the new DOM.
+        new: Ref,
+        /// The name of the property.
+        name: Ustr,
+        /// The value in the old DOM, or `None` if it wasn't present.
+        old_value: Option<Variant>,
+        /// The value in the new DOM, or `None` if it isn't present.
+        new_value: Option<Variant>,
+    },
+}
+
+/// The differences between two [`WeakDom`]s.
+///
+/// ## Example
+/// ```
+/// use rbx_dom_weak::{Change, Diff, InstanceBuilder, MatchStrategy, WeakDom};
+///
+/// let old = WeakDom::new(
+///     InstanceBuilder::new("DataModel").with_child(InstanceBuilder::new("Folder")),
+/// );
+/// let new = WeakDom::new(
+///     InstanceBuilder::new("DataModel")
+///         .with_child(InstanceBuilder::new("Folder").with_property("Tag", "new")),
+/// );
+///
+/// let diff = Diff::new(&old, &new, MatchStrategy::NamePath);
+/// assert!(matches!(diff.changes(), [Change::PropertyChanged { .. }]));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diff {
+    matches: AHashMap<Ref, Ref>,
+    changes: Vec<Change>,
+}
+
+impl Diff {
+    /// Compares two DOMs, pairing up their instances with the given strategy.
+    ///
+    /// Changes are ordered so that, within each kind of change, parents come
+    /// before their descendants. Every unpaired instance is reported, not
+    /// only the top of each added or removed subtree.
+    ///
+    /// `Ref` properties are considered equal if they point to paired
+    /// instances.
+    pub fn new(old: &WeakDom, new: &WeakDom, strategy: MatchStrategy) -> Self {
+        let matches = match strategy {
+            MatchStrategy::UniqueId => match_by_unique_id(old, new),
+            MatchStrategy::Referent => match_by_referent(old, new),
+            MatchStrategy::NamePath => match_by_name_path(old, new),
+        };
+
+        let mut diff = Diff {
+            matches,
+            changes: Vec::new(),
+        };
+        diff.compute_changes(old, new);
+        diff
+    }
+
+    /// Returns every change found between the two DOMs.
+    #[inline]
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Consumes the `Diff`, returning every change found between the two DOMs.
+    #[inline]
+    pub fn into_changes(self) -> Vec<Change> {
+        self.changes
+    }
+
+    /// Returns true if the two DOMs had no differences.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the referent in the new DOM that the given referent from the
+    /// old DOM was paired with, if any.
+    #[inline]
+    pub fn matching_new_ref(&self, old: Ref) -> Option<Ref> {
+        self.matches.get(&old).copied()
+    }
+
+    /// Returns an iterator over every pair of matched referents, as
+    /// `(old, new)`.
+    pub fn matched(&self) -> impl Iterator<Item = (Ref, Ref)> + '_ {
+        self.matches.iter().map(|(&old, &new)| (old, new))
+    }
+
+    fn compute_changes(&mut self, old: &WeakDom, new: &WeakDom) {
+        let new_to_old: AHashMap<Ref, Ref> = self
+            .matches
+            .iter()
+            .map(|(&old_ref, &new_ref)| (new_ref, old_ref))
+            .collect();
+
+        let mut added = Vec::new();
+        let mut moved = Vec::new();
+        let mut renamed = Vec::new();
+        let mut class_changed = Vec::new();
+        let mut properties = Vec::new();
+
+        for new_instance in new.descendants() {
+            let new_ref = new_instance.referent();
+
+            let old_ref = match new_to_old.get(&new_ref) {
+                Some(&old_ref) => old_ref,
+                None => {
+                    added.push(Change::Added {
+                        new: new_ref,
+                        new_parent: new_instance.parent(),
+                    });
+                    continue;
+                }
+            };
+            let old_instance = old.get_by_ref(old_ref).unwrap();
+
+            let old_parent = old_instance.parent();
+            let new_parent = new_instance.parent();
+            let parent_matches = if old_parent.is_none() {
+                new_parent.is_none()
+            } else {
+                self.matches.get(&old_parent) == Some(&new_parent)
+            };
+
+            if !parent_matches {
+                moved.push(Change::Moved {
+                    old: old_ref,
+                    new: new_ref,
+                    old_parent,
+                    new_parent,
+                });
+            }
+
+            if old_instance.name != new_instance.name {
+                renamed.push(Change::Renamed {
+                    old: old_ref,
+                    new: new_ref,
+                    old_name: old_instance.name.clone(),
+                    new_name: new_instance.name.clone(),
+                });
+            }
+
+            if old_instance.class != new_instance.class {
+                class_changed.push(Change::ClassChanged {
+                    old: old_ref,
+                    new: new_ref,
+                    old_class: old_instance.class,
+                    new_class: new_instance.class,
+                });
+            }
+
+            self.diff_properties(old_instance, new_instance, &mut properties);
+        }
+
+        let mut removed = Vec::new();
+        for old_instance in old.descendants() {
+            if !self.matches.contains_key(&old_instance.referent()) {
+                removed.push(Change::Removed {
+                    old: old_instance.referent(),
+                    old_parent: old_instance.parent(),
+                });
+            }
+        }
+
+        self.changes.extend(added);
+        self.changes.extend(removed);
+        self.changes.extend(moved);
+        self.changes.extend(renamed);
+        self.changes.extend(class_changed);
+        self.changes.extend(properties);
+    }
+
+    fn diff_properties(&self, old: &Instance, new: &Instance, changes: &mut Vec<Change>) {
+        let mut names: Vec<Ustr> = old
+            .properties
+            .keys()
+            .chain(new.properties.keys())
+            .copied()
+            .collect::<AHashSet<_>>()
+            .into_iter()
+            .collect();
+        names.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        for name in names {
+            let old_value = old.properties.get(&name);
+            let new_value = new.properties.get(&name);
+
+            let equal = match (old_value, new_value) {
+                (Some(old_value), Some(new_value)) => self.values_equal(old_value, new_value),
+                (None, None) => true,
+                _ => false,
+            };
+
+            if !equal {
+                changes.push(Change::PropertyChanged {
+                    old: old.referent(),
+                    new: new.referent(),
+                    name,
+                    old_value: old_value.cloned(),
+                    new_value: new_value.cloned(),
+                });
+            }
+        }
+    }
+
+    /// Compares two property values, treating referents as equal if they
+    /// point to paired instances.
+    fn values_equal(&self, old: &Variant, new: &Variant) -> bool {
+        match (old, new) {
+            (Variant::Ref(old), Variant::Ref(new)) => self.refs_equal(*old, *new),
+            (Variant::Content(old), Variant::Content(new)) => match (old.value(), new.value()) {
+                (ContentType::Object(old), ContentType::Object(new)) => self.refs_equal(*old, *new),
+                _ => old == new,
+            },
+            _ => old == new,
+        }
+    }
+
+    fn refs_equal(&self, old: Ref, new: Ref) -> bool {
+        if old.is_none() {
+            new.is_none()
+        } else {
+            self.matches.get(&old) == Some(&new)
+        }
+    }
+}
+
+fn match_by_unique_id(old: &WeakDom, new: &WeakDom) -> AHashMap<Ref, Ref> {
+    // Copying instances in Studio duplicates their UniqueId, so each ID can
+    // belong to several instances. Those are paired in the order they appear
+    // so that no instance is paired twice.
+    fn refs_by_id(dom: &WeakDom) -> AHashMap<UniqueId, VecDeque<Ref>> {
+        let mut refs_by_id: AHashMap<UniqueId, VecDeque<Ref>> = AHashMap::new();
+        for instance in dom.descendants() {
+            let referent = instance.referent();
+            if referent == dom.root_ref() {
+                continue;
+            }
+
+            if let Some(id) = dom.get_unique_id(referent) {
+                refs_by_id.entry(id).or_default().push_back(referent);
+            }
+        }
+        refs_by_id
+    }
+
+    let mut new_by_id = refs_by_id(new);
+
+    let mut matches = AHashMap::new();
+    matches.insert(old.root_ref(), new.root_ref());
+
+    for instance in old.descendants() {
+        let referent = instance.referent();
+        if referent == old.root_ref() {
+            continue;
+        }
+
+        if let Some(id) = old.get_unique_id(referent) {
+            if let Some(new_ref) = new_by_id.get_mut(&id).and_then(VecDeque::pop_front) {
+                matches.insert(referent, new_ref);
+            }
+        }
+    }
+
+    matches
+}
+
+fn match_by_referent(old: &WeakDom, new: &WeakDom) -> AHashMap<Ref, Ref> {
+    let mut matches = AHashMap::new();
+    matches.insert(old.root_ref(), new.root_ref());
+
+    for instance in old.descendants() {
+        let referent = instance.referent();
+        if referent != old.root_ref()
+            && referent != new.root_ref()
+            && new.get_by_ref(referent).is_some()
+        {
+            matches.insert(referent, referent);
+        }
+    }
+
+    matches
+}
+
+fn match_by_name_path(old: &WeakDom, new: &WeakDom) -> AHashMap<Ref, Ref> {
+    let mut matches = AHashMap::new();
+    let mut queue = VecDeque::new();
+    queue.push_back((old.root_ref(), new.root_ref()));
+
+    while let Some((old_ref, new_ref)) = queue.pop_front() {
+        matches.insert(old_ref, new_ref);
+
+        let old_children = old.get_by_ref(old_ref).unwrap().children();
+        let new_children = new.get_by_ref(new_ref).unwrap().children();
+
+        // Siblings with the same name are paired in the order they appear, so
+        // each new child can only be claimed once.
+        let mut new_by_name: AHashMap<&str, VecDeque<Ref>> = AHashMap::new();
+        for &new_child in new_children {
+            let new_name = new.get_by_ref(new_child).unwrap().name.as_str();
+            new_by_name
+                .entry(new_name)
+                .or_default()
+                .push_back(new_child);
+        }
+
+        for &old_child in old_children {
+            let old_name = old.get_by_ref(old_child).unwrap().name.as_str();
+
+            if let Some(new_child) = new_by_name.get_mut(old_name).and_then(VecDeque::pop_front) {
+                queue.push_back((old_child, new_child));
+            }
+        }
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::InstanceBuilder;
+
+    fn find(dom: &WeakDom, name: &str) -> Ref {
+        dom.descendants()
+            .find(|instance| instance.name == name)
+            .unwrap()
+            .referent()
+    }
+
+    #[test]
+    fn no_changes() {
+        let dom = WeakDom::new(
+            InstanceBuilder::new("DataModel")
+                .with_child(InstanceBuilder::new("Folder").with_property("Value", 1.0f64)),
+        );
+
+        for strategy in [
+            MatchStrategy::NamePath,
+            MatchStrategy::Referent,
+            MatchStrategy::UniqueId,
+        ] {
+            let diff = Diff::new(&dom, &dom, strategy);
+
+            if strategy == MatchStrategy::UniqueId {
+                // Nothing has a UniqueId, so nothing can be paired.
+                assert_eq!(diff.changes().len(), 2);
+            } else {
+                assert!(diff.is_empty(), "{:?} produced {:?}", strategy, diff);
+            }
+        }
+    }
+
+    #[test]
+    fn added_removed_and_properties() {
+        let old = WeakDom::new(
+            InstanceBuilder::new("DataModel").with_children([
+                InstanceBuilder::new("Folder")
+                    .with_name("Kept")
+                    .with_property("Value", 1.0f64)
+                    .with_property("Removed", true),
+                InstanceBuilder::new("Folder").with_name("Gone"),
+            ]),
+        );
+        let new = WeakDom::new(
+            InstanceBuilder::new("DataModel").with_children([
+                InstanceBuilder::new("Folder")
+                    .with_name("Kept")
+                    .with_property("Value", 2.0f64)
+                    .with_property("Added", "hi"),
+                InstanceBuilder::new("Part").with_name("Fresh"),
+            ]),
+        );
+
+        let diff = Diff::new(&old, &new, MatchStrategy::NamePath);
+        let old_kept = find(&old, "Kept");
+        let new_kept = find(&new, "Kept");
+
+        assert_eq!(
+            diff.changes(),
+            &[
+                Change::Added {
+                    new: find(&new, "Fresh"),
+                    new_parent: new.root_ref(),
+                },
+                Change::Removed {
+                    old: find(&old, "Gone"),
+                    old_parent: old.root_ref(),
+                },
+                Change::PropertyChanged {
+                    old: old_kept,
+                    new: new_kept,
+                    name: "Added".into(),
+                    old_value: None,
+                    new_value: Some("hi".into()),
+                },
+                Change::PropertyChanged {
+                    old: old_kept,
+                    new: new_kept,
+                    name: "Removed".into(),
+                    old_value: Some(true.into()),
+                    new_value: None,
+                },
+                Change::PropertyChanged {
+                    old: old_kept,
+                    new: new_kept,
+                    name: "Value".into(),
+                    old_value: Some(1.0f64.into()),
+                    new_value: Some(2.0f64.into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn moved_and_renamed_by_unique_id() {
+        let part_id = UniqueId::new(1, 2, 3);
+        let folder_id = UniqueId::new(4, 5, 6);
+
+        let old = WeakDom::new(
+            InstanceBuilder::new("DataModel").with_children([
+                InstanceBuilder::new("Folder")
+                    .with_name("Folder")
+                    .with_property("UniqueId", folder_id),
+                InstanceBuilder::new("Part")
+                    .with_name("Part")
+                    .with_property("UniqueId", part_id),
+            ]),
+        );
+        let new = WeakDom::new(
+            InstanceBuilder::new("DataModel").with_child(
+                InstanceBuilder::new("Folder")
+                    .with_name("Folder")
+                    .with_property("UniqueId", folder_id)
+                    .with_child(
+                        InstanceBuilder::new("Part")
+                            .with_name("Renamed")
+                            .with_property("UniqueId", part_id),
+                    ),
+            ),
+        );
+
+        let diff = Diff::new(&old, &new, MatchStrategy::UniqueId);
+        let old_part = find(&old, "Part");
+        let new_part = find(&new, "Renamed");
+
+        assert_eq!(diff.matching_new_ref(old_part), Some(new_part));
+        assert_eq!(
+            diff.changes(),
+            &[
+                Change::Moved {
+                    old: old_part,
+                    new: new_part,
+                    old_parent: old.root_ref(),
+                    new_parent: find(&new, "Folder"),
+                },
+                Change::Renamed {
+                    old: old_part,
+                    new: new_part,
+                    old_name: "Part".to_owned(),
+                    new_name: "Renamed".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicated_unique_ids() {
+        fn build(names: &[&str]) -> WeakDom {
+            let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+            for name in names {
+                let referent = dom.insert(
+                    dom.root_ref(),
+                    InstanceBuilder::new("Part").with_name(*name),
+                );
+
+                // Inserting would give each instance a new UniqueId, so the
+                // duplicates have to be made afterwards.
+                dom.get_by_ref_mut(referent)
+                    .unwrap()
+                    .properties
+                    .insert("UniqueId".into(), UniqueId::new(1, 2, 3).into());
+            }
+            dom
+        }
+
+        let mut old = build(&["One", "Two", "Three"]);
+        let new = build(&["One", "Two"]);
+
+        let diff = Diff::new(&old, &new, MatchStrategy::UniqueId);
+        assert_eq!(
+            diff.matching_new_ref(find(&old, "One")),
+            Some(find(&new, "One"))
+        );
+        assert_eq!(
+            diff.matching_new_ref(find(&old, "Two")),
+            Some(find(&new, "Two"))
+        );
+        assert_eq!(
+            diff.changes(),
+            &[Change::Removed {
+                old: find(&old, "Three"),
+                old_parent: old.root_ref(),
+            }]
+        );
+
+        // The diff should describe exactly how to get to the new DOM.
+        let patch = crate::Patch::from_diff(&diff, &old, &new);
+        assert_eq!(patch.apply(&mut old), Vec::new());
+        assert!(Diff::new(&old, &new, MatchStrategy::NamePath).is_empty());
+    }
+
+    #[test]
+    fn refs_compared_by_pairing() {
+        fn build() -> WeakDom {
+            let target = InstanceBuilder::new("Part").with_name("Target");
+            let target_ref = target.referent();
+
+            WeakDom::new(
+                InstanceBuilder::new("DataModel")
+                    .with_child(target)
+                    .with_child(
+                        InstanceBuilder::new("ObjectValue")
+                            .with_name("Pointer")
+                            .with_property("Value", target_ref),
+                    ),
+            )
+        }
+
+        // Both DOMs have different referents, but the same structure.
+        let old = build();
+        let new = build();
+
+        let diff = Diff::new(&old, &new, MatchStrategy::NamePath);
+        assert!(diff.is_empty(), "{:?}", diff);
+    }
+}
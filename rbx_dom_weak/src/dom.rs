@@ -4,7 +4,12 @@ use ahash::{AHashMap, AHashSet};
 use rbx_types::{Ref, UniqueId, Variant};
 use ustr::ustr;
 
-use crate::instance::{Instance, InstanceBuilder};
+use crate::{
+    index::DomIndex,
+    instance::{Instance, InstanceBuilder},
+    journal::{DomEvent, DomJournal},
+    transaction::History,
+};
 
 /// Represents a DOM containing one or more Roblox instances.
 ///
@@ -15,9 +20,12 @@ use crate::instance::{Instance, InstanceBuilder};
 /// objects and insert them into the tree.
 #[derive(Debug)]
 pub struct WeakDom {
-    instances: AHashMap<Ref, Instance>,
+    pub(crate) instances: AHashMap<Ref, Instance>,
     root_ref: Ref,
     unique_ids: AHashSet<UniqueId>,
+    pub(crate) index: Option<DomIndex>,
+    pub(crate) journal: DomJournal,
+    pub(crate) history: History,
 }
 
 impl WeakDom {
@@ -27,6 +35,9 @@ impl WeakDom {
             instances: AHashMap::new(),
             root_ref: builder.referent,
             unique_ids: AHashSet::new(),
+            index: None,
+            journal: DomJournal::default(),
+            history: History::default(),
         };
 
         dom.insert(Ref::none(), builder);
@@ -55,6 +66,7 @@ impl WeakDom {
         let mut unique_ids = AHashSet::with_capacity(instances.len());
         for inst in instances.values() {
             // This `if` cannot be collapsed into the match with an if guard.
+            #[allow(clippy::collapsible_match)]
             match inst.properties.get(&ustr("UniqueId")) {
                 Some(Variant::UniqueId(id)) => {
                     if !unique_ids.insert(*id) {
@@ -76,6 +88,9 @@ impl WeakDom {
             instances,
             root_ref,
             unique_ids,
+            index: None,
+            journal: DomJournal::default(),
+            history: History::default(),
         }
     }
 
@@ -104,7 +119,8 @@ impl WeakDom {
 
     /// Returns a _mutable_ reference to the root instance of the `WeakDom`.
     pub fn root_mut(&mut self) -> &mut Instance {
-        self.instances.get_mut(&self.root_ref).unwrap()
+        let root_ref = self.root_ref;
+        self.get_by_ref_mut(root_ref).unwrap()
     }
 
     /// Returns a reference to an instance by referent, or `None` if it is not
@@ -115,8 +131,26 @@ impl WeakDom {
 
     /// Returns a _mutable_ reference to an instance by referent, or `None` if
     /// it is not found.
+    ///
+    /// When indexes, the journal, observers, or a transaction are active, the
+    /// instance is remembered so that changes made through the returned
+    /// reference can be picked up later. The journal, observers, and
+    /// transactions need a copy of the instance's properties for this, so
+    /// every call clones them while any of those are active.
     pub fn get_by_ref_mut(&mut self, referent: Ref) -> Option<&mut Instance> {
-        self.instances.get_mut(&referent)
+        if !self.is_tracking_changes() {
+            return self.instances.get_mut(&referent);
+        }
+
+        self.flush_pending();
+
+        let instance = self.instances.get_mut(&referent)?;
+        if let Some(index) = &mut self.index {
+            index.pending = Some(referent);
+        }
+        self.journal.snapshot(instance);
+
+        Some(instance)
     }
 
     /// Returns the [`UniqueId`] for the Instance with the provided referent, if it
@@ -129,6 +163,145 @@ impl WeakDom {
         }
     }
 
+    /// Starts maintaining indexes that speed up [`WeakDom::find_by_class`],
+    /// [`WeakDom::find_children_by_name`], [`WeakDom::find_by_unique_id`], and
+    /// [`WeakDom::find_references_to`].
+    ///
+    /// Indexes are kept up to date as the DOM changes, including changes made
+    /// through [`WeakDom::get_by_ref_mut`], at the cost of some extra work
+    /// for every mutation. Building them takes time proportional to the size
+    /// of the DOM.
+    pub fn enable_indexes(&mut self) {
+        if self.index.is_some() {
+            return;
+        }
+
+        let mut index = DomIndex::default();
+        for instance in self.instances.values() {
+            index.insert(instance);
+        }
+
+        self.index = Some(index);
+    }
+
+    /// Stops maintaining the indexes created by [`WeakDom::enable_indexes`]
+    /// and frees their memory.
+    pub fn disable_indexes(&mut self) {
+        self.index = None;
+    }
+
+    /// Returns whether indexes are enabled for this DOM.
+    #[inline]
+    pub fn has_indexes(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Returns the referents of every instance in the DOM with the given class,
+    /// in no particular order.
+    ///
+    /// This requires a scan of the whole DOM unless indexes are enabled with
+    /// [`WeakDom::enable_indexes`].
+    pub fn find_by_class(&self, class: &str) -> Vec<Ref> {
+        match &self.index {
+            Some(index) => index.by_class(class, self.pending_instance()),
+            None => self
+                .instances
+                .values()
+                .filter(|instance| instance.class == class)
+                .map(|instance| instance.referent)
+                .collect(),
+        }
+    }
+
+    /// Returns the referents of every child of `parent` with the given name, in
+    /// no particular order. If `parent` is the none Ref, instances without a
+    /// parent are searched instead.
+    ///
+    /// This requires a scan of the parent's children unless indexes are
+    /// enabled with [`WeakDom::enable_indexes`].
+    pub fn find_children_by_name(&self, parent: Ref, name: &str) -> Vec<Ref> {
+        match &self.index {
+            Some(index) => index.by_parent_name(parent, name, self.pending_instance()),
+            None if parent.is_some() => self
+                .instances
+                .get(&parent)
+                .map(|parent| parent.children.as_slice())
+                .unwrap_or_default()
+                .iter()
+                .copied()
+                .filter(|child| self.instances[child].name == name)
+                .collect(),
+            None => self
+                .instances
+                .values()
+                .filter(|instance| instance.parent.is_none() && instance.name == name)
+                .map(|instance| instance.referent)
+                .collect(),
+        }
+    }
+
+    /// Returns the referent of the instance with the given [`UniqueId`], if
+    /// there is one. This is the reverse of [`WeakDom::get_unique_id`].
+    ///
+    /// This requires a scan of the whole DOM unless indexes are enabled with
+    /// [`WeakDom::enable_indexes`].
+    pub fn find_by_unique_id(&self, unique_id: UniqueId) -> Option<Ref> {
+        match &self.index {
+            Some(index) => index.by_unique_id(unique_id, self.pending_instance()),
+            None => self
+                .instances
+                .values()
+                .find(|instance| {
+                    instance.properties.get(&ustr("UniqueId"))
+                        == Some(&Variant::UniqueId(unique_id))
+                })
+                .map(|instance| instance.referent),
+        }
+    }
+
+    /// Starts recording every change made to the DOM as a [`DomEvent`], which
+    /// can be retrieved with [`WeakDom::drain_journal`].
+    ///
+    /// Changes made through [`WeakDom::get_by_ref_mut`] are found by comparing
+    /// the instance against a copy taken when it was borrowed, so they're
+    /// recorded the next time the DOM is changed or the journal is drained.
+    pub fn enable_journal(&mut self) {
+        self.journal.set_recording(true);
+    }
+
+    /// Stops recording changes and returns any events that haven't been
+    /// drained yet.
+    pub fn disable_journal(&mut self) -> Vec<DomEvent> {
+        let events = self.drain_journal();
+        self.journal.set_recording(false);
+        events
+    }
+
+    /// Returns every event recorded since the journal was enabled or last
+    /// drained, oldest first.
+    pub fn drain_journal(&mut self) -> Vec<DomEvent> {
+        self.flush_pending();
+        self.journal.take_events()
+    }
+
+    /// Registers a callback that's called with every change made to the DOM,
+    /// whether or not the journal is enabled. Changes made through
+    /// [`WeakDom::get_by_ref_mut`] are reported on the next change to the DOM
+    /// or call to [`WeakDom::drain_journal`].
+    pub fn observe<F>(&mut self, observer: F)
+    where
+        F: FnMut(&DomEvent) + Send + Sync + 'static,
+    {
+        self.flush_pending();
+        self.journal.add_observer(Box::new(observer));
+    }
+
+    /// Removes every callback registered with [`WeakDom::observe`].
+    pub fn clear_observers(&mut self) {
+        self.flush_pending();
+        self.journal.clear_observers();
+    }
+
     /// Returns an iterator that goes through every descendant Instance of the
     /// root referent.
     ///
@@ -197,6 +370,8 @@ impl WeakDom {
     /// ## Panics
     /// Panics if `parent_ref` is some and does not refer to an instance in the DOM.
     pub fn insert(&mut self, parent_ref: Ref, root_builder: InstanceBuilder) -> Ref {
+        self.flush_pending();
+
         fn insert(
             dom: &mut WeakDom,
             builder: InstanceBuilder,
@@ -264,6 +439,8 @@ impl WeakDom {
             panic!("cannot destroy the root instance of a WeakDom");
         }
 
+        self.flush_pending();
+
         let instance = self
             .instances
             .get(&referent)
@@ -278,10 +455,17 @@ impl WeakDom {
         let mut to_remove = VecDeque::new();
         to_remove.push_back(referent);
 
+        let mut events = Vec::new();
         while let Some(referent) = to_remove.pop_front() {
             let instance = self.inner_remove(referent);
+            events.extend(self.journal.destroyed_event(&instance));
             to_remove.extend(instance.children);
         }
+
+        // Children are reported before their parents.
+        for event in events.into_iter().rev() {
+            self.journal.push(event);
+        }
     }
 
     /// Move the instance with the given referent to a new `WeakDom`, parenting
@@ -295,11 +479,18 @@ impl WeakDom {
     /// `dest_parent_ref` does not refer to an instance in `other_dom`.
     ///
     /// Will also panic if `referent` refers to the root instance in this
-    /// `WeakDom`.
+    /// `WeakDom`, or if either `WeakDom` has a transaction in progress, since
+    /// a transfer can't be rolled back.
     pub fn transfer(&mut self, referent: Ref, dest: &mut WeakDom, dest_parent_ref: Ref) {
         if referent == self.root_ref {
             panic!("cannot transfer the root instance of WeakDom");
         }
+        if self.in_transaction() || dest.in_transaction() {
+            panic!("cannot transfer between WeakDoms while a transaction is in progress");
+        }
+
+        self.flush_pending();
+        dest.flush_pending();
 
         let mut instance = self.inner_remove(referent);
 
@@ -318,6 +509,9 @@ impl WeakDom {
         let mut to_move = VecDeque::new();
         to_move.extend(instance.children.iter().copied());
 
+        let mut events = Vec::new();
+        events.extend(self.journal.destroyed_event(&instance));
+
         // Instance was released.
         // Bye-bye, instance!
         instance.parent = dest_parent_ref;
@@ -326,11 +520,16 @@ impl WeakDom {
         // Transfer all of the descendants of the moving instance breadth-first.
         while let Some(referent) = to_move.pop_front() {
             let instance = self.inner_remove(referent);
+            events.extend(self.journal.destroyed_event(&instance));
 
             to_move.extend(instance.children.iter().copied());
             dest.inner_insert(referent, instance);
         }
 
+        for event in events.into_iter().rev() {
+            self.journal.push(event);
+        }
+
         // Finally, notify the new parent instance that their adoption is
         // complete. Enjoy!
         let dest_parent = dest.instances.get_mut(&dest_parent_ref).unwrap_or_else(|| {
@@ -357,6 +556,8 @@ impl WeakDom {
             panic!("cannot transfer the root instance of WeakDom");
         }
 
+        self.flush_pending();
+
         let instance = self
             .instances
             .get_mut(&referent)
@@ -366,6 +567,17 @@ impl WeakDom {
         let parent_ref = instance.parent;
         instance.parent = dest_parent_ref;
 
+        if let Some(index) = &mut self.index {
+            index.update(instance);
+        }
+        if parent_ref != dest_parent_ref && self.journal.is_active() {
+            self.journal.push(DomEvent::Moved {
+                referent,
+                old_parent: parent_ref,
+                new_parent: dest_parent_ref,
+            });
+        }
+
         // Remove the instance's referent from its parent's list of children.
         if parent_ref.is_some() {
             let parent = self.instances.get_mut(&parent_ref).unwrap();
@@ -477,6 +689,11 @@ impl WeakDom {
                 self.unique_ids.insert(*unique_id);
             };
         }
+
+        if let Some(index) = &mut self.index {
+            index.insert(instance);
+        }
+        self.journal.inserted(instance);
     }
 
     fn inner_remove(&mut self, referent: Ref) -> Instance {
@@ -489,8 +706,44 @@ impl WeakDom {
             self.unique_ids.remove(unique_id);
         }
 
+        if let Some(index) = &mut self.index {
+            index.remove(referent);
+        }
+
         instance
     }
+
+    /// Tells whether anything needs to know about changes made through
+    /// [`WeakDom::get_by_ref_mut`].
+    fn is_tracking_changes(&self) -> bool {
+        self.index.is_some()
+            || self.journal.is_active()
+            || self.journal.pending_referent().is_some()
+    }
+
+    /// Re-indexes and journals the instance most recently returned by
+    /// [`WeakDom::get_by_ref_mut`], since it may have been changed.
+    pub(crate) fn flush_pending(&mut self) {
+        if let Some(index) = &mut self.index {
+            if let Some(referent) = index.pending.take() {
+                if let Some(instance) = self.instances.get(&referent) {
+                    index.update(instance);
+                }
+            }
+        }
+
+        if let Some(referent) = self.journal.pending_referent() {
+            match self.instances.get(&referent) {
+                Some(instance) => self.journal.compare_pending(instance),
+                None => self.journal.discard_pending(),
+            }
+        }
+    }
+
+    pub(crate) fn pending_instance(&self) -> Option<&Instance> {
+        let referent = self.index.as_ref()?.pending?;
+        self.instances.get(&referent)
+    }
 }
 
 /// A struct for iterating through the descendants of an Instance in a
@@ -522,6 +775,9 @@ impl Default for WeakDom {
             instances: AHashMap::new(),
             root_ref: Ref::none(),
             unique_ids: AHashSet::new(),
+            index: None,
+            journal: DomJournal::default(),
+            history: History::default(),
         }
     }
 }
@@ -0,0 +1,295 @@
+use ahash::{AHashMap, AHashSet};
+use rbx_types::{Ref, UniqueId, Variant};
+use ustr::{ustr, Ustr, UstrMap};
+
+use crate::{refs::outgoing_refs, Instance};
+
+/// Secondary indexes over the instances in a `WeakDom`, enabled with
+/// `WeakDom::enable_indexes`.
+///
+/// Instances handed out by `WeakDom::get_by_ref_mut` can be changed in any
+/// way, so the most recent one is remembered as `pending` and re-indexed the
+/// next time the DOM is mutated. Queries treat the pending instance
+/// specially, so results are always accurate.
+#[derive(Debug, Default)]
+pub(crate) struct DomIndex {
+    entries: AHashMap<Ref, Entry>,
+    by_class: UstrMap<AHashSet<Ref>>,
+    by_parent_name: AHashMap<(Ref, String), AHashSet<Ref>>,
+    by_unique_id: AHashMap<UniqueId, Ref>,
+    refs_to: AHashMap<Ref, AHashSet<(Ref, Ustr)>>,
+    pub(crate) pending: Option<Ref>,
+}
+
+/// The keys an instance was indexed under, so that it can be removed from the
+/// index even after it's been changed.
+#[derive(Debug)]
+struct Entry {
+    class: Ustr,
+    parent: Ref,
+    name: String,
+    unique_id: Option<UniqueId>,
+    refs: Vec<(Ustr, Ref)>,
+}
+
+impl DomIndex {
+    pub fn insert(&mut self, instance: &Instance) {
+        let unique_id = match instance.properties.get(&ustr("UniqueId")) {
+            Some(Variant::UniqueId(unique_id)) => Some(*unique_id),
+            _ => None,
+        };
+        let entry = Entry {
+            class: instance.class,
+            parent: instance.parent(),
+            name: instance.name.clone(),
+            unique_id,
+            refs: outgoing_refs(instance).collect(),
+        };
+        let referent = instance.referent();
+
+        self.by_class
+            .entry(entry.class)
+            .or_default()
+            .insert(referent);
+        self.by_parent_name
+            .entry((entry.parent, entry.name.clone()))
+            .or_default()
+            .insert(referent);
+        if let Some(unique_id) = entry.unique_id {
+            self.by_unique_id.insert(unique_id, referent);
+        }
+        for (name, target) in &entry.refs {
+            self.refs_to
+                .entry(*target)
+                .or_default()
+                .insert((referent, *name));
+        }
+
+        self.entries.insert(referent, entry);
+    }
+
+    pub fn remove(&mut self, referent: Ref) {
+        if self.pending == Some(referent) {
+            self.pending = None;
+        }
+
+        let entry = match self.entries.remove(&referent) {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        if let Some(refs) = self.by_class.get_mut(&entry.class) {
+            refs.remove(&referent);
+            if refs.is_empty() {
+                self.by_class.remove(&entry.class);
+            }
+        }
+
+        let key = (entry.parent, entry.name);
+        if let Some(refs) = self.by_parent_name.get_mut(&key) {
+            refs.remove(&referent);
+            if refs.is_empty() {
+                self.by_parent_name.remove(&key);
+            }
+        }
+
+        if let Some(unique_id) = entry.unique_id {
+            if self.by_unique_id.get(&unique_id) == Some(&referent) {
+                self.by_unique_id.remove(&unique_id);
+            }
+        }
+
+        for (name, target) in entry.refs {
+            if let Some(sources) = self.refs_to.get_mut(&target) {
+                sources.remove(&(referent, name));
+                if sources.is_empty() {
+                    self.refs_to.remove(&target);
+                }
+            }
+        }
+    }
+
+    pub fn update(&mut self, instance: &Instance) {
+        self.remove(instance.referent());
+        self.insert(instance);
+    }
+
+    pub fn by_class(&self, class: &str, pending: Option<&Instance>) -> Vec<Ref> {
+        let mut refs: Vec<Ref> = Ustr::from_existing(class)
+            .and_then(|class| self.by_class.get(&class))
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|referent| Some(*referent) != self.pending)
+            .collect();
+
+        if let Some(instance) = pending {
+            if instance.class == class {
+                refs.push(instance.referent());
+            }
+        }
+
+        refs
+    }
+
+    pub fn by_parent_name(&self, parent: Ref, name: &str, pending: Option<&Instance>) -> Vec<Ref> {
+        let mut refs: Vec<Ref> = self
+            .by_parent_name
+            .get(&(parent, name.to_owned()))
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|referent| Some(*referent) != self.pending)
+            .collect();
+
+        if let Some(instance) = pending {
+            if instance.parent() == parent && instance.name == name {
+                refs.push(instance.referent());
+            }
+        }
+
+        refs
+    }
+
+    pub fn refs_to(&self, target: Ref, pending: Option<&Instance>) -> Vec<(Ref, Ustr)> {
+        let mut refs: Vec<(Ref, Ustr)> = self
+            .refs_to
+            .get(&target)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|(source, _)| Some(*source) != self.pending)
+            .collect();
+
+        if let Some(instance) = pending {
+            refs.extend(
+                outgoing_refs(instance)
+                    .filter(|(_, referent)| *referent == target)
+                    .map(|(name, _)| (instance.referent(), name)),
+            );
+        }
+
+        refs
+    }
+
+    pub fn by_unique_id(&self, unique_id: UniqueId, pending: Option<&Instance>) -> Option<Ref> {
+        if let Some(instance) = pending {
+            if instance.properties.get(&ustr("UniqueId")) == Some(&Variant::UniqueId(unique_id)) {
+                return Some(instance.referent());
+            }
+        }
+
+        self.by_unique_id
+            .get(&unique_id)
+            .copied()
+            .filter(|referent| Some(*referent) != self.pending)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rbx_types::{Ref, UniqueId};
+
+    use crate::{InstanceBuilder, WeakDom};
+
+    fn sorted(mut refs: Vec<Ref>) -> Vec<Ref> {
+        refs.sort_unstable_by_key(|referent| referent.to_string());
+        refs
+    }
+
+    fn check(dom: &mut WeakDom, parent: Ref) {
+        // Every indexed query should agree with the same query on an
+        // unindexed DOM.
+        let indexed = (
+            sorted(dom.find_by_class("Part")),
+            sorted(dom.find_children_by_name(parent, "A")),
+        );
+        dom.disable_indexes();
+        let scanned = (
+            sorted(dom.find_by_class("Part")),
+            sorted(dom.find_children_by_name(parent, "A")),
+        );
+        dom.enable_indexes();
+
+        assert_eq!(indexed, scanned);
+    }
+
+    #[test]
+    fn indexes_follow_mutations() {
+        let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+        dom.enable_indexes();
+        let root = dom.root_ref();
+
+        let folder = dom.insert(root, InstanceBuilder::new("Folder").with_name("A"));
+        let part = dom.insert(
+            folder,
+            InstanceBuilder::new("Part")
+                .with_name("A")
+                .with_child(InstanceBuilder::new("Part").with_name("A")),
+        );
+
+        assert_eq!(dom.find_by_class("Part").len(), 2);
+        assert_eq!(dom.find_children_by_name(root, "A"), [folder]);
+        assert_eq!(dom.find_children_by_name(folder, "A"), [part]);
+        check(&mut dom, folder);
+
+        dom.transfer_within(part, root);
+        assert_eq!(
+            sorted(dom.find_children_by_name(root, "A")),
+            sorted(vec![folder, part])
+        );
+        assert!(dom.find_children_by_name(folder, "A").is_empty());
+        check(&mut dom, root);
+
+        // Changes made through `get_by_ref_mut` are visible immediately...
+        let instance = dom.get_by_ref_mut(part).unwrap();
+        instance.name = "B".to_owned();
+        instance.class = "Model".into();
+        assert_eq!(dom.find_children_by_name(root, "A"), [folder]);
+        assert_eq!(dom.find_children_by_name(root, "B"), [part]);
+        assert_eq!(dom.find_by_class("Part").len(), 1);
+        assert_eq!(dom.find_by_class("Model"), [part]);
+
+        // ...and after the next mutation.
+        dom.get_by_ref_mut(folder).unwrap().name = "C".to_owned();
+        assert_eq!(dom.find_children_by_name(root, "B"), [part]);
+        assert_eq!(dom.find_children_by_name(root, "C"), [folder]);
+        check(&mut dom, root);
+
+        dom.destroy(part);
+        assert!(dom.find_by_class("Part").is_empty());
+        assert!(dom.find_by_class("Model").is_empty());
+        check(&mut dom, root);
+    }
+
+    #[test]
+    fn unique_ids() {
+        let first = UniqueId::new(1, 2, 3);
+        let second = UniqueId::new(4, 5, 6);
+
+        let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+        dom.enable_indexes();
+
+        let part = dom.insert(
+            dom.root_ref(),
+            InstanceBuilder::new("Part").with_property("UniqueId", first),
+        );
+        assert_eq!(dom.find_by_unique_id(first), Some(part));
+
+        dom.get_by_ref_mut(part)
+            .unwrap()
+            .properties
+            .insert("UniqueId".into(), second.into());
+        assert_eq!(dom.find_by_unique_id(first), None);
+        assert_eq!(dom.find_by_unique_id(second), Some(part));
+
+        let mut other = WeakDom::new(InstanceBuilder::new("DataModel"));
+        other.enable_indexes();
+        let other_root = other.root_ref();
+        dom.transfer(part, &mut other, other_root);
+
+        assert_eq!(dom.find_by_unique_id(second), None);
+        assert_eq!(other.find_by_unique_id(second), Some(part));
+        assert_eq!(other.find_by_class("Part"), [part]);
+    }
+}
@@ -94,6 +94,11 @@ impl InstanceBuilder {
         }
     }
 
+    /// Return the name of the instance that the `InstanceBuilder` refers to.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Change the name of the `InstanceBuilder`.
     pub fn with_name<S: Into<String>>(self, name: S) -> Self {
         Self {
@@ -137,6 +142,17 @@ impl InstanceBuilder {
         self.properties.iter().any(|(k, _)| *k == key)
     }
 
+    /// Returns how many properties have been added to the `InstanceBuilder`.
+    pub fn property_count(&self) -> usize {
+        self.properties.len()
+    }
+
+    /// Remove all properties from the `InstanceBuilder`, returning them as an
+    /// iterator in the order they were added.
+    pub fn drain_properties(&mut self) -> impl Iterator<Item = (Ustr, Variant)> + '_ {
+        self.properties.drain(..)
+    }
+
     /// Add multiple properties to the `InstanceBuilder` at once.
     pub fn with_properties<K, V, I>(mut self, props: I) -> Self
     where
@@ -0,0 +1,401 @@
+use std::fmt;
+
+use rbx_types::{Ref, Variant};
+use ustr::{Ustr, UstrMap};
+
+use crate::Instance;
+
+/// A change made to a [`WeakDom`][crate::WeakDom], recorded by its journal or
+/// passed to its observers.
+///
+/// Every event carries enough information to undo it.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DomEvent {
+    /// An instance was added to the DOM. Inserting a tree of instances
+    /// produces one event per instance, parents first. Instances transferred
+    /// in from another DOM are reported this way too.
+    Inserted {
+        /// The new instance.
+        referent: Ref,
+        /// The parent of the new instance.
+        parent: Ref,
+        /// The class of the new instance.
+        class: Ustr,
+        /// The name of the new instance.
+        name: String,
+        /// The properties of the new instance.
+        properties: UstrMap<Variant>,
+    },
+
+    /// An instance was removed from the DOM. Destroying a tree of instances
+    /// produces one event per instance, children first. Instances transferred
+    /// out to another DOM are reported this way too.
+    Destroyed {
+        /// The instance that was removed.
+        referent: Ref,
+        /// The parent the instance had.
+        parent: Ref,
+        /// The class the instance had.
+        class: Ustr,
+        /// The name the instance had.
+        name: String,
+        /// The properties the instance had.
+        properties: UstrMap<Variant>,
+    },
+
+    /// An instance was moved to a new parent within the DOM.
+    Moved {
+        /// The instance that was moved.
+        referent: Ref,
+        /// The previous parent of the instance.
+        old_parent: Ref,
+        /// The new parent of the instance.
+        new_parent: Ref,
+    },
+
+    /// An instance's name changed.
+    Renamed {
+        /// The instance that was renamed.
+        referent: Ref,
+        /// The previous name of the instance.
+        old_name: String,
+        /// The new name of the instance.
+        new_name: String,
+    },
+
+    /// An instance's class changed.
+    ClassChanged {
+        /// The instance that changed.
+        referent: Ref,
+        /// The previous class of the instance.
+        old_class: Ustr,
+        /// The new class of the instance.
+        new_class: Ustr,
+    },
+
+    /// A property was added, changed, or removed.
+    PropertyChanged {
+        /// The instance that changed.
+        referent: Ref,
+        /// The name of the property.
+        name: Ustr,
+        /// The previous value, or `None` if the property was added.
+        old_value: Option<Variant>,
+        /// The new value, or `None` if the property was removed.
+        new_value: Option<Variant>,
+    },
+}
+
+type Observer = Box<dyn FnMut(&DomEvent) + Send + Sync>;
+
+/// Records events for a `WeakDom` and passes them to observers.
+///
+/// Like indexes, changes made through `WeakDom::get_by_ref_mut` can't be seen
+/// as they happen. Instead, a snapshot of the instance is taken and compared
+/// against it the next time the DOM is mutated or the journal is drained.
+#[derive(Default)]
+pub(crate) struct DomJournal {
+    recording: bool,
+    events: Vec<DomEvent>,
+    observers: Vec<Observer>,
+    pending: Option<Snapshot>,
+    transaction: Option<Vec<DomEvent>>,
+}
+
+struct Snapshot {
+    referent: Ref,
+    name: String,
+    class: Ustr,
+    properties: UstrMap<Variant>,
+}
+
+impl DomJournal {
+    pub fn is_active(&self) -> bool {
+        self.recording || self.transaction.is_some() || !self.observers.is_empty()
+    }
+
+    /// Starts collecting events for a transaction, separately from the
+    /// journal.
+    pub fn begin_transaction(&mut self) {
+        self.transaction = Some(Vec::new());
+    }
+
+    /// Stops collecting events for the current transaction and returns them.
+    pub fn end_transaction(&mut self) -> Option<Vec<DomEvent>> {
+        self.transaction.take()
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+    }
+
+    pub fn add_observer(&mut self, observer: Observer) {
+        self.observers.push(observer);
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+
+    pub fn take_events(&mut self) -> Vec<DomEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn push(&mut self, event: DomEvent) {
+        for observer in &mut self.observers {
+            observer(&event);
+        }
+
+        if let Some(transaction) = &mut self.transaction {
+            transaction.push(event.clone());
+        }
+
+        if self.recording {
+            self.events.push(event);
+        }
+    }
+
+    pub fn inserted(&mut self, instance: &Instance) {
+        if self.is_active() {
+            self.push(DomEvent::Inserted {
+                referent: instance.referent(),
+                parent: instance.parent(),
+                class: instance.class,
+                name: instance.name.clone(),
+                properties: instance.properties.clone(),
+            });
+        }
+    }
+
+    /// Creates a `Destroyed` event for an instance, to be pushed once the
+    /// order of the destroyed instances is known.
+    pub fn destroyed_event(&self, instance: &Instance) -> Option<DomEvent> {
+        self.is_active().then(|| DomEvent::Destroyed {
+            referent: instance.referent(),
+            parent: instance.parent(),
+            class: instance.class,
+            name: instance.name.clone(),
+            properties: instance.properties.clone(),
+        })
+    }
+
+    /// Remembers the current state of an instance that's about to be handed
+    /// out mutably.
+    pub fn snapshot(&mut self, instance: &Instance) {
+        if self.is_active() {
+            self.pending = Some(Snapshot {
+                referent: instance.referent(),
+                name: instance.name.clone(),
+                class: instance.class,
+                properties: instance.properties.clone(),
+            });
+        }
+    }
+
+    pub fn pending_referent(&self) -> Option<Ref> {
+        self.pending.as_ref().map(|snapshot| snapshot.referent)
+    }
+
+    /// Compares the pending snapshot against the instance it was taken from
+    /// and emits events for any differences.
+    pub fn compare_pending(&mut self, instance: &Instance) {
+        let snapshot = match self.pending.take() {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+        let referent = snapshot.referent;
+
+        if snapshot.name != instance.name {
+            self.push(DomEvent::Renamed {
+                referent,
+                old_name: snapshot.name,
+                new_name: instance.name.clone(),
+            });
+        }
+
+        if snapshot.class != instance.class {
+            self.push(DomEvent::ClassChanged {
+                referent,
+                old_class: snapshot.class,
+                new_class: instance.class,
+            });
+        }
+
+        let mut names: Vec<Ustr> = snapshot
+            .properties
+            .keys()
+            .chain(instance.properties.keys())
+            .copied()
+            .collect();
+        names.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+        names.dedup();
+
+        for name in names {
+            let old_value = snapshot.properties.get(&name);
+            let new_value = instance.properties.get(&name);
+
+            if old_value != new_value {
+                self.push(DomEvent::PropertyChanged {
+                    referent,
+                    name,
+                    old_value: old_value.cloned(),
+                    new_value: new_value.cloned(),
+                });
+            }
+        }
+    }
+
+    pub fn discard_pending(&mut self) {
+        self.pending = None;
+    }
+}
+
+impl fmt::Debug for DomJournal {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("DomJournal")
+            .field("recording", &self.recording)
+            .field("events", &self.events)
+            .field("observers", &self.observers.len())
+            .field("transaction", &self.transaction)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use crate::{InstanceBuilder, WeakDom};
+
+    #[test]
+    fn records_changes() {
+        let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let root = dom.root_ref();
+        dom.enable_journal();
+
+        let folder = dom.insert(
+            root,
+            InstanceBuilder::new("Folder")
+                .with_name("Folder")
+                .with_child(InstanceBuilder::new("Part").with_name("Part")),
+        );
+        let part = dom.get_by_ref(folder).unwrap().children()[0];
+
+        dom.transfer_within(part, root);
+
+        let instance = dom.get_by_ref_mut(part).unwrap();
+        instance.name = "Renamed".to_owned();
+        instance.properties.insert("Anchored".into(), true.into());
+
+        dom.destroy(folder);
+
+        let events = dom.drain_journal();
+        let summary: Vec<_> = events
+            .iter()
+            .map(|event| match event {
+                DomEvent::Inserted { referent, .. } => ("inserted", *referent),
+                DomEvent::Destroyed { referent, .. } => ("destroyed", *referent),
+                DomEvent::Moved { referent, .. } => ("moved", *referent),
+                DomEvent::Renamed { referent, .. } => ("renamed", *referent),
+                DomEvent::ClassChanged { referent, .. } => ("class", *referent),
+                DomEvent::PropertyChanged { referent, .. } => ("property", *referent),
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                ("inserted", folder),
+                ("inserted", part),
+                ("moved", part),
+                ("renamed", part),
+                ("property", part),
+                ("destroyed", folder),
+            ]
+        );
+        assert_eq!(
+            events[4],
+            DomEvent::PropertyChanged {
+                referent: part,
+                name: "Anchored".into(),
+                old_value: None,
+                new_value: Some(true.into()),
+            }
+        );
+
+        assert!(dom.drain_journal().is_empty());
+    }
+
+    #[test]
+    fn destroy_order() {
+        let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let root = dom.root_ref();
+        let outer = dom.insert(
+            root,
+            InstanceBuilder::new("Folder").with_child(
+                InstanceBuilder::new("Folder").with_child(InstanceBuilder::new("Part")),
+            ),
+        );
+        let middle = dom.get_by_ref(outer).unwrap().children()[0];
+        let inner = dom.get_by_ref(middle).unwrap().children()[0];
+
+        dom.enable_journal();
+        dom.destroy(outer);
+
+        let destroyed: Vec<_> = dom
+            .disable_journal()
+            .into_iter()
+            .map(|event| match event {
+                DomEvent::Destroyed { referent, .. } => referent,
+                other => panic!("unexpected event {:?}", other),
+            })
+            .collect();
+        assert_eq!(destroyed, [inner, middle, outer]);
+
+        dom.insert(root, InstanceBuilder::new("Folder"));
+        assert!(dom.drain_journal().is_empty());
+    }
+
+    #[test]
+    fn observers() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let root = dom.root_ref();
+        dom.observe({
+            let seen = Arc::clone(&seen);
+            move |event| seen.lock().unwrap().push(event.clone())
+        });
+
+        let part = dom.insert(root, InstanceBuilder::new("Part"));
+        assert_eq!(seen.lock().unwrap().len(), 1);
+
+        // Mutable borrows are reported once the DOM is next changed.
+        dom.get_by_ref_mut(part).unwrap().class = "Model".into();
+        assert_eq!(seen.lock().unwrap().len(), 1);
+        dom.destroy(part);
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(
+            seen[1],
+            DomEvent::ClassChanged {
+                referent: part,
+                old_class: "Part".into(),
+                new_class: "Model".into(),
+            }
+        );
+        assert!(matches!(seen[2], DomEvent::Destroyed { .. }));
+
+        // Observing doesn't turn on the journal.
+        drop(seen);
+        assert!(dom.drain_journal().is_empty());
+    }
+}
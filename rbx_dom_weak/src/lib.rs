@@ -41,8 +41,22 @@
 
 #![deny(missing_docs)]
 
+mod diff;
 mod dom;
+mod index;
 mod instance;
+mod journal;
+mod patch;
+#[cfg(feature = "reflection")]
+mod reflection;
+mod refs;
+mod selector;
+#[cfg(feature = "math")]
+mod spatial;
+mod stable_id;
+mod transaction;
+#[cfg(feature = "reflection")]
+mod validate;
 mod viewer;
 
 pub use rbx_types as types;
@@ -51,11 +65,25 @@ pub use ahash::AHashMap;
 pub use ustr::{ustr, Ustr, UstrMap, UstrSet};
 
 pub use crate::{
+    diff::{Change, Diff, MatchStrategy},
     dom::WeakDom,
     instance::{Instance, InstanceBuilder},
+    journal::DomEvent,
+    patch::{Conflict, ConflictKind, Patch, PatchOperation},
+    refs::{InboundRefPolicy, Reference, TransferRefPolicy, TransferReport},
+    selector::{Selector, SelectorError},
     viewer::{DomViewer, ViewedInstance},
 };
 
+#[cfg(feature = "math")]
+pub use crate::spatial::BoundingBox;
+
+#[cfg(feature = "reflection")]
+pub use crate::{
+    reflection::ReflectedInstance,
+    validate::{ValidationIssue, ValidationIssueKind},
+};
+
 /// Helper trait that provides convenience methods for `AHashMap` and `UstrMap`.
 pub trait HashMapExt {
     /// Constructs an empty map.
@@ -0,0 +1,763 @@
+use ahash::AHashMap;
+use rbx_types::{Content, ContentType, Ref, Variant};
+use serde::{Deserialize, Serialize};
+use ustr::Ustr;
+
+use crate::{Change, Diff, Instance, InstanceBuilder, WeakDom};
+
+/// A serializable set of changes that can be applied to a [`WeakDom`].
+///
+/// Patches are created from a [`Diff`] with [`Patch::from_diff`]. Referents in
+/// a patch refer to instances in the diff's old DOM, except for instances
+/// that the patch inserts, which keep the referents they had in the new DOM.
+/// Applying a patch to a DOM with different referents, like a separately
+/// loaded copy of the old DOM, requires mapping them with
+/// [`Patch::rewrite_refs`] first.
+///
+/// Every operation records the state it expects to find, which is used to
+/// detect conflicts when applying it and to produce an [inverse][Patch::invert].
+///
+/// ## Example
+/// ```
+/// use rbx_dom_weak::{Diff, InstanceBuilder, MatchStrategy, Patch, WeakDom};
+///
+/// let mut old = WeakDom::new(
+///     InstanceBuilder::new("DataModel").with_child(InstanceBuilder::new("Folder")),
+/// );
+/// let new = WeakDom::new(
+///     InstanceBuilder::new("DataModel")
+///         .with_child(InstanceBuilder::new("Folder").with_property("Tag", "new")),
+/// );
+///
+/// let diff = Diff::new(&old, &new, MatchStrategy::NamePath);
+/// let patch = Patch::from_diff(&diff, &old, &new);
+///
+/// assert!(patch.apply(&mut old).is_empty());
+/// assert!(Diff::new(&old, &new, MatchStrategy::NamePath).is_empty());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Patch {
+    /// The operations in this patch, in the order they're applied.
+    pub operations: Vec<PatchOperation>,
+}
+
+/// A single operation contained in a [`Patch`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum PatchOperation {
+    /// Create a new instance with no children.
+    Insert {
+        /// The referent the new instance will have.
+        referent: Ref,
+        /// The parent of the new instance.
+        parent: Ref,
+        /// The class of the new instance.
+        class: Ustr,
+        /// The name of the new instance.
+        name: String,
+        /// The properties of the new instance, sorted by name.
+        properties: Vec<(Ustr, Variant)>,
+    },
+
+    /// Destroy an instance, which is expected to have no children.
+    Remove {
+        /// The instance to destroy.
+        referent: Ref,
+        /// The expected parent of the instance.
+        parent: Ref,
+        /// The expected class of the instance.
+        class: Ustr,
+        /// The expected name of the instance.
+        name: String,
+        /// The expected properties of the instance, sorted by name.
+        properties: Vec<(Ustr, Variant)>,
+    },
+
+    /// Change the parent of an instance.
+    Move {
+        /// The instance to move.
+        referent: Ref,
+        /// The expected current parent of the instance.
+        old_parent: Ref,
+        /// The new parent of the instance.
+        new_parent: Ref,
+    },
+
+    /// Change the name of an instance.
+    Rename {
+        /// The instance to rename.
+        referent: Ref,
+        /// The expected current name of the instance.
+        old_name: String,
+        /// The new name of the instance.
+        new_name: String,
+    },
+
+    /// Change the class of an instance.
+    SetClass {
+        /// The instance to change.
+        referent: Ref,
+        /// The expected current class of the instance.
+        old_class: Ustr,
+        /// The new class of the instance.
+        new_class: Ustr,
+    },
+
+    /// Add, change, or remove a property on an instance.
+    SetProperty {
+        /// The instance to change.
+        referent: Ref,
+        /// The name of the property.
+        name: Ustr,
+        /// The expected current value, or `None` if it should be absent.
+        old_value: Option<Variant>,
+        /// The new value, or `None` to remove the property.
+        new_value: Option<Variant>,
+    },
+}
+
+impl PatchOperation {
+    /// Returns the referent of the instance this operation acts on.
+    pub fn referent(&self) -> Ref {
+        match self {
+            PatchOperation::Insert { referent, .. }
+            | PatchOperation::Remove { referent, .. }
+            | PatchOperation::Move { referent, .. }
+            | PatchOperation::Rename { referent, .. }
+            | PatchOperation::SetClass { referent, .. }
+            | PatchOperation::SetProperty { referent, .. } => *referent,
+        }
+    }
+
+    fn invert(self) -> PatchOperation {
+        match self {
+            PatchOperation::Insert {
+                referent,
+                parent,
+                class,
+                name,
+                properties,
+            } => PatchOperation::Remove {
+                referent,
+                parent,
+                class,
+                name,
+                properties,
+            },
+            PatchOperation::Remove {
+                referent,
+                parent,
+                class,
+                name,
+                properties,
+            } => PatchOperation::Insert {
+                referent,
+                parent,
+                class,
+                name,
+                properties,
+            },
+            PatchOperation::Move {
+                referent,
+                old_parent,
+                new_parent,
+            } => PatchOperation::Move {
+                referent,
+                old_parent: new_parent,
+                new_parent: old_parent,
+            },
+            PatchOperation::Rename {
+                referent,
+                old_name,
+                new_name,
+            } => PatchOperation::Rename {
+                referent,
+                old_name: new_name,
+                new_name: old_name,
+            },
+            PatchOperation::SetClass {
+                referent,
+                old_class,
+                new_class,
+            } => PatchOperation::SetClass {
+                referent,
+                old_class: new_class,
+                new_class: old_class,
+            },
+            PatchOperation::SetProperty {
+                referent,
+                name,
+                old_value,
+                new_value,
+            } => PatchOperation::SetProperty {
+                referent,
+                name,
+                old_value: new_value,
+                new_value: old_value,
+            },
+        }
+    }
+
+    fn rewrite_refs(&mut self, map: &AHashMap<Ref, Ref>) {
+        let rewrite = |referent: &mut Ref| {
+            if let Some(new_ref) = map.get(referent) {
+                *referent = *new_ref;
+            }
+        };
+
+        match self {
+            PatchOperation::Insert {
+                referent,
+                parent,
+                properties,
+                ..
+            }
+            | PatchOperation::Remove {
+                referent,
+                parent,
+                properties,
+                ..
+            } => {
+                rewrite(referent);
+                rewrite(parent);
+                for (_, value) in properties {
+                    rewrite_value_refs(value, map);
+                }
+            }
+            PatchOperation::Move {
+                referent,
+                old_parent,
+                new_parent,
+            } => {
+                rewrite(referent);
+                rewrite(old_parent);
+                rewrite(new_parent);
+            }
+            PatchOperation::Rename { referent, .. } | PatchOperation::SetClass { referent, .. } => {
+                rewrite(referent)
+            }
+            PatchOperation::SetProperty {
+                referent,
+                old_value,
+                new_value,
+                ..
+            } => {
+                rewrite(referent);
+                for value in old_value.iter_mut().chain(new_value.iter_mut()) {
+                    rewrite_value_refs(value, map);
+                }
+            }
+        }
+    }
+}
+
+/// Describes why a [`PatchOperation`] could not be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConflictKind {
+    /// The instance the operation acts on does not exist.
+    MissingInstance,
+    /// The parent the operation needs does not exist.
+    MissingParent,
+    /// An instance being inserted already exists.
+    AlreadyExists,
+    /// An instance being removed still has children.
+    HasChildren,
+    /// The instance's parent, name, class, or properties don't match what the
+    /// operation expected.
+    Mismatch,
+}
+
+/// An operation from a [`Patch`] that could not be applied, returned from
+/// [`Patch::apply`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// The index of the operation in [`Patch::operations`].
+    pub index: usize,
+    /// The operation that could not be applied.
+    pub operation: PatchOperation,
+    /// Why the operation could not be applied.
+    pub kind: ConflictKind,
+}
+
+impl Patch {
+    /// Creates a patch that turns `old` into `new`, using the instance pairing
+    /// from a [`Diff`] of the two DOMs.
+    ///
+    /// Operations are ordered so that inserts come first, top-down, followed
+    /// by moves, renames, class and property changes, and finally removals,
+    /// bottom-up.
+    pub fn from_diff(diff: &Diff, old: &WeakDom, new: &WeakDom) -> Patch {
+        let new_to_old: AHashMap<Ref, Ref> = diff
+            .matched()
+            .map(|(old_ref, new_ref)| (new_ref, old_ref))
+            .collect();
+
+        let mut inserts = Vec::new();
+        let mut updates = Vec::new();
+        let mut removes = Vec::new();
+
+        for change in diff.changes() {
+            match change {
+                Change::Added { new: new_ref, .. } => {
+                    let mut operation = instance_operation(new.get_by_ref(*new_ref).unwrap(), true);
+                    operation.rewrite_refs(&new_to_old);
+                    inserts.push(operation);
+                }
+                Change::Removed { old: old_ref, .. } => {
+                    removes.push(instance_operation(old.get_by_ref(*old_ref).unwrap(), false));
+                }
+                Change::Moved {
+                    old: old_ref,
+                    old_parent,
+                    new_parent,
+                    ..
+                } => updates.push(PatchOperation::Move {
+                    referent: *old_ref,
+                    old_parent: *old_parent,
+                    new_parent: new_to_old.get(new_parent).copied().unwrap_or(*new_parent),
+                }),
+                Change::Renamed {
+                    old: old_ref,
+                    old_name,
+                    new_name,
+                    ..
+                } => updates.push(PatchOperation::Rename {
+                    referent: *old_ref,
+                    old_name: old_name.clone(),
+                    new_name: new_name.clone(),
+                }),
+                Change::ClassChanged {
+                    old: old_ref,
+                    old_class,
+                    new_class,
+                    ..
+                } => updates.push(PatchOperation::SetClass {
+                    referent: *old_ref,
+                    old_class: *old_class,
+                    new_class: *new_class,
+                }),
+                Change::PropertyChanged {
+                    old: old_ref,
+                    name,
+                    old_value,
+                    new_value,
+                    ..
+                } => {
+                    let mut new_value = new_value.clone();
+                    if let Some(value) = &mut new_value {
+                        rewrite_value_refs(value, &new_to_old);
+                    }
+
+                    updates.push(PatchOperation::SetProperty {
+                        referent: *old_ref,
+                        name: *name,
+                        old_value: old_value.clone(),
+                        new_value,
+                    });
+                }
+            }
+        }
+
+        // Diffs list removed instances top-down, but children need to be
+        // removed before their parents.
+        removes.reverse();
+
+        let mut operations = inserts;
+        operations.extend(updates);
+        operations.extend(removes);
+
+        Patch { operations }
+    }
+
+    /// Returns true if this patch contains no operations.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Creates a patch that undoes this patch when applied to a DOM that this
+    /// patch was applied to.
+    pub fn invert(&self) -> Patch {
+        Patch {
+            operations: self
+                .operations
+                .iter()
+                .rev()
+                .cloned()
+                .map(PatchOperation::invert)
+                .collect(),
+        }
+    }
+
+    /// Replaces every referent in this patch that's a key in `map` with its
+    /// value, including referents in `Ref` properties.
+    pub fn rewrite_refs(&mut self, map: &AHashMap<Ref, Ref>) {
+        for operation in &mut self.operations {
+            operation.rewrite_refs(map);
+        }
+    }
+
+    /// Applies this patch to the given DOM.
+    ///
+    /// Each operation is checked against the current state of the DOM before
+    /// it's applied. Operations that don't match are skipped and returned as
+    /// [`Conflict`]s; every other operation is applied.
+    #[must_use = "conflicting operations are skipped and should be handled"]
+    pub fn apply(&self, dom: &mut WeakDom) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+
+        for (index, operation) in self.operations.iter().enumerate() {
+            if let Err(kind) = apply_operation(dom, operation) {
+                conflicts.push(Conflict {
+                    index,
+                    operation: operation.clone(),
+                    kind,
+                });
+            }
+        }
+
+        conflicts
+    }
+}
+
+/// Creates an `Insert` or `Remove` operation that captures everything about
+/// the given instance except for its children.
+fn instance_operation(instance: &Instance, insert: bool) -> PatchOperation {
+    let mut properties: Vec<(Ustr, Variant)> = instance
+        .properties
+        .iter()
+        .map(|(name, value)| (*name, value.clone()))
+        .collect();
+    properties.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+    if insert {
+        PatchOperation::Insert {
+            referent: instance.referent(),
+            parent: instance.parent(),
+            class: instance.class,
+            name: instance.name.clone(),
+            properties,
+        }
+    } else {
+        PatchOperation::Remove {
+            referent: instance.referent(),
+            parent: instance.parent(),
+            class: instance.class,
+            name: instance.name.clone(),
+            properties,
+        }
+    }
+}
+
+fn rewrite_value_refs(value: &mut Variant, map: &AHashMap<Ref, Ref>) {
+    match value {
+        Variant::Ref(referent) => {
+            if let Some(new_ref) = map.get(referent) {
+                *referent = *new_ref;
+            }
+        }
+        Variant::Content(content) => {
+            if let ContentType::Object(referent) = content.value() {
+                if let Some(new_ref) = map.get(referent) {
+                    *content = Content::from_referent(*new_ref);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn properties_match(instance: &Instance, properties: &[(Ustr, Variant)]) -> bool {
+    instance.properties.len() == properties.len()
+        && properties
+            .iter()
+            .all(|(name, value)| instance.properties.get(name) == Some(value))
+}
+
+fn parent_exists(dom: &WeakDom, parent: Ref) -> bool {
+    parent.is_none() || dom.get_by_ref(parent).is_some()
+}
+
+fn apply_operation(dom: &mut WeakDom, operation: &PatchOperation) -> Result<(), ConflictKind> {
+    match operation {
+        PatchOperation::Insert {
+            referent,
+            parent,
+            class,
+            name,
+            properties,
+        } => {
+            if dom.get_by_ref(*referent).is_some() {
+                return Err(ConflictKind::AlreadyExists);
+            }
+            if !parent_exists(dom, *parent) {
+                return Err(ConflictKind::MissingParent);
+            }
+
+            let builder = InstanceBuilder::new(*class)
+                .with_referent(*referent)
+                .with_name(name.clone())
+                .with_properties(properties.iter().cloned());
+            dom.insert(*parent, builder);
+        }
+        PatchOperation::Remove {
+            referent,
+            parent,
+            class,
+            name,
+            properties,
+        } => {
+            let instance = dom
+                .get_by_ref(*referent)
+                .ok_or(ConflictKind::MissingInstance)?;
+
+            if !instance.children().is_empty() {
+                return Err(ConflictKind::HasChildren);
+            }
+            if instance.parent() != *parent
+                || instance.class != *class
+                || instance.name != *name
+                || !properties_match(instance, properties)
+            {
+                return Err(ConflictKind::Mismatch);
+            }
+
+            dom.destroy(*referent);
+        }
+        PatchOperation::Move {
+            referent,
+            old_parent,
+            new_parent,
+        } => {
+            let instance = dom
+                .get_by_ref(*referent)
+                .ok_or(ConflictKind::MissingInstance)?;
+
+            if instance.parent() != *old_parent {
+                return Err(ConflictKind::Mismatch);
+            }
+            if !parent_exists(dom, *new_parent) {
+                return Err(ConflictKind::MissingParent);
+            }
+            // Moving an instance into itself or one of its descendants would
+            // detach it from the tree.
+            if new_parent.is_some()
+                && dom
+                    .ancestors_of(*new_parent)
+                    .any(|a| a.referent() == *referent)
+            {
+                return Err(ConflictKind::Mismatch);
+            }
+
+            dom.transfer_within(*referent, *new_parent);
+        }
+        PatchOperation::Rename {
+            referent,
+            old_name,
+            new_name,
+        } => {
+            let instance = dom
+                .get_by_ref_mut(*referent)
+                .ok_or(ConflictKind::MissingInstance)?;
+
+            if instance.name != *old_name {
+                return Err(ConflictKind::Mismatch);
+            }
+
+            instance.name = new_name.clone();
+        }
+        PatchOperation::SetClass {
+            referent,
+            old_class,
+            new_class,
+        } => {
+            let instance = dom
+                .get_by_ref_mut(*referent)
+                .ok_or(ConflictKind::MissingInstance)?;
+
+            if instance.class != *old_class {
+                return Err(ConflictKind::Mismatch);
+            }
+
+            instance.class = *new_class;
+        }
+        PatchOperation::SetProperty {
+            referent,
+            name,
+            old_value,
+            new_value,
+        } => {
+            let instance = dom
+                .get_by_ref_mut(*referent)
+                .ok_or(ConflictKind::MissingInstance)?;
+
+            if instance.properties.get(name) != old_value.as_ref() {
+                return Err(ConflictKind::Mismatch);
+            }
+
+            match new_value {
+                Some(value) => {
+                    instance.properties.insert(*name, value.clone());
+                }
+                None => {
+                    instance.properties.remove(name);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::MatchStrategy;
+
+    fn find(dom: &WeakDom, name: &str) -> Ref {
+        dom.descendants()
+            .find(|instance| instance.name == name)
+            .unwrap()
+            .referent()
+    }
+
+    fn old_dom() -> WeakDom {
+        let target = InstanceBuilder::new("Part").with_name("Target");
+        let target_ref = target.referent();
+
+        WeakDom::new(
+            InstanceBuilder::new("DataModel").with_children([
+                InstanceBuilder::new("Folder")
+                    .with_name("Folder")
+                    .with_child(target)
+                    .with_child(
+                        InstanceBuilder::new("ObjectValue")
+                            .with_name("Pointer")
+                            .with_property("Value", target_ref),
+                    ),
+                InstanceBuilder::new("Model")
+                    .with_name("Doomed")
+                    .with_child(InstanceBuilder::new("Part").with_name("DoomedChild")),
+                InstanceBuilder::new("StringValue")
+                    .with_name("Label")
+                    .with_property("Value", "old"),
+            ]),
+        )
+    }
+
+    fn new_dom() -> WeakDom {
+        let fresh = InstanceBuilder::new("Part").with_name("Fresh");
+        let fresh_ref = fresh.referent();
+
+        WeakDom::new(
+            InstanceBuilder::new("DataModel").with_children([
+                InstanceBuilder::new("Folder")
+                    .with_name("Folder")
+                    .with_child(
+                        InstanceBuilder::new("Model")
+                            .with_name("Wrapper")
+                            .with_child(InstanceBuilder::new("Part").with_name("Target")),
+                    )
+                    .with_child(
+                        InstanceBuilder::new("ObjectValue")
+                            .with_name("Pointer")
+                            .with_property("Value", fresh_ref),
+                    ),
+                fresh,
+                InstanceBuilder::new("StringValue")
+                    .with_name("Label")
+                    .with_property("Value", "new"),
+            ]),
+        )
+    }
+
+    #[test]
+    fn apply_and_invert() {
+        let mut dom = old_dom();
+        let new = new_dom();
+
+        let diff = Diff::new(&dom, &new, MatchStrategy::Referent);
+        let patch = Patch::from_diff(&diff, &dom, &new);
+
+        assert_eq!(patch.apply(&mut dom), Vec::new());
+        assert!(Diff::new(&dom, &new, MatchStrategy::NamePath).is_empty());
+
+        // Refs set by the patch should point at instances it inserted.
+        let pointer = dom.get_by_ref(find(&dom, "Pointer")).unwrap();
+        assert_eq!(
+            pointer.properties.get(&"Value".into()),
+            Some(&Variant::Ref(find(&dom, "Fresh")))
+        );
+
+        assert_eq!(patch.invert().apply(&mut dom), Vec::new());
+        assert!(Diff::new(&dom, &old_dom(), MatchStrategy::NamePath).is_empty());
+    }
+
+    #[test]
+    fn name_path_patch() {
+        let mut dom = old_dom();
+        let new = new_dom();
+
+        let diff = Diff::new(&dom, &new, MatchStrategy::NamePath);
+        let patch = Patch::from_diff(&diff, &dom, &new);
+
+        assert_eq!(patch.apply(&mut dom), Vec::new());
+        assert!(Diff::new(&dom, &new, MatchStrategy::NamePath).is_empty());
+    }
+
+    #[test]
+    fn conflicts() {
+        let base = old_dom();
+        let new = new_dom();
+
+        let diff = Diff::new(&base, &new, MatchStrategy::Referent);
+        let patch = Patch::from_diff(&diff, &base, &new);
+
+        let mut dom = old_dom();
+        let label = find(&dom, "Label");
+        let mut map = AHashMap::new();
+        map.insert(find(&base, "Label"), label);
+
+        let mut label_patch = Patch {
+            operations: patch
+                .operations
+                .iter()
+                .filter(|operation| operation.referent() == find(&base, "Label"))
+                .cloned()
+                .collect(),
+        };
+        label_patch.rewrite_refs(&map);
+
+        // Someone else changed the property in the meantime.
+        dom.get_by_ref_mut(label)
+            .unwrap()
+            .properties
+            .insert("Value".into(), "theirs".into());
+
+        let conflicts = label_patch.apply(&mut dom);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, ConflictKind::Mismatch);
+        assert_eq!(
+            dom.get_by_ref(label)
+                .unwrap()
+                .properties
+                .get(&"Value".into()),
+            Some(&Variant::from("theirs"))
+        );
+    }
+
+    #[test]
+    fn serialize() {
+        let base = old_dom();
+        let new = new_dom();
+
+        let diff = Diff::new(&base, &new, MatchStrategy::Referent);
+        let patch = Patch::from_diff(&diff, &base, &new);
+
+        let encoded = serde_json::to_string(&patch).unwrap();
+        let decoded: Patch = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded, patch);
+    }
+}
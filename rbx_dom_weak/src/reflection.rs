@@ -0,0 +1,179 @@
+use rbx_reflection::{ClassDescriptor, PropertyKind, ReflectionDatabase};
+use rbx_types::Variant;
+
+use crate::Instance;
+
+impl Instance {
+    /// Pairs this instance with a reflection database, which can answer
+    /// questions about the instance that depend on its class.
+    #[inline]
+    pub fn reflect<'a>(&'a self, database: &'a ReflectionDatabase<'a>) -> ReflectedInstance<'a> {
+        ReflectedInstance {
+            instance: self,
+            database,
+            class: database.classes.get(self.class.as_str()),
+        }
+    }
+}
+
+/// An [`Instance`] viewed through a [`ReflectionDatabase`], created with
+/// [`Instance::reflect`].
+///
+/// Classes and properties that the database doesn't know about are treated
+/// as if they have no superclasses, aliases, or defaults.
+#[derive(Debug, Clone, Copy)]
+pub struct ReflectedInstance<'a> {
+    instance: &'a Instance,
+    database: &'a ReflectionDatabase<'a>,
+    class: Option<&'a ClassDescriptor<'a>>,
+}
+
+impl<'a> ReflectedInstance<'a> {
+    /// Returns the underlying instance.
+    #[inline]
+    pub fn instance(&self) -> &'a Instance {
+        self.instance
+    }
+
+    /// Returns the descriptor for this instance's class, if the database
+    /// knows about it.
+    #[inline]
+    pub fn class_descriptor(&self) -> Option<&'a ClassDescriptor<'a>> {
+        self.class
+    }
+
+    /// Returns whether this instance is of the given class or one of its
+    /// subclasses, like Roblox's `Instance:IsA`.
+    pub fn is_a(&self, class: &str) -> bool {
+        match self.class {
+            Some(descriptor) => self
+                .database
+                .superclasses_iter(descriptor)
+                .any(|superclass| superclass.name == class),
+            None => self.instance.class == class,
+        }
+    }
+
+    /// Returns the canonical name of the given property on this instance's
+    /// class, resolving aliases like `size` to `Size`. Returns `None` if the
+    /// property isn't known.
+    pub fn canonical_property_name(&self, name: &str) -> Option<&'a str> {
+        let descriptor = self
+            .database
+            .superclasses_iter(self.class?)
+            .find_map(|class| class.properties.get(name))?;
+
+        match &descriptor.kind {
+            PropertyKind::Alias { alias_for } => Some(alias_for),
+            _ => Some(descriptor.name),
+        }
+    }
+
+    /// Returns the value stored on this instance for the given property,
+    /// accepting either its canonical name or an alias.
+    pub fn property(&self, name: &str) -> Option<&'a Variant> {
+        let canonical = self.canonical_property_name(name).unwrap_or(name);
+
+        self.instance
+            .properties
+            .get(&canonical.into())
+            .or_else(|| self.instance.properties.get(&name.into()))
+    }
+
+    /// Returns the value of the given property as Roblox would see it: the
+    /// value stored on this instance if there is one, or the default value for
+    /// this instance's class otherwise.
+    pub fn effective_property(&self, name: &str) -> Option<&'a Variant> {
+        self.property(name).or_else(|| {
+            let canonical = self.canonical_property_name(name).unwrap_or(name);
+            self.database.find_default_property(self.class?, canonical)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rbx_reflection::{DataType, PropertyDescriptor};
+    use rbx_types::VariantType;
+
+    use crate::{InstanceBuilder, WeakDom};
+
+    fn database() -> ReflectionDatabase<'static> {
+        let mut database = ReflectionDatabase::new();
+
+        let instance = ClassDescriptor::new("Instance");
+
+        let mut base_part = ClassDescriptor::new("BasePart");
+        base_part.superclass = Some("Instance");
+        base_part.properties.insert(
+            "Anchored",
+            PropertyDescriptor::new("Anchored", DataType::Value(VariantType::Bool)),
+        );
+        base_part.properties.insert(
+            "Size",
+            PropertyDescriptor::new("Size", DataType::Value(VariantType::Vector3)),
+        );
+        let mut size_alias = PropertyDescriptor::new("size", DataType::Value(VariantType::Vector3));
+        size_alias.kind = PropertyKind::Alias { alias_for: "Size" };
+        base_part.properties.insert("size", size_alias);
+        base_part
+            .default_properties
+            .insert("Anchored", Variant::Bool(false));
+
+        let mut part = ClassDescriptor::new("Part");
+        part.superclass = Some("BasePart");
+
+        database.classes.insert("Instance", instance);
+        database.classes.insert("BasePart", base_part);
+        database.classes.insert("Part", part);
+
+        database
+    }
+
+    #[test]
+    fn is_a() {
+        let database = database();
+        let part = InstanceBuilder::new("Part");
+        let dom = WeakDom::new(part);
+        let reflected = dom.root().reflect(&database);
+
+        assert!(reflected.is_a("Part"));
+        assert!(reflected.is_a("BasePart"));
+        assert!(reflected.is_a("Instance"));
+        assert!(!reflected.is_a("Model"));
+
+        let unknown = WeakDom::new(InstanceBuilder::new("Mystery"));
+        assert!(unknown.root().reflect(&database).is_a("Mystery"));
+        assert!(!unknown.root().reflect(&database).is_a("Instance"));
+    }
+
+    #[test]
+    fn properties() {
+        let database = database();
+        let dom = WeakDom::new(
+            InstanceBuilder::new("Part")
+                .with_property("Size", rbx_types::Vector3::new(1.0, 2.0, 3.0))
+                .with_property("Custom", 5i32),
+        );
+        let reflected = dom.root().reflect(&database);
+
+        assert_eq!(reflected.canonical_property_name("size"), Some("Size"));
+        assert_eq!(reflected.canonical_property_name("Size"), Some("Size"));
+        assert_eq!(reflected.canonical_property_name("Custom"), None);
+
+        assert_eq!(
+            reflected.property("size"),
+            Some(&Variant::Vector3(rbx_types::Vector3::new(1.0, 2.0, 3.0)))
+        );
+        assert_eq!(reflected.property("Custom"), Some(&Variant::Int32(5)));
+
+        assert_eq!(reflected.property("Anchored"), None);
+        assert_eq!(
+            reflected.effective_property("Anchored"),
+            Some(&Variant::Bool(false))
+        );
+        assert_eq!(reflected.effective_property("Missing"), None);
+    }
+}
@@ -0,0 +1,436 @@
+use ahash::{AHashMap, AHashSet};
+use rbx_types::{Content, ContentType, Ref, Variant};
+use ustr::Ustr;
+
+use crate::{Instance, WeakDom};
+
+/// A property on one instance that refers to another instance, either with a
+/// [`Variant::Ref`] or with a [`Content`] that holds an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reference {
+    /// The instance that has the property.
+    pub source: Ref,
+    /// The name of the property.
+    pub property: Ustr,
+    /// The instance the property refers to.
+    pub target: Ref,
+}
+
+/// What [`WeakDom::destroy_with_policy`] should do with properties outside of
+/// the destroyed instances that refer to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InboundRefPolicy {
+    /// Leave the properties alone, so they refer to instances that no longer
+    /// exist.
+    Keep,
+    /// Set the properties to the none Ref, or to an empty `Content`.
+    Nullify,
+}
+
+/// What [`WeakDom::transfer_with_policy`] should do with properties on the
+/// transferred instances that refer to instances that weren't transferred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TransferRefPolicy<'a> {
+    /// Leave the properties alone.
+    Keep,
+    /// Set the properties to the none Ref, or to an empty `Content`.
+    Nullify,
+    /// Replace each referent using the given table, which usually maps
+    /// instances in the source DOM to their counterparts in the destination
+    /// DOM. Referents that aren't in the table are set to none.
+    Map(&'a AHashMap<Ref, Ref>),
+}
+
+/// The properties that referred across the boundary of a transferred subtree,
+/// returned by [`WeakDom::transfer_with_policy`]. Each list is in no
+/// particular order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransferReport {
+    /// Properties on transferred instances that referred to instances that
+    /// stayed behind, with the targets they had before the transfer.
+    pub outbound: Vec<Reference>,
+    /// Properties on instances that stayed behind that referred to
+    /// transferred instances. These are left unchanged.
+    pub inbound: Vec<Reference>,
+}
+
+impl WeakDom {
+    /// Returns every property in the DOM that refers to `target`, in no
+    /// particular order.
+    ///
+    /// This requires a scan of the whole DOM unless indexes are enabled with
+    /// [`WeakDom::enable_indexes`].
+    pub fn find_references_to(&self, target: Ref) -> Vec<Reference> {
+        if target.is_none() {
+            return Vec::new();
+        }
+
+        match &self.index {
+            Some(index) => index
+                .refs_to(target, self.pending_instance())
+                .into_iter()
+                .map(|(source, property)| Reference {
+                    source,
+                    property,
+                    target,
+                })
+                .collect(),
+            None => self.scan_references(|referent| referent == target),
+        }
+    }
+
+    /// Destroys the instance with the given referent like
+    /// [`WeakDom::destroy`], then handles any properties on the remaining
+    /// instances that referred to it or its descendants according to
+    /// `policy`.
+    ///
+    /// Returns those properties in no particular order, so that callers can
+    /// report them.
+    ///
+    /// ## Panics
+    /// Panics if `referent` does not refer to an instance in the DOM or refers
+    /// to the root instance.
+    pub fn destroy_with_policy(
+        &mut self,
+        referent: Ref,
+        policy: InboundRefPolicy,
+    ) -> Vec<Reference> {
+        if self.get_by_ref(referent).is_none() {
+            panic!("cannot destroy an instance that does not exist");
+        }
+
+        let subtree = self.subtree_of(referent);
+        let inbound = self.inbound_references(&subtree);
+
+        self.destroy(referent);
+
+        if policy == InboundRefPolicy::Nullify {
+            for reference in &inbound {
+                self.retarget(reference, Ref::none());
+            }
+        }
+
+        inbound
+    }
+
+    /// Moves the instance with the given referent to `dest` like
+    /// [`WeakDom::transfer`], then handles any properties on the moved
+    /// instances that refer to instances outside of them according to
+    /// `policy`.
+    ///
+    /// Refs between the moved instances are always preserved, since
+    /// transferring doesn't change referents.
+    ///
+    /// ## Panics
+    /// Panics if `referent` does not refer to an instance in `self`, if
+    /// `dest_parent_ref` does not refer to an instance in `dest`, if
+    /// `referent` refers to the root instance, or if either `WeakDom` has a
+    /// transaction in progress.
+    pub fn transfer_with_policy(
+        &mut self,
+        referent: Ref,
+        dest: &mut WeakDom,
+        dest_parent_ref: Ref,
+        policy: TransferRefPolicy<'_>,
+    ) -> TransferReport {
+        if self.get_by_ref(referent).is_none() {
+            panic!("cannot transfer an instance that does not exist");
+        }
+
+        let subtree = self.subtree_of(referent);
+        let inbound = self.inbound_references(&subtree);
+        let outbound: Vec<Reference> = subtree
+            .iter()
+            .flat_map(|&source| {
+                outgoing_refs(&self.instances[&source]).map(move |(property, target)| Reference {
+                    source,
+                    property,
+                    target,
+                })
+            })
+            .filter(|reference| !subtree.contains(&reference.target))
+            .collect();
+
+        self.transfer(referent, dest, dest_parent_ref);
+
+        for reference in &outbound {
+            let target = match policy {
+                TransferRefPolicy::Keep => continue,
+                TransferRefPolicy::Nullify => Ref::none(),
+                TransferRefPolicy::Map(table) => table
+                    .get(&reference.target)
+                    .copied()
+                    .unwrap_or_else(Ref::none),
+            };
+            dest.retarget(reference, target);
+        }
+
+        TransferReport { outbound, inbound }
+    }
+
+    /// Returns the referents of an instance and all of its descendants.
+    fn subtree_of(&self, referent: Ref) -> AHashSet<Ref> {
+        std::iter::once(referent)
+            .chain(
+                self.descendants_of(referent)
+                    .map(|instance| instance.referent()),
+            )
+            .collect()
+    }
+
+    /// Returns every property outside of `subtree` that refers to an instance
+    /// inside of it.
+    fn inbound_references(&self, subtree: &AHashSet<Ref>) -> Vec<Reference> {
+        let references = if self.index.is_some() {
+            subtree
+                .iter()
+                .flat_map(|&target| self.find_references_to(target))
+                .collect()
+        } else {
+            self.scan_references(|target| subtree.contains(&target))
+        };
+
+        references
+            .into_iter()
+            .filter(|reference| !subtree.contains(&reference.source))
+            .collect()
+    }
+
+    /// Points the property described by `reference` at `target`, keeping the
+    /// kind of value it had.
+    fn retarget(&mut self, reference: &Reference, target: Ref) {
+        let instance = match self.get_by_ref_mut(reference.source) {
+            Some(instance) => instance,
+            None => return,
+        };
+
+        let value = match instance.properties.get(&reference.property) {
+            Some(Variant::Ref(_)) => Variant::Ref(target),
+            Some(Variant::Content(_)) if target.is_some() => {
+                Variant::Content(Content::from_referent(target))
+            }
+            Some(Variant::Content(_)) => Variant::Content(Content::none()),
+            _ => return,
+        };
+        instance.properties.insert(reference.property, value);
+    }
+
+    fn scan_references(&self, mut is_target: impl FnMut(Ref) -> bool) -> Vec<Reference> {
+        self.instances
+            .values()
+            .flat_map(|instance| {
+                outgoing_refs(instance).map(move |(property, target)| Reference {
+                    source: instance.referent(),
+                    property,
+                    target,
+                })
+            })
+            .filter(|reference| is_target(reference.target))
+            .collect()
+    }
+}
+
+/// Returns the name and target of every property on the instance that refers
+/// to another instance.
+pub(crate) fn outgoing_refs(instance: &Instance) -> impl Iterator<Item = (Ustr, Ref)> + '_ {
+    instance.properties.iter().filter_map(|(name, value)| {
+        let target = match value {
+            Variant::Ref(target) => *target,
+            Variant::Content(content) => match content.value() {
+                ContentType::Object(target) => *target,
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        target.is_some().then_some((*name, target))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::InstanceBuilder;
+
+    fn sorted(mut references: Vec<Reference>) -> Vec<Reference> {
+        references.sort_unstable_by_key(|reference| {
+            (reference.source.to_string(), reference.property.to_string())
+        });
+        references
+    }
+
+    /// Builds a DOM with a model containing two parts, a weld between them
+    /// outside of the model, and a decal whose texture is the model.
+    fn dom() -> (WeakDom, Ref, Ref, Ref, Ref) {
+        let part0 = InstanceBuilder::new("Part");
+        let part1 = InstanceBuilder::new("Part");
+        let model = InstanceBuilder::new("Model").with_property("PrimaryPart", part0.referent());
+        let model_ref = model.referent();
+
+        let weld = InstanceBuilder::new("Weld")
+            .with_property("Part0", part0.referent())
+            .with_property("Part1", part1.referent());
+        let decal = InstanceBuilder::new("Decal")
+            .with_property("TextureContent", Content::from_referent(model_ref));
+        let (weld_ref, decal_ref, part0_ref) =
+            (weld.referent(), decal.referent(), part0.referent());
+
+        let dom = WeakDom::new(InstanceBuilder::new("DataModel").with_children([
+            model.with_children([part0, part1]),
+            weld,
+            decal,
+        ]));
+
+        (dom, model_ref, part0_ref, weld_ref, decal_ref)
+    }
+
+    #[test]
+    fn find_references() {
+        let (mut dom, model, part0, weld, decal) = dom();
+
+        let expected = sorted(vec![
+            Reference {
+                source: model,
+                property: "PrimaryPart".into(),
+                target: part0,
+            },
+            Reference {
+                source: weld,
+                property: "Part0".into(),
+                target: part0,
+            },
+        ]);
+        assert_eq!(sorted(dom.find_references_to(part0)), expected);
+        assert_eq!(
+            dom.find_references_to(model),
+            [Reference {
+                source: decal,
+                property: "TextureContent".into(),
+                target: model,
+            }]
+        );
+
+        dom.enable_indexes();
+        assert_eq!(sorted(dom.find_references_to(part0)), expected);
+
+        // The index notices changes made through `get_by_ref_mut`.
+        dom.get_by_ref_mut(weld)
+            .unwrap()
+            .properties
+            .insert("Part0".into(), Ref::none().into());
+        assert_eq!(dom.find_references_to(part0).len(), 1);
+        dom.destroy(model);
+        assert!(dom.find_references_to(part0).is_empty());
+        assert!(dom.find_references_to(Ref::none()).is_empty());
+    }
+
+    #[test]
+    fn destroy_keep() {
+        let (mut dom, model, _, weld, decal) = dom();
+
+        let inbound = dom.destroy_with_policy(model, InboundRefPolicy::Keep);
+        let sources: AHashSet<_> = inbound.iter().map(|reference| reference.source).collect();
+        assert_eq!(inbound.len(), 3);
+        assert_eq!(sources, [weld, decal].iter().copied().collect());
+
+        let weld = dom.get_by_ref(weld).unwrap();
+        assert!(
+            matches!(weld.properties.get(&"Part0".into()), Some(Variant::Ref(r)) if r.is_some())
+        );
+    }
+
+    #[test]
+    fn destroy_nullify() {
+        for indexed in [false, true] {
+            let (mut dom, model, _, weld, decal) = dom();
+            if indexed {
+                dom.enable_indexes();
+            }
+
+            let inbound = dom.destroy_with_policy(model, InboundRefPolicy::Nullify);
+            assert_eq!(inbound.len(), 3);
+
+            let weld = dom.get_by_ref(weld).unwrap();
+            assert_eq!(
+                weld.properties.get(&"Part0".into()),
+                Some(&Variant::Ref(Ref::none()))
+            );
+            assert_eq!(
+                weld.properties.get(&"Part1".into()),
+                Some(&Variant::Ref(Ref::none()))
+            );
+            assert_eq!(
+                dom.get_by_ref(decal)
+                    .unwrap()
+                    .properties
+                    .get(&"TextureContent".into()),
+                Some(&Variant::Content(Content::none()))
+            );
+
+            for instance in dom.descendants() {
+                assert_eq!(outgoing_refs(instance).count(), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn transfer_policies() {
+        let (mut source, model, part0, weld, decal) = dom();
+        let mut dest = WeakDom::new(InstanceBuilder::new("Folder"));
+        let dest_root = dest.root_ref();
+
+        // Moving the weld leaves both of its parts behind.
+        let report =
+            source.transfer_with_policy(weld, &mut dest, dest_root, TransferRefPolicy::Keep);
+        assert_eq!(report.outbound.len(), 2);
+        assert!(report.inbound.is_empty());
+        assert_eq!(
+            dest.get_by_ref(weld)
+                .unwrap()
+                .properties
+                .get(&"Part0".into()),
+            Some(&Variant::Ref(part0))
+        );
+
+        let replacement = dest.insert(dest_root, InstanceBuilder::new("Part"));
+        let table: AHashMap<Ref, Ref> = [(part0, replacement)].iter().copied().collect();
+        dest.transfer_with_policy(weld, &mut source, model, TransferRefPolicy::Keep);
+        let report =
+            source.transfer_with_policy(weld, &mut dest, dest_root, TransferRefPolicy::Map(&table));
+        assert_eq!(report.outbound.len(), 2);
+
+        let properties = &dest.get_by_ref(weld).unwrap().properties;
+        assert_eq!(
+            properties.get(&"Part0".into()),
+            Some(&Variant::Ref(replacement))
+        );
+        assert_eq!(
+            properties.get(&"Part1".into()),
+            Some(&Variant::Ref(Ref::none()))
+        );
+
+        // Moving the model keeps refs within it and reports the decal that
+        // pointed at it.
+        let report =
+            source.transfer_with_policy(model, &mut dest, dest_root, TransferRefPolicy::Nullify);
+        assert!(report.outbound.is_empty());
+        assert_eq!(
+            report.inbound,
+            [Reference {
+                source: decal,
+                property: "TextureContent".into(),
+                target: model,
+            }]
+        );
+        assert_eq!(
+            dest.get_by_ref(model)
+                .unwrap()
+                .properties
+                .get(&"PrimaryPart".into()),
+            Some(&Variant::Ref(part0))
+        );
+    }
+}
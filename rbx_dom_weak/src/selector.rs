@@ -0,0 +1,516 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use ahash::AHashSet;
+use rbx_types::{Ref, Variant};
+use ustr::Ustr;
+
+#[cfg(feature = "reflection")]
+use rbx_reflection::ReflectionDatabase;
+
+use crate::{Instance, WeakDom};
+
+/// A parsed path that selects instances from a [`WeakDom`].
+///
+/// Selectors are made of segments separated by `/`, starting from the
+/// children of the DOM's root. Each segment is one of:
+///
+/// - A name, like `Workspace`, which matches children with that name. Names
+///   containing special characters can be quoted, like `"Map/Old"`.
+/// - `*`, which matches every child.
+/// - `**`, which matches the current instances and all of their descendants.
+///
+/// Names and `*` can be followed by any number of filters:
+///
+/// - `[Prop]` matches instances that have the property `Prop`.
+/// - `[Prop=value]` and `[Prop!=value]` compare a property against a value.
+///   `Name` and `ClassName` compare against the instance's name and class.
+/// - `:IsA(Class)` matches instances of the given class or its subclasses.
+///
+/// A name can be left out entirely if it's followed by a filter, so
+/// `**/[ClassName=Part]` is the same as `**/*[ClassName=Part]`.
+///
+/// ## Example
+/// ```
+/// use rbx_dom_weak::{InstanceBuilder, Selector, WeakDom};
+///
+/// let dom = WeakDom::new(InstanceBuilder::new("DataModel").with_child(
+///     InstanceBuilder::new("Workspace").with_name("Workspace").with_child(
+///         InstanceBuilder::new("Part")
+///             .with_name("Floor")
+///             .with_property("Anchored", false),
+///     ),
+/// ));
+///
+/// let selector: Selector = "Workspace/**/*[Anchored=false]".parse().unwrap();
+/// let parts = selector.select(&dom);
+///
+/// assert_eq!(parts.len(), 1);
+/// assert_eq!(dom.get_by_ref(parts[0]).unwrap().name, "Floor");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Descendants,
+    Step {
+        name: Option<String>,
+        filters: Vec<Filter>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Has(Ustr),
+    Equals(Ustr, String),
+    NotEquals(Ustr, String),
+    IsA(String),
+}
+
+impl Selector {
+    /// Parses a selector from a string.
+    pub fn parse(source: &str) -> Result<Selector, SelectorError> {
+        Parser { source, offset: 0 }.parse()
+    }
+
+    /// Returns the referents of all instances in `dom` that match this
+    /// selector, in the order they were found.
+    ///
+    /// Without a reflection database, `:IsA(Class)` only matches instances
+    /// whose class is exactly `Class`.
+    pub fn select(&self, dom: &WeakDom) -> Vec<Ref> {
+        self.select_inner(dom, &|instance: &Instance, class: &str| {
+            instance.class == class
+        })
+    }
+
+    /// Like [`Selector::select`], but uses the given reflection database to
+    /// resolve `:IsA(Class)` so that subclasses are also matched.
+    #[cfg(feature = "reflection")]
+    pub fn select_with_database(&self, dom: &WeakDom, database: &ReflectionDatabase) -> Vec<Ref> {
+        self.select_inner(dom, &|instance: &Instance, class: &str| {
+            instance.reflect(database).is_a(class)
+        })
+    }
+
+    fn select_inner(&self, dom: &WeakDom, is_a: &dyn Fn(&Instance, &str) -> bool) -> Vec<Ref> {
+        let mut current = vec![dom.root_ref()];
+
+        for segment in &self.segments {
+            let mut seen = AHashSet::new();
+            let mut next = Vec::new();
+
+            match segment {
+                Segment::Descendants => {
+                    for &referent in &current {
+                        // Descendants of an instance we've already visited
+                        // have been visited too.
+                        if seen.contains(&referent) {
+                            continue;
+                        }
+                        for descendant in dom.descendants_of(referent) {
+                            if seen.insert(descendant.referent()) {
+                                next.push(descendant.referent());
+                            }
+                        }
+                    }
+                }
+                Segment::Step { name, filters } => {
+                    for &referent in &current {
+                        let parent = dom.get_by_ref(referent).unwrap();
+
+                        for &child_ref in parent.children() {
+                            let child = dom.get_by_ref(child_ref).unwrap();
+
+                            let name_matches = match name {
+                                Some(name) => child.name == *name,
+                                None => true,
+                            };
+                            if name_matches
+                                && filters.iter().all(|filter| filter.matches(child, is_a))
+                                && seen.insert(child_ref)
+                            {
+                                next.push(child_ref);
+                            }
+                        }
+                    }
+                }
+            }
+
+            current = next;
+        }
+
+        current
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Selector::parse(source)
+    }
+}
+
+impl Filter {
+    fn matches(&self, instance: &Instance, is_a: &dyn Fn(&Instance, &str) -> bool) -> bool {
+        match self {
+            Filter::Has(property) => match property.as_str() {
+                "Name" | "ClassName" => true,
+                _ => instance.properties.contains_key(property),
+            },
+            Filter::Equals(property, value) => property_equals(instance, *property, value),
+            Filter::NotEquals(property, value) => !property_equals(instance, *property, value),
+            Filter::IsA(class) => is_a(instance, class),
+        }
+    }
+}
+
+/// Compares a property against the text of a filter. Only properties with
+/// an obvious text form can be compared.
+fn property_equals(instance: &Instance, property: Ustr, text: &str) -> bool {
+    let value = match property.as_str() {
+        "Name" => return instance.name == text,
+        "ClassName" => return instance.class == text,
+        _ => match instance.properties.get(&property) {
+            Some(value) => value,
+            None => return false,
+        },
+    };
+
+    let number = || text.parse::<f64>().ok();
+
+    match value {
+        Variant::String(value) => value == text,
+        Variant::Bool(value) => text.parse::<bool>().ok() == Some(*value),
+        Variant::Int32(value) => number() == Some(f64::from(*value)),
+        Variant::Int64(value) => number() == Some(*value as f64),
+        Variant::Float32(value) => number() == Some(f64::from(*value)),
+        Variant::Float64(value) => number() == Some(*value),
+        Variant::Enum(value) => text.parse::<u32>().ok() == Some(value.to_u32()),
+        Variant::EnumItem(value) => text.parse::<u32>().ok() == Some(value.value),
+        Variant::BrickColor(value) => {
+            value.to_string() == text || text.parse::<u16>().ok() == Some(*value as u16)
+        }
+        Variant::Tags(tags) => tags.iter().any(|tag| tag == text),
+        _ => false,
+    }
+}
+
+/// An error that occurred while parsing a [`Selector`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorError {
+    offset: usize,
+    message: String,
+}
+
+impl SelectorError {
+    /// The byte offset in the source string where the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl Error for SelectorError {}
+
+struct Parser<'a> {
+    source: &'a str,
+    offset: usize,
+}
+
+impl Parser<'_> {
+    fn parse(mut self) -> Result<Selector, SelectorError> {
+        let mut segments = Vec::new();
+
+        loop {
+            segments.push(self.parse_segment()?);
+
+            match self.peek() {
+                None => break,
+                Some('/') => self.offset += 1,
+                Some(other) => return Err(self.error(format!("unexpected '{other}'"))),
+            }
+        }
+
+        Ok(Selector { segments })
+    }
+
+    fn parse_segment(&mut self) -> Result<Segment, SelectorError> {
+        if self.rest().starts_with("**") {
+            self.offset += 2;
+            return Ok(Segment::Descendants);
+        }
+
+        let start = self.offset;
+        let name = match self.peek() {
+            Some('*') => {
+                self.offset += 1;
+                None
+            }
+            Some('"') => Some(self.parse_quoted()?),
+            _ => {
+                let name = self.take_while(|c| !"/[]:\"*=!()".contains(c));
+                (!name.is_empty()).then(|| name.to_owned())
+            }
+        };
+
+        let mut filters = Vec::new();
+        loop {
+            match self.peek() {
+                Some('[') => filters.push(self.parse_property_filter()?),
+                Some(':') => filters.push(self.parse_pseudo_class()?),
+                _ => break,
+            }
+        }
+
+        if self.offset == start {
+            return Err(self.error("expected a name, '*', or '**'".to_owned()));
+        }
+
+        Ok(Segment::Step { name, filters })
+    }
+
+    fn parse_property_filter(&mut self) -> Result<Filter, SelectorError> {
+        self.expect('[')?;
+
+        let property = self.take_while(|c| !"[]=!\"".contains(c));
+        if property.is_empty() {
+            return Err(self.error("expected a property name".to_owned()));
+        }
+        let property = Ustr::from(property);
+
+        let filter = match self.peek() {
+            Some(']') => Filter::Has(property),
+            Some('=') => {
+                self.offset += 1;
+                Filter::Equals(property, self.parse_value()?)
+            }
+            Some('!') => {
+                self.offset += 1;
+                self.expect('=')?;
+                Filter::NotEquals(property, self.parse_value()?)
+            }
+            _ => return Err(self.error("expected ']', '=', or '!='".to_owned())),
+        };
+
+        self.expect(']')?;
+        Ok(filter)
+    }
+
+    fn parse_pseudo_class(&mut self) -> Result<Filter, SelectorError> {
+        self.expect(':')?;
+
+        let start = self.offset;
+        let name = self.take_while(|c| c.is_ascii_alphanumeric()).to_owned();
+        if name != "IsA" {
+            self.offset = start;
+            return Err(self.error(format!("unknown pseudo-class ':{name}'")));
+        }
+
+        self.expect('(')?;
+        let class = self.take_while(|c| c != ')');
+        if class.is_empty() {
+            return Err(self.error("expected a class name".to_owned()));
+        }
+        let class = class.to_owned();
+        self.expect(')')?;
+
+        Ok(Filter::IsA(class))
+    }
+
+    fn parse_value(&mut self) -> Result<String, SelectorError> {
+        match self.peek() {
+            Some('"') => self.parse_quoted(),
+            _ => Ok(self.take_while(|c| c != ']').to_owned()),
+        }
+    }
+
+    fn parse_quoted(&mut self) -> Result<String, SelectorError> {
+        let start = self.offset;
+        self.expect('"')?;
+
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.offset += index + 1;
+                    return Ok(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                _ => value.push(c),
+            }
+        }
+
+        self.offset = start;
+        Err(self.error("unterminated string".to_owned()))
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SelectorError> {
+        if self.peek() == Some(expected) {
+            self.offset += expected.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{expected}'")))
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &str {
+        let rest = &self.source[self.offset..];
+        let len = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.offset += len;
+        &rest[..len]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn rest(&self) -> &str {
+        &self.source[self.offset..]
+    }
+
+    fn error(&self, message: String) -> SelectorError {
+        SelectorError {
+            offset: self.offset,
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::InstanceBuilder;
+
+    fn dom() -> WeakDom {
+        WeakDom::new(
+            InstanceBuilder::new("DataModel").with_children([
+                InstanceBuilder::new("Workspace")
+                    .with_name("Workspace")
+                    .with_child(
+                        InstanceBuilder::new("Model")
+                            .with_name("Map")
+                            .with_children([
+                                InstanceBuilder::new("Part")
+                                    .with_name("Floor")
+                                    .with_property("Anchored", true),
+                                InstanceBuilder::new("Folder")
+                                    .with_name("Props")
+                                    .with_child(
+                                        InstanceBuilder::new("Part")
+                                            .with_name("Crate")
+                                            .with_property("Anchored", false),
+                                    ),
+                                InstanceBuilder::new("MeshPart")
+                                    .with_name("Rock")
+                                    .with_property("Anchored", false),
+                            ]),
+                    ),
+                InstanceBuilder::new("Folder").with_name("Map/Old"),
+            ]),
+        )
+    }
+
+    fn names(dom: &WeakDom, selector: &str) -> Vec<String> {
+        Selector::parse(selector)
+            .unwrap()
+            .select(dom)
+            .into_iter()
+            .map(|referent| dom.get_by_ref(referent).unwrap().name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn paths() {
+        let dom = dom();
+
+        assert_eq!(names(&dom, "Workspace/Map"), ["Map"]);
+        assert_eq!(names(&dom, "Workspace/Map/*"), ["Floor", "Props", "Rock"]);
+        assert_eq!(names(&dom, "\"Map/Old\""), ["Map/Old"]);
+        assert_eq!(names(&dom, "Workspace/Missing"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn descendants() {
+        let dom = dom();
+
+        assert_eq!(
+            names(&dom, "Workspace/**/[ClassName=Part]"),
+            ["Floor", "Crate"]
+        );
+        assert_eq!(names(&dom, "**/**/Crate"), ["Crate"]);
+        assert_eq!(
+            names(&dom, "Workspace/Map/**"),
+            ["Map", "Floor", "Props", "Rock", "Crate"]
+        );
+    }
+
+    #[test]
+    fn property_filters() {
+        let dom = dom();
+
+        assert_eq!(
+            names(&dom, "Workspace/Map/**/*[Anchored=false]"),
+            ["Rock", "Crate"]
+        );
+        assert_eq!(names(&dom, "**/*[Anchored!=false][Anchored]"), ["Floor"]);
+        assert_eq!(names(&dom, "**/Crate[ClassName=Part]"), ["Crate"]);
+    }
+
+    #[test]
+    fn is_a_without_database() {
+        let dom = dom();
+
+        assert_eq!(names(&dom, "**/:IsA(Part)"), ["Floor", "Crate"]);
+    }
+
+    #[cfg(feature = "reflection")]
+    #[test]
+    fn is_a_with_database() {
+        use rbx_reflection::ClassDescriptor;
+
+        let mut database = ReflectionDatabase::new();
+        for (class, superclass) in [
+            ("Instance", None),
+            ("BasePart", Some("Instance")),
+            ("Part", Some("BasePart")),
+            ("MeshPart", Some("BasePart")),
+        ] {
+            let mut descriptor = ClassDescriptor::new(class);
+            descriptor.superclass = superclass;
+            database.classes.insert(class, descriptor);
+        }
+
+        let dom = dom();
+        let selector = Selector::parse("**/:IsA(BasePart)").unwrap();
+        let names: Vec<_> = selector
+            .select_with_database(&dom, &database)
+            .into_iter()
+            .map(|referent| dom.get_by_ref(referent).unwrap().name.as_str())
+            .collect();
+
+        assert_eq!(names, ["Floor", "Rock", "Crate"]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Selector::parse("Workspace/").unwrap_err().offset(), 10);
+        assert_eq!(Selector::parse("A[Anchored").unwrap_err().offset(), 10);
+        assert_eq!(Selector::parse("A:Foo(B)").unwrap_err().offset(), 2);
+        assert_eq!(Selector::parse("\"A").unwrap_err().offset(), 0);
+        assert!(Selector::parse("A]").is_err());
+    }
+}
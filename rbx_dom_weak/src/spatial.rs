@@ -0,0 +1,409 @@
+use rbx_types::{CFrame, Matrix3, Ref, Region3, Variant, Vector3};
+
+use crate::{Instance, WeakDom};
+
+/// A box that can be rotated, like the CFrame and size returned by Roblox's
+/// `Model:GetBoundingBox`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    /// The center of the box and its rotation.
+    pub cframe: CFrame,
+
+    /// The full length of the box along each of its own axes.
+    pub size: Vector3,
+}
+
+impl BoundingBox {
+    /// Returns the eight corners of the box in world space.
+    pub fn corners(&self) -> [Vector3; 8] {
+        let half = self.size / 2.0;
+        let mut corners = [Vector3::zero(); 8];
+
+        for (index, corner) in corners.iter_mut().enumerate() {
+            let sign = |bit: usize| if index & bit == 0 { -1.0 } else { 1.0 };
+            let offset = Vector3::new(sign(1), sign(2), sign(4)) * half;
+            *corner = self.cframe * offset;
+        }
+
+        corners
+    }
+
+    /// Returns the smallest box that lines up with the world axes and
+    /// contains this box.
+    pub fn to_region(&self) -> Region3 {
+        let corners = self.corners();
+        let (min, max) = extents(corners.iter().copied(), CFrame::identity());
+        Region3::new(min, max)
+    }
+}
+
+/// Spatial queries for parts and models, available with the `math` feature.
+///
+/// Parts are any instances with a `CFrame` and a `Size`, so this works
+/// without a reflection database. Other instances, like `Model` and
+/// `Folder`, are measured by the parts inside them.
+impl WeakDom {
+    /// Returns where an instance is in world space.
+    ///
+    /// For parts, this is their `CFrame`. Attachments and bones are placed
+    /// relative to the part or bone they're in. Everything else uses
+    /// [`WeakDom::pivot`].
+    pub fn world_cframe(&self, referent: Ref) -> Option<CFrame> {
+        let instance = self.get_by_ref(referent)?;
+
+        if let Some(part) = part_box(instance) {
+            return Some(part.cframe);
+        }
+
+        if let ("Attachment" | "Bone", Some(Variant::CFrame(cframe))) = (
+            instance.class.as_str(),
+            instance.properties.get(&"CFrame".into()),
+        ) {
+            return Some(self.world_cframe(instance.parent())? * *cframe);
+        }
+
+        self.pivot(referent)
+    }
+
+    /// Returns the pivot of a part or model, like Roblox's
+    /// `PVInstance:GetPivot`.
+    ///
+    /// A part's pivot is its `CFrame` offset by its `PivotOffset`. A model's
+    /// pivot is its `PrimaryPart`'s pivot if it has one, then its
+    /// `WorldPivotData`, and finally the center of its bounding box.
+    pub fn pivot(&self, referent: Ref) -> Option<CFrame> {
+        let instance = self.get_by_ref(referent)?;
+
+        if let Some(part) = part_box(instance) {
+            return Some(part.cframe * pivot_offset(instance));
+        }
+
+        self.explicit_pivot(instance).or_else(|| {
+            self.bounding_box_with(referent, Matrix3::identity())
+                .map(|bounds| bounds.cframe)
+        })
+    }
+
+    /// Moves an instance so that its pivot is at `pivot`, like Roblox's
+    /// `PVInstance:PivotTo`. Every part inside the instance moves with it,
+    /// along with the `WorldPivotData` of every model inside it.
+    ///
+    /// Returns `false` without changing anything if the instance doesn't have
+    /// a pivot.
+    pub fn pivot_to(&mut self, referent: Ref, pivot: CFrame) -> bool {
+        let current = match self.pivot(referent) {
+            Some(current) => current,
+            None => return false,
+        };
+        let transform = pivot * current.inverse();
+
+        let moved: Vec<_> = self
+            .descendants_of(referent)
+            .filter_map(|instance| {
+                if part_box(instance).is_some() {
+                    Some((instance.referent(), "CFrame"))
+                } else if let Some(Variant::OptionalCFrame(Some(_))) =
+                    instance.properties.get(&"WorldPivotData".into())
+                {
+                    Some((instance.referent(), "WorldPivotData"))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        for (referent, property) in moved {
+            let instance = self.get_by_ref_mut(referent).unwrap();
+
+            match instance.properties.get_mut(&property.into()) {
+                Some(Variant::CFrame(cframe)) | Some(Variant::OptionalCFrame(Some(cframe))) => {
+                    *cframe = (transform * *cframe).orthonormalize();
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        true
+    }
+
+    /// Returns a box that contains every part in an instance, including the
+    /// instance itself, like Roblox's `Model:GetBoundingBox`.
+    ///
+    /// The box is rotated to match the model's `PrimaryPart` or
+    /// `WorldPivotData` if it has one and lines up with the world axes
+    /// otherwise. A part's box is the part itself. Returns `None` if there
+    /// are no parts.
+    pub fn bounding_box(&self, referent: Ref) -> Option<BoundingBox> {
+        let instance = self.get_by_ref(referent)?;
+
+        if let Some(part) = part_box(instance) {
+            return Some(part);
+        }
+
+        let orientation = match self.explicit_pivot(instance) {
+            Some(pivot) => pivot.orientation,
+            None => Matrix3::identity(),
+        };
+        self.bounding_box_with(referent, orientation)
+    }
+
+    /// Returns the smallest box that lines up with the world axes and
+    /// contains every part in an instance, including the instance itself.
+    /// Returns `None` if there are no parts.
+    pub fn axis_aligned_bounding_box(&self, referent: Ref) -> Option<Region3> {
+        let bounds = self.bounding_box_with(referent, Matrix3::identity())?;
+        let half = bounds.size / 2.0;
+
+        Some(Region3::new(
+            bounds.cframe.position - half,
+            bounds.cframe.position + half,
+        ))
+    }
+
+    /// Returns the pivot that a model has set, either through its
+    /// `PrimaryPart` or `WorldPivotData`.
+    fn explicit_pivot(&self, instance: &Instance) -> Option<CFrame> {
+        if let Some(Variant::Ref(primary_part)) = instance.properties.get(&"PrimaryPart".into()) {
+            if let Some(primary_part) = self.get_by_ref(*primary_part) {
+                if let Some(part) = part_box(primary_part) {
+                    return Some(part.cframe * pivot_offset(primary_part));
+                }
+            }
+        }
+
+        match instance.properties.get(&"WorldPivotData".into()) {
+            Some(Variant::OptionalCFrame(pivot)) => *pivot,
+            _ => None,
+        }
+    }
+
+    /// Measures every part in an instance along the axes of `orientation`.
+    fn bounding_box_with(&self, referent: Ref, orientation: Matrix3) -> Option<BoundingBox> {
+        self.get_by_ref(referent)?;
+
+        let frame = CFrame::new(Vector3::zero(), orientation);
+        let mut corners = self
+            .descendants_of(referent)
+            .filter_map(part_box)
+            .flat_map(|part| part.corners())
+            .peekable();
+        corners.peek()?;
+
+        let (min, max) = extents(corners, frame);
+        Some(BoundingBox {
+            cframe: frame * CFrame::from_position((min + max) / 2.0),
+            size: max - min,
+        })
+    }
+}
+
+/// Returns the CFrame and size of an instance if it's a part.
+fn part_box(instance: &Instance) -> Option<BoundingBox> {
+    match (
+        instance.properties.get(&"CFrame".into()),
+        instance.properties.get(&"Size".into()),
+    ) {
+        (Some(Variant::CFrame(cframe)), Some(Variant::Vector3(size))) => Some(BoundingBox {
+            cframe: *cframe,
+            size: *size,
+        }),
+        _ => None,
+    }
+}
+
+fn pivot_offset(part: &Instance) -> CFrame {
+    match part.properties.get(&"PivotOffset".into()) {
+        Some(Variant::CFrame(offset)) => *offset,
+        _ => CFrame::identity(),
+    }
+}
+
+/// Finds the minimum and maximum of `points` in the space of `frame`.
+fn extents(points: impl Iterator<Item = Vector3>, frame: CFrame) -> (Vector3, Vector3) {
+    let infinity = Vector3::one() * f32::INFINITY;
+
+    points
+        .map(|point| frame.point_to_object_space(point))
+        .fold((infinity, -infinity), |(min, max), point| {
+            (min.min(point), max.max(point))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::InstanceBuilder;
+
+    const TOLERANCE: f32 = 1e-4;
+
+    fn part(name: &str, cframe: CFrame, size: Vector3) -> InstanceBuilder {
+        InstanceBuilder::new("Part")
+            .with_name(name)
+            .with_property("CFrame", cframe)
+            .with_property("Size", size)
+    }
+
+    fn assert_cframe_eq(actual: CFrame, expected: CFrame) {
+        assert!(
+            actual.fuzzy_eq(expected, TOLERANCE),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn assert_vector_eq(actual: Vector3, expected: Vector3) {
+        assert!(
+            actual.fuzzy_eq(expected, TOLERANCE),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    /// Two parts side by side, the second turned 90 degrees so that it's
+    /// longest along Z.
+    fn sample_model() -> (WeakDom, Ref, Ref) {
+        let turned = Matrix3::from_axis_angle(Vector3::y_axis(), FRAC_PI_2);
+        let first = part(
+            "First",
+            CFrame::from_position(Vector3::new(0.0, 0.5, 0.0)),
+            Vector3::new(4.0, 1.0, 2.0),
+        );
+        let second = part(
+            "Second",
+            CFrame::new(Vector3::new(10.0, 0.5, 0.0), turned),
+            Vector3::new(4.0, 1.0, 2.0),
+        )
+        .with_child(
+            InstanceBuilder::new("Attachment")
+                .with_property("CFrame", CFrame::from_position(Vector3::new(1.0, 0.0, 0.0))),
+        );
+        let first_ref = first.referent();
+        let model = InstanceBuilder::new("Model").with_children([first, second]);
+        let model_ref = model.referent();
+
+        (
+            WeakDom::new(InstanceBuilder::new("DataModel").with_child(model)),
+            model_ref,
+            first_ref,
+        )
+    }
+
+    #[test]
+    fn bounding_boxes() {
+        let (dom, model, first) = sample_model();
+
+        let bounds = dom.bounding_box(model).unwrap();
+        assert_cframe_eq(
+            bounds.cframe,
+            CFrame::from_position(Vector3::new(4.5, 0.5, 0.0)),
+        );
+        assert_vector_eq(bounds.size, Vector3::new(13.0, 1.0, 4.0));
+
+        let region = dom.axis_aligned_bounding_box(model).unwrap();
+        assert_vector_eq(region.min, Vector3::new(-2.0, 0.0, -2.0));
+        assert_vector_eq(region.max, Vector3::new(11.0, 1.0, 2.0));
+
+        let part = dom.bounding_box(first).unwrap();
+        assert_vector_eq(part.size, Vector3::new(4.0, 1.0, 2.0));
+        assert_eq!(
+            part.to_region(),
+            dom.axis_aligned_bounding_box(first).unwrap()
+        );
+
+        assert_eq!(dom.bounding_box(dom.root_ref()), Some(bounds));
+        let empty = InstanceBuilder::new("Folder");
+        let empty_ref = empty.referent();
+        let dom = WeakDom::new(empty);
+        assert_eq!(dom.bounding_box(empty_ref), None);
+        assert_eq!(dom.pivot(empty_ref), None);
+    }
+
+    /// The bounding box should follow the rotation of the model's pivot.
+    #[test]
+    fn oriented_bounding_box() {
+        let (mut dom, model, _) = sample_model();
+        let pivot = CFrame::new(
+            Vector3::new(1.0, 2.0, 3.0),
+            Matrix3::from_axis_angle(Vector3::y_axis(), FRAC_PI_2),
+        );
+        dom.get_by_ref_mut(model).unwrap().properties.insert(
+            "WorldPivotData".into(),
+            Variant::OptionalCFrame(Some(pivot)),
+        );
+
+        let bounds = dom.bounding_box(model).unwrap();
+        assert_cframe_eq(
+            bounds.cframe,
+            CFrame::new(Vector3::new(4.5, 0.5, 0.0), pivot.orientation),
+        );
+        assert_vector_eq(bounds.size, Vector3::new(4.0, 1.0, 13.0));
+    }
+
+    #[test]
+    fn pivots() {
+        let (mut dom, model, first) = sample_model();
+
+        // Without a pivot set, the model's pivot is the center of its box.
+        assert_cframe_eq(
+            dom.pivot(model).unwrap(),
+            CFrame::from_position(Vector3::new(4.5, 0.5, 0.0)),
+        );
+
+        let offset = CFrame::from_position(Vector3::new(0.0, -0.5, 0.0));
+        let instance = dom.get_by_ref_mut(first).unwrap();
+        instance
+            .properties
+            .insert("PivotOffset".into(), offset.into());
+        assert_cframe_eq(dom.pivot(first).unwrap(), CFrame::identity());
+
+        let world_pivot = CFrame::from_position(Vector3::new(-1.0, 0.0, 0.0));
+        let instance = dom.get_by_ref_mut(model).unwrap();
+        instance.properties.insert(
+            "WorldPivotData".into(),
+            Variant::OptionalCFrame(Some(world_pivot)),
+        );
+        assert_cframe_eq(dom.pivot(model).unwrap(), world_pivot);
+
+        let instance = dom.get_by_ref_mut(model).unwrap();
+        instance
+            .properties
+            .insert("PrimaryPart".into(), first.into());
+        assert_cframe_eq(dom.pivot(model).unwrap(), CFrame::identity());
+    }
+
+    #[test]
+    fn pivot_to() {
+        let (mut dom, model, first) = sample_model();
+        let world_pivot = CFrame::from_position(Vector3::new(5.0, 0.0, 0.0));
+        dom.get_by_ref_mut(model).unwrap().properties.insert(
+            "WorldPivotData".into(),
+            Variant::OptionalCFrame(Some(world_pivot)),
+        );
+        let attachment = dom.find_by_class("Attachment")[0];
+
+        let target = CFrame::new(
+            Vector3::new(100.0, 0.0, 0.0),
+            Matrix3::from_axis_angle(Vector3::y_axis(), FRAC_PI_2),
+        );
+        assert!(dom.pivot_to(model, target));
+
+        assert_cframe_eq(dom.pivot(model).unwrap(), target);
+        assert_cframe_eq(
+            dom.world_cframe(first).unwrap(),
+            CFrame::new(Vector3::new(100.0, 0.5, 5.0), target.orientation),
+        );
+
+        // Attachments are relative to their part, so they move along with it.
+        assert_vector_eq(
+            dom.world_cframe(attachment).unwrap().position,
+            Vector3::new(99.0, 0.5, -5.0),
+        );
+
+        let empty = dom.insert(dom.root_ref(), InstanceBuilder::new("Folder"));
+        assert!(!dom.pivot_to(empty, target));
+    }
+}
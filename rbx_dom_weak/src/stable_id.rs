@@ -0,0 +1,146 @@
+use std::hash::Hash;
+
+use ahash::{AHashMap, AHashSet};
+use rbx_types::{Ref, Variant};
+use ustr::ustr;
+
+use crate::WeakDom;
+
+impl WeakDom {
+    /// Gives every instance in the given subtrees an ID that stays the same
+    /// when other instances are added or removed, for writing files that
+    /// diff well in version control.
+    ///
+    /// Each instance is described by a key made from its `UniqueId`, or from
+    /// the names of it and its ancestors along with its position among
+    /// siblings that share its name. `hash` turns a key into an ID. If an ID
+    /// was already given out, `#` is added to the key until the ID is new, so
+    /// IDs are always unique.
+    ///
+    /// `hash` must be able to produce more distinct IDs than there are
+    /// instances, and should produce many more so that collisions are rare.
+    /// Otherwise, this never returns.
+    ///
+    /// IDs are returned in the order they were given out, with parents before
+    /// their children. When keys or IDs collide, the instance that comes first
+    /// in that order keeps the unsuffixed key, so reordering instances that
+    /// share a `UniqueId` or a path can swap their IDs.
+    pub fn stable_ids<T, F>(&self, roots: &[Ref], mut hash: F) -> Vec<(Ref, T)>
+    where
+        T: Clone + Eq + Hash,
+        F: FnMut(&str) -> T,
+    {
+        let mut ids = Vec::new();
+        let mut used = AHashSet::new();
+        let mut to_visit: Vec<(String, &[Ref])> = vec![(String::new(), roots)];
+
+        while let Some((parent_key, children)) = to_visit.pop() {
+            let mut seen_names: AHashMap<&str, usize> = AHashMap::new();
+
+            for &referent in children {
+                let instance = match self.get_by_ref(referent) {
+                    Some(instance) => instance,
+                    None => continue,
+                };
+
+                let index = seen_names.entry(instance.name.as_str()).or_default();
+                let mut key = match instance.properties.get(&ustr("UniqueId")) {
+                    Some(Variant::UniqueId(unique_id)) if !unique_id.is_nil() => {
+                        format!("UniqueId:{unique_id}")
+                    }
+                    _ => format!("{parent_key}/{}[{index}]", instance.name),
+                };
+                *index += 1;
+
+                // Copying instances in Studio duplicates their UniqueId, and
+                // short IDs can collide even when keys don't. Extending the
+                // key keeps the ID stable only as long as the order of the
+                // instances involved is.
+                let mut id = hash(&key);
+                while !used.insert(id.clone()) {
+                    key.push('#');
+                    id = hash(&key);
+                }
+
+                ids.push((referent, id));
+                to_visit.push((key, instance.children()));
+            }
+        }
+
+        ids
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rbx_types::UniqueId;
+
+    use crate::{InstanceBuilder, WeakDom};
+
+    fn dom(extra: bool) -> WeakDom {
+        let mut children = Vec::new();
+        if extra {
+            children.push(InstanceBuilder::new("Part").with_name("Part"));
+        }
+        children.push(InstanceBuilder::new("Part").with_name("Part"));
+        children.push(
+            InstanceBuilder::new("Part")
+                .with_name("Tagged")
+                .with_property("UniqueId", UniqueId::new(1, 2, 3)),
+        );
+
+        WeakDom::new(
+            InstanceBuilder::new("DataModel").with_child(
+                InstanceBuilder::new("Model")
+                    .with_name("Model")
+                    .with_children(children),
+            ),
+        )
+    }
+
+    fn keys(dom: &WeakDom) -> Vec<String> {
+        dom.stable_ids(dom.root().children(), str::to_owned)
+            .into_iter()
+            .map(|(_, key)| key)
+            .collect()
+    }
+
+    #[test]
+    fn keys_by_path_and_unique_id() {
+        let unique_id = UniqueId::new(1, 2, 3);
+        assert_eq!(
+            keys(&dom(false)),
+            [
+                "/Model[0]".to_owned(),
+                "/Model[0]/Part[0]".to_owned(),
+                format!("UniqueId:{unique_id}"),
+            ]
+        );
+
+        // Siblings with the same name are told apart by their position.
+        assert_eq!(
+            keys(&dom(true)),
+            [
+                "/Model[0]".to_owned(),
+                "/Model[0]/Part[0]".to_owned(),
+                "/Model[0]/Part[1]".to_owned(),
+                format!("UniqueId:{unique_id}"),
+            ]
+        );
+    }
+
+    /// IDs that collide should be replaced with new ones. Each `#` moves this
+    /// hash on to the next of its 7 outputs, so the 4 instances here can
+    /// always be given unique IDs.
+    #[test]
+    fn collisions() {
+        let dom = dom(true);
+        let ids = dom.stable_ids(dom.root().children(), |key| key.len() % 7);
+
+        let mut written: Vec<_> = ids.iter().map(|(_, id)| *id).collect();
+        assert_eq!(written.len(), 4);
+        written.sort_unstable();
+        written.dedup();
+        assert_eq!(written.len(), 4);
+    }
+}
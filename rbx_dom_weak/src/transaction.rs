@@ -0,0 +1,464 @@
+use rbx_types::Ref;
+
+use crate::{DomEvent, InstanceBuilder, WeakDom};
+
+/// The undo and redo stacks of a `WeakDom`. Each entry is the list of events
+/// from one committed transaction, oldest first.
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    undo: Vec<Vec<DomEvent>>,
+    redo: Vec<Vec<DomEvent>>,
+}
+
+impl WeakDom {
+    /// Starts a transaction. Every change made to the DOM until
+    /// [`WeakDom::commit_transaction`] or [`WeakDom::rollback_transaction`] is
+    /// called becomes part of it.
+    ///
+    /// ## Panics
+    /// Panics if a transaction is already in progress.
+    pub fn begin_transaction(&mut self) {
+        if self.journal.in_transaction() {
+            panic!("cannot begin a transaction while another is in progress");
+        }
+
+        self.flush_pending();
+        self.journal.begin_transaction();
+    }
+
+    /// Returns whether a transaction is in progress.
+    #[inline]
+    pub fn in_transaction(&self) -> bool {
+        self.journal.in_transaction()
+    }
+
+    /// Finishes the current transaction, keeping its changes. The transaction
+    /// can be undone later with [`WeakDom::undo`].
+    ///
+    /// ## Panics
+    /// Panics if no transaction is in progress.
+    pub fn commit_transaction(&mut self) {
+        self.flush_pending();
+        let events = self
+            .journal
+            .end_transaction()
+            .unwrap_or_else(|| panic!("cannot commit without a transaction in progress"));
+
+        if !events.is_empty() {
+            self.history.undo.push(events);
+            self.history.redo.clear();
+        }
+    }
+
+    /// Finishes the current transaction by reverting all of its changes,
+    /// leaving the DOM as it was when the transaction began.
+    ///
+    /// Instances that are restored are added as the last child of their
+    /// parent, so the order of children may differ from before.
+    ///
+    /// ## Panics
+    /// Panics if no transaction is in progress.
+    pub fn rollback_transaction(&mut self) {
+        self.flush_pending();
+        let events = self
+            .journal
+            .end_transaction()
+            .unwrap_or_else(|| panic!("cannot roll back without a transaction in progress"));
+
+        self.revert_events(&events);
+    }
+
+    /// Reverts the most recently committed transaction that hasn't been
+    /// undone. Returns `false` if there was nothing to undo.
+    ///
+    /// Changes made outside of a transaction aren't part of the history. If
+    /// they conflict with a change being undone, like by destroying the
+    /// instance it was made to, that change is skipped and the rest of the
+    /// transaction is still undone.
+    ///
+    /// ## Panics
+    /// Panics if a transaction is in progress.
+    pub fn undo(&mut self) -> bool {
+        if self.journal.in_transaction() {
+            panic!("cannot undo while a transaction is in progress");
+        }
+
+        self.flush_pending();
+        match self.history.undo.pop() {
+            Some(events) => {
+                self.revert_events(&events);
+                self.history.redo.push(events);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapplies the most recently undone transaction. Returns `false` if
+    /// there was nothing to redo.
+    ///
+    /// Like [`WeakDom::undo`], changes that no longer apply are skipped.
+    ///
+    /// ## Panics
+    /// Panics if a transaction is in progress.
+    pub fn redo(&mut self) -> bool {
+        if self.journal.in_transaction() {
+            panic!("cannot redo while a transaction is in progress");
+        }
+
+        self.flush_pending();
+        match self.history.redo.pop() {
+            Some(events) => {
+                for event in &events {
+                    self.apply_event(event);
+                }
+                self.history.undo.push(events);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns whether there's a committed transaction that can be undone.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty()
+    }
+
+    /// Returns whether there's an undone transaction that can be redone.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    /// Forgets every transaction that could be undone or redone.
+    pub fn clear_history(&mut self) {
+        self.history = History::default();
+    }
+
+    fn revert_events(&mut self, events: &[DomEvent]) {
+        for event in events.iter().rev() {
+            self.apply_event(&invert(event));
+        }
+    }
+
+    /// Applies an event to the DOM, skipping it if it refers to instances that
+    /// are missing or would create a cycle.
+    fn apply_event(&mut self, event: &DomEvent) {
+        match event {
+            DomEvent::Inserted {
+                referent,
+                parent,
+                class,
+                name,
+                properties,
+            } => {
+                if self.get_by_ref(*referent).is_some()
+                    || (parent.is_some() && self.get_by_ref(*parent).is_none())
+                {
+                    return;
+                }
+
+                let builder = InstanceBuilder::new(*class)
+                    .with_referent(*referent)
+                    .with_name(name.clone())
+                    .with_properties(properties.clone());
+                self.insert(*parent, builder);
+            }
+            DomEvent::Destroyed { referent, .. } => {
+                if *referent != self.root_ref() && self.get_by_ref(*referent).is_some() {
+                    self.destroy(*referent);
+                }
+            }
+            DomEvent::Moved {
+                referent,
+                new_parent,
+                ..
+            } => {
+                if self.can_move(*referent, *new_parent) {
+                    self.transfer_within(*referent, *new_parent);
+                }
+            }
+            DomEvent::Renamed {
+                referent, new_name, ..
+            } => {
+                if let Some(instance) = self.get_by_ref_mut(*referent) {
+                    instance.name = new_name.clone();
+                }
+            }
+            DomEvent::ClassChanged {
+                referent,
+                new_class,
+                ..
+            } => {
+                if let Some(instance) = self.get_by_ref_mut(*referent) {
+                    instance.class = *new_class;
+                }
+            }
+            DomEvent::PropertyChanged {
+                referent,
+                name,
+                new_value,
+                ..
+            } => {
+                if let Some(instance) = self.get_by_ref_mut(*referent) {
+                    match new_value {
+                        Some(value) => instance.properties.insert(*name, value.clone()),
+                        None => instance.properties.remove(name),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Tells whether `referent` can be moved into `new_parent` without
+    /// referring to a missing instance or making an instance its own
+    /// ancestor.
+    fn can_move(&self, referent: Ref, new_parent: Ref) -> bool {
+        if referent == self.root_ref() || self.get_by_ref(referent).is_none() {
+            return false;
+        }
+
+        let mut ancestor = new_parent;
+        while ancestor.is_some() {
+            if ancestor == referent {
+                return false;
+            }
+
+            match self.get_by_ref(ancestor) {
+                Some(instance) => ancestor = instance.parent(),
+                None => return false,
+            }
+        }
+
+        true
+    }
+}
+
+/// Returns an event that undoes the given event.
+fn invert(event: &DomEvent) -> DomEvent {
+    match event.clone() {
+        DomEvent::Inserted {
+            referent,
+            parent,
+            class,
+            name,
+            properties,
+        } => DomEvent::Destroyed {
+            referent,
+            parent,
+            class,
+            name,
+            properties,
+        },
+        DomEvent::Destroyed {
+            referent,
+            parent,
+            class,
+            name,
+            properties,
+        } => DomEvent::Inserted {
+            referent,
+            parent,
+            class,
+            name,
+            properties,
+        },
+        DomEvent::Moved {
+            referent,
+            old_parent,
+            new_parent,
+        } => DomEvent::Moved {
+            referent,
+            old_parent: new_parent,
+            new_parent: old_parent,
+        },
+        DomEvent::Renamed {
+            referent,
+            old_name,
+            new_name,
+        } => DomEvent::Renamed {
+            referent,
+            old_name: new_name,
+            new_name: old_name,
+        },
+        DomEvent::ClassChanged {
+            referent,
+            old_class,
+            new_class,
+        } => DomEvent::ClassChanged {
+            referent,
+            old_class: new_class,
+            new_class: old_class,
+        },
+        DomEvent::PropertyChanged {
+            referent,
+            name,
+            old_value,
+            new_value,
+        } => DomEvent::PropertyChanged {
+            referent,
+            name,
+            old_value: new_value,
+            new_value: old_value,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Diff, InstanceBuilder, MatchStrategy, WeakDom};
+
+    fn dom() -> WeakDom {
+        WeakDom::new(
+            InstanceBuilder::new("DataModel").with_children([
+                InstanceBuilder::new("Folder")
+                    .with_name("A")
+                    .with_child(InstanceBuilder::new("Part").with_name("Part")),
+                InstanceBuilder::new("Folder")
+                    .with_name("B")
+                    .with_property("Value", 1i32),
+            ]),
+        )
+    }
+
+    fn find(dom: &WeakDom, name: &str) -> rbx_types::Ref {
+        dom.descendants()
+            .find(|instance| instance.name == name)
+            .unwrap()
+            .referent()
+    }
+
+    /// Makes a variety of changes to the DOM.
+    fn edit(dom: &mut WeakDom) {
+        let a = find(dom, "A");
+        let b = find(dom, "B");
+        let part = find(dom, "Part");
+
+        dom.insert(
+            b,
+            InstanceBuilder::new("Model")
+                .with_name("New")
+                .with_child(InstanceBuilder::new("Part")),
+        );
+        dom.transfer_within(part, b);
+        dom.destroy(a);
+
+        let instance = dom.get_by_ref_mut(b).unwrap();
+        instance.name = "Renamed".to_owned();
+        instance.class = "Model".into();
+        instance.properties.insert("Value".into(), 2i32.into());
+        instance.properties.insert("Other".into(), true.into());
+    }
+
+    fn same(a: &WeakDom, b: &WeakDom) -> bool {
+        Diff::new(a, b, MatchStrategy::NamePath).is_empty()
+    }
+
+    #[test]
+    fn rollback() {
+        let mut dom = dom();
+
+        dom.begin_transaction();
+        edit(&mut dom);
+        assert!(!same(&dom, &self::dom()));
+        dom.rollback_transaction();
+
+        assert!(same(&dom, &self::dom()));
+        assert!(!dom.in_transaction());
+        assert!(!dom.can_undo());
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut dom = dom();
+
+        let mut edited = self::dom();
+        edit(&mut edited);
+
+        dom.begin_transaction();
+        edit(&mut dom);
+        dom.commit_transaction();
+        assert!(same(&dom, &edited));
+
+        assert!(dom.undo());
+        assert!(same(&dom, &self::dom()));
+        assert!(!dom.undo());
+
+        assert!(dom.redo());
+        assert!(same(&dom, &edited));
+        assert!(!dom.redo());
+
+        // Committing a new transaction forgets anything that was undone.
+        dom.undo();
+        dom.begin_transaction();
+        dom.get_by_ref_mut(find(&dom, "B")).unwrap().name = "C".to_owned();
+        dom.commit_transaction();
+        assert!(!dom.can_redo());
+        assert!(dom.undo());
+        assert!(same(&dom, &self::dom()));
+    }
+
+    #[test]
+    fn journal_still_records() {
+        let mut dom = dom();
+        dom.enable_journal();
+
+        dom.begin_transaction();
+        edit(&mut dom);
+        dom.rollback_transaction();
+
+        // Both the edits and their reversal are real changes to the DOM.
+        let events = dom.drain_journal();
+        assert!(events.len() > 10);
+    }
+
+    /// Changes made outside of a transaction can make parts of the history
+    /// impossible to undo, which should be skipped instead of panicking.
+    #[test]
+    fn undo_after_outside_changes() {
+        let mut dom = dom();
+        let a = find(&dom, "A");
+        let b = find(&dom, "B");
+
+        dom.begin_transaction();
+        dom.get_by_ref_mut(a).unwrap().name = "Renamed".to_owned();
+        dom.get_by_ref_mut(b)
+            .unwrap()
+            .properties
+            .insert("Value".into(), 2i32.into());
+        dom.commit_transaction();
+
+        dom.destroy(a);
+        assert!(dom.undo());
+        assert!(dom.get_by_ref(a).is_none());
+        assert_eq!(
+            dom.get_by_ref(b).unwrap().properties.get(&"Value".into()),
+            Some(&1i32.into())
+        );
+
+        dom.destroy(b);
+        assert!(dom.redo());
+        assert!(dom.get_by_ref(b).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn transfer_in_transaction() {
+        let mut dom = dom();
+        let mut other = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let part = find(&dom, "Part");
+        let other_root = other.root_ref();
+
+        dom.begin_transaction();
+        dom.transfer(part, &mut other, other_root);
+    }
+
+    #[test]
+    #[should_panic]
+    fn nested_transaction() {
+        let mut dom = dom();
+        dom.begin_transaction();
+        dom.begin_transaction();
+    }
+}
@@ -0,0 +1,353 @@
+use std::fmt;
+
+use rbx_reflection::{ClassTag, DataType, ReflectionDatabase};
+use rbx_types::{ContentType, Ref, Variant, VariantType};
+use ustr::Ustr;
+
+use crate::{Instance, WeakDom};
+
+/// A problem found by [`WeakDom::validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// The instance that has the problem.
+    pub referent: Ref,
+    /// What the problem is.
+    pub kind: ValidationIssueKind,
+}
+
+/// Describes a [`ValidationIssue`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ValidationIssueKind {
+    /// The instance's class isn't in the reflection database.
+    UnknownClass {
+        /// The class of the instance.
+        class: Ustr,
+    },
+
+    /// The instance has a property that its class doesn't have.
+    UnknownProperty {
+        /// The name of the property.
+        property: Ustr,
+    },
+
+    /// A property's value isn't of the type the reflection database expects.
+    TypeMismatch {
+        /// The name of the property.
+        property: Ustr,
+        /// The type the reflection database expects.
+        expected: VariantType,
+        /// The type of the value on the instance.
+        actual: VariantType,
+    },
+
+    /// An enum property's value isn't one of the enum's items.
+    InvalidEnumValue {
+        /// The name of the property.
+        property: Ustr,
+        /// The name of the enum, like `Material`.
+        enum_name: String,
+        /// The value on the instance.
+        value: u32,
+    },
+
+    /// A property refers to an instance that isn't in the DOM.
+    DanglingRef {
+        /// The name of the property.
+        property: Ustr,
+        /// The referent that couldn't be found.
+        target: Ref,
+    },
+
+    /// A service is parented to something other than the root of the DOM.
+    MisplacedService {
+        /// The class of the service.
+        class: Ustr,
+    },
+}
+
+impl fmt::Display for ValidationIssueKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssueKind::UnknownClass { class } => {
+                write!(formatter, "unknown class {class}")
+            }
+            ValidationIssueKind::UnknownProperty { property } => {
+                write!(formatter, "unknown property {property}")
+            }
+            ValidationIssueKind::TypeMismatch {
+                property,
+                expected,
+                actual,
+            } => write!(
+                formatter,
+                "property {property} should be {expected:?} but is {actual:?}"
+            ),
+            ValidationIssueKind::InvalidEnumValue {
+                property,
+                enum_name,
+                value,
+            } => write!(
+                formatter,
+                "property {property} has value {value}, which is not a member of enum {enum_name}"
+            ),
+            ValidationIssueKind::DanglingRef { property, target } => write!(
+                formatter,
+                "property {property} refers to {target}, which is not in the DOM"
+            ),
+            ValidationIssueKind::MisplacedService { class } => {
+                write!(formatter, "service {class} is not a child of the root")
+            }
+        }
+    }
+}
+
+impl WeakDom {
+    /// Checks every instance in the DOM against the given reflection database
+    /// and returns any problems that were found, in the order the instances
+    /// were visited.
+    ///
+    /// The value of each property is checked against the type the database
+    /// expects. Serializers are able to convert some mismatched types, like
+    /// `Int32` into `Int64`, but they're still reported.
+    pub fn validate(&self, database: &ReflectionDatabase) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        for instance in self.descendants() {
+            let mut report = |kind| {
+                issues.push(ValidationIssue {
+                    referent: instance.referent(),
+                    kind,
+                })
+            };
+
+            let class = match database.classes.get(instance.class.as_str()) {
+                Some(class) => class,
+                None => {
+                    report(ValidationIssueKind::UnknownClass {
+                        class: instance.class,
+                    });
+                    validate_refs(self, instance, &mut report);
+                    continue;
+                }
+            };
+
+            if class.tags.contains(&ClassTag::Service)
+                && instance.referent() != self.root_ref()
+                && instance.parent() != self.root_ref()
+            {
+                report(ValidationIssueKind::MisplacedService {
+                    class: instance.class,
+                });
+            }
+
+            let mut properties: Vec<_> = instance.properties.iter().collect();
+            properties.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+            for (&property, value) in properties {
+                let descriptor = database
+                    .superclasses_iter(class)
+                    .find_map(|class| class.properties.get(property.as_str()));
+
+                let descriptor = match descriptor {
+                    Some(descriptor) => descriptor,
+                    None => {
+                        report(ValidationIssueKind::UnknownProperty { property });
+                        continue;
+                    }
+                };
+
+                match &descriptor.data_type {
+                    DataType::Value(expected) if value.ty() != *expected => {
+                        report(ValidationIssueKind::TypeMismatch {
+                            property,
+                            expected: *expected,
+                            actual: value.ty(),
+                        });
+                    }
+                    DataType::Enum(enum_name) => {
+                        let number = match value {
+                            Variant::Enum(value) => value.to_u32(),
+                            Variant::EnumItem(item) => item.value,
+                            _ => {
+                                report(ValidationIssueKind::TypeMismatch {
+                                    property,
+                                    expected: VariantType::Enum,
+                                    actual: value.ty(),
+                                });
+                                continue;
+                            }
+                        };
+
+                        // Enums that the database doesn't know about can't be
+                        // checked, so they're assumed to be fine.
+                        let is_member = match database.enums.get(enum_name) {
+                            Some(descriptor) => {
+                                descriptor.items.values().any(|&item| item == number)
+                            }
+                            None => true,
+                        };
+
+                        if !is_member {
+                            report(ValidationIssueKind::InvalidEnumValue {
+                                property,
+                                enum_name: enum_name.to_string(),
+                                value: number,
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            validate_refs(self, instance, &mut report);
+        }
+
+        issues
+    }
+}
+
+fn validate_refs(dom: &WeakDom, instance: &Instance, report: &mut impl FnMut(ValidationIssueKind)) {
+    let mut properties: Vec<_> = instance.properties.iter().collect();
+    properties.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+    for (&property, value) in properties {
+        let target = match value {
+            Variant::Ref(target) => *target,
+            Variant::Content(content) => match content.value() {
+                ContentType::Object(target) => *target,
+                _ => continue,
+            },
+            _ => continue,
+        };
+
+        if target.is_some() && dom.get_by_ref(target).is_none() {
+            report(ValidationIssueKind::DanglingRef { property, target });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rbx_reflection::{ClassDescriptor, EnumDescriptor, PropertyDescriptor};
+    use rbx_types::{Enum, Vector3};
+
+    use crate::InstanceBuilder;
+
+    fn database() -> ReflectionDatabase<'static> {
+        let mut database = ReflectionDatabase::new();
+
+        let mut instance = ClassDescriptor::new("Instance");
+        instance.properties.insert(
+            "Archivable",
+            PropertyDescriptor::new("Archivable", DataType::Value(VariantType::Bool)),
+        );
+
+        let mut data_model = ClassDescriptor::new("DataModel");
+        data_model.superclass = Some("Instance");
+
+        let mut workspace = ClassDescriptor::new("Workspace");
+        workspace.superclass = Some("Instance");
+        workspace.tags.insert(ClassTag::Service);
+
+        let mut part = ClassDescriptor::new("Part");
+        part.superclass = Some("Instance");
+        part.properties.insert(
+            "Size",
+            PropertyDescriptor::new("Size", DataType::Value(VariantType::Vector3)),
+        );
+        part.properties.insert(
+            "Material",
+            PropertyDescriptor::new("Material", DataType::Enum("Material")),
+        );
+
+        let mut object_value = ClassDescriptor::new("ObjectValue");
+        object_value.superclass = Some("Instance");
+        object_value.properties.insert(
+            "Value",
+            PropertyDescriptor::new("Value", DataType::Value(VariantType::Ref)),
+        );
+
+        for class in [instance, data_model, workspace, part, object_value] {
+            database.classes.insert(class.name, class);
+        }
+
+        let mut material = EnumDescriptor::new("Material");
+        material.items.insert("Plastic", 256);
+        database.enums.insert("Material", material);
+
+        database
+    }
+
+    #[test]
+    fn valid() {
+        let dom = WeakDom::new(
+            InstanceBuilder::new("DataModel").with_child(
+                InstanceBuilder::new("Workspace").with_child(
+                    InstanceBuilder::new("Part")
+                        .with_property("Size", Vector3::new(1.0, 1.0, 1.0))
+                        .with_property("Material", Enum::from_u32(256))
+                        .with_property("Archivable", true),
+                ),
+            ),
+        );
+
+        assert_eq!(dom.validate(&database()), Vec::new());
+    }
+
+    #[test]
+    fn issues() {
+        let part = InstanceBuilder::new("Part")
+            .with_property("Size", 5.0f32)
+            .with_property("Material", Enum::from_u32(7))
+            .with_property("Color", 5i32);
+        let part_ref = part.referent();
+        let dangling = Ref::new();
+
+        let dom = WeakDom::new(
+            InstanceBuilder::new("DataModel")
+                .with_child(part.with_child(InstanceBuilder::new("Workspace")))
+                .with_child(InstanceBuilder::new("Mystery"))
+                .with_child(InstanceBuilder::new("ObjectValue").with_property("Value", dangling)),
+        );
+
+        let kinds: Vec<_> = dom
+            .validate(&database())
+            .into_iter()
+            .map(|issue| issue.kind)
+            .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                ValidationIssueKind::UnknownProperty {
+                    property: "Color".into()
+                },
+                ValidationIssueKind::InvalidEnumValue {
+                    property: "Material".into(),
+                    enum_name: "Material".to_owned(),
+                    value: 7,
+                },
+                ValidationIssueKind::TypeMismatch {
+                    property: "Size".into(),
+                    expected: VariantType::Vector3,
+                    actual: VariantType::Float32,
+                },
+                ValidationIssueKind::UnknownClass {
+                    class: "Mystery".into()
+                },
+                ValidationIssueKind::DanglingRef {
+                    property: "Value".into(),
+                    target: dangling,
+                },
+                ValidationIssueKind::MisplacedService {
+                    class: "Workspace".into()
+                },
+            ]
+        );
+
+        assert_eq!(dom.validate(&database())[0].referent, part_ref);
+    }
+}
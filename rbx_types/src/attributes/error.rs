@@ -18,8 +18,8 @@ pub(crate) enum AttributeError {
     #[error("missing attribute value type")]
     NoValueType,
 
-    #[error("invalid value type: {0}")]
-    InvalidValueType(u8),
+    #[error("attribute {0:?} might already be stored with an unsupported type")]
+    UnknownKey(String),
 
     #[error("{0:?} values are not supported in attributes")]
     UnsupportedVariantType(VariantType),
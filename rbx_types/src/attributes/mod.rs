@@ -30,6 +30,9 @@ pub(crate) use self::error::AttributeError;
 )]
 pub struct Attributes {
     data: BTreeMap<String, Variant>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    unknown: Option<UnknownAttributes>,
 }
 
 impl Attributes {
@@ -37,19 +40,32 @@ impl Attributes {
     pub const fn new() -> Self {
         Self {
             data: BTreeMap::new(),
+            unknown: None,
         }
     }
 
     /// Reads from a serialized attributes string, and produces a new `Attributes` from it.
+    ///
+    /// If an attribute has a type that isn't supported, it and the attributes
+    /// after it are kept as [`UnknownAttributes`] instead of causing an error.
     pub fn from_reader<R: Read>(reader: R) -> Result<Self, Error> {
-        Ok(Attributes {
-            data: read_attributes(reader)?,
-        })
+        let (data, unknown) = read_attributes(reader)?;
+
+        Ok(Attributes { data, unknown })
     }
 
-    /// Writes the attributes as a serialized string to the writer.
+    /// Writes the attributes as a serialized string to the writer, including
+    /// any [`UnknownAttributes`] exactly as they were read.
     pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<(), Error> {
-        write_attributes(&self.data, &mut writer).map_err(Into::into)
+        write_attributes(&self.data, self.unknown.as_ref(), &mut writer).map_err(Into::into)
+    }
+
+    /// Returns the attributes that couldn't be read by
+    /// [`Attributes::from_reader`] because of an unsupported type, if there
+    /// were any.
+    #[inline]
+    pub fn unknown(&self) -> Option<&UnknownAttributes> {
+        self.unknown.as_ref()
     }
 
     /// Get the attribute with the following key.
@@ -59,27 +75,54 @@ impl Attributes {
 
     /// Inserts an attribute with the given key and value.
     /// Will return the attribute that used to be there if one existed.
+    ///
+    /// Keys that might belong to one of the [`UnknownAttributes`] aren't
+    /// inserted, since they would be written twice, and `None` is returned
+    /// for them too. Use [`Attributes::try_insert`] to tell the two apart.
     pub fn insert(&mut self, key: String, value: Variant) -> Option<Variant> {
-        self.data.insert(key, value)
+        self.try_insert(key, value).ok().flatten()
+    }
+
+    /// Inserts an attribute with the given key and value.
+    /// Will return the attribute that used to be there if one existed.
+    ///
+    /// Returns an error without inserting anything if the key might belong to
+    /// one of the [`UnknownAttributes`]. See [`UnknownAttributes::contains_key`].
+    pub fn try_insert(&mut self, key: String, value: Variant) -> Result<Option<Variant>, Error> {
+        if self.is_unknown_key(&key) {
+            return Err(AttributeError::UnknownKey(key).into());
+        }
+
+        Ok(self.data.insert(key, value))
     }
 
     /// Inserts an attribute with the given key and value.
     /// Will overwrite the attribute that used to be there if one existed.
+    ///
+    /// Like [`Attributes::insert`], keys that might belong to one of the
+    /// [`UnknownAttributes`] aren't inserted.
     pub fn with<K: Into<String>, V: Into<Variant>>(mut self, key: K, value: V) -> Self {
-        self.data.insert(key.into(), value.into());
+        self.insert(key.into(), value.into());
         self
     }
 
+    fn is_unknown_key(&self, key: &str) -> bool {
+        self.unknown
+            .as_ref()
+            .is_some_and(|unknown| unknown.contains_key(key))
+    }
+
     /// Removes an attribute with the given key.
     /// Will return the value that was there if one existed.
     pub fn remove<K: Hash + Eq + Borrow<str>>(&mut self, key: K) -> Option<Variant> {
         self.data.remove(key.borrow())
     }
 
-    /// Removes all attributes.
+    /// Removes all attributes, including unknown ones.
     #[inline]
     pub fn clear(&mut self) {
-        self.data.clear()
+        self.data.clear();
+        self.unknown = None;
     }
 
     /// Returns an iterator of borrowed attributes.
@@ -98,13 +141,14 @@ impl Attributes {
         AttributesDrain { inner: self }
     }
 
-    /// Returns the number of attributes.
+    /// Returns the number of attributes, not counting unknown ones.
     #[inline]
     pub fn len(&self) -> usize {
         self.data.len()
     }
 
-    /// Returns true if the struct contains no attributes.
+    /// Returns true if the struct contains no attributes, not counting
+    /// unknown ones.
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
@@ -143,10 +187,65 @@ impl FromIterator<(String, Variant)> for Attributes {
     fn from_iter<T: IntoIterator<Item = (String, Variant)>>(iter: T) -> Self {
         Self {
             data: iter.into_iter().collect(),
+            unknown: None,
         }
     }
 }
 
+/// Attributes that [`Attributes::from_reader`] couldn't read because one of
+/// them has a type that isn't supported yet.
+///
+/// Attributes aren't stored with their length, so there's no way to skip over
+/// one with an unknown type. Instead, it and every attribute after it are kept
+/// as bytes and written back out unchanged by [`Attributes::to_writer`].
+/// They're lost when attributes are serialized with serde.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownAttributes {
+    key: String,
+    type_id: u8,
+    count: u32,
+    data: Vec<u8>,
+}
+
+impl UnknownAttributes {
+    /// The name of the first attribute that couldn't be read.
+    #[inline]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The type ID of the first attribute that couldn't be read.
+    #[inline]
+    pub fn type_id(&self) -> u8 {
+        self.type_id
+    }
+
+    /// How many attributes couldn't be read, including the first one.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    /// Tells whether one of these attributes might have the given key.
+    ///
+    /// Only the first key is known for sure, so the rest of the attributes
+    /// are searched for the key as it would be written. This can find a key
+    /// in the middle of a value, but never misses one that's there.
+    pub fn contains_key(&self, key: &str) -> bool {
+        if key == self.key {
+            return true;
+        }
+
+        let mut needle = Vec::with_capacity(4 + key.len());
+        needle.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        needle.extend_from_slice(key.as_bytes());
+
+        self.data
+            .windows(needle.len())
+            .any(|window| window == needle.as_slice())
+    }
+}
+
 /// An owning iterator over the entries of an `Attributes`.
 /// This is created by [`Attributes::into_iter`].
 pub struct AttributesIntoIter {
@@ -263,6 +362,96 @@ mod tests {
         );
     }
 
+    /// Attributes with types we don't know about should be kept instead of
+    /// making the rest unreadable.
+    #[test]
+    fn unknown_types() {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&3u32.to_le_bytes());
+        for (key, type_id, value) in [
+            ("Known", 0x03, &[1][..]),
+            ("Unknown", 0xFE, &[1, 2, 3, 4][..]),
+            ("AfterUnknown", 0x03, &[0][..]),
+        ] {
+            buffer.extend_from_slice(&(key.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(key.as_bytes());
+            buffer.push(type_id);
+            buffer.extend_from_slice(value);
+        }
+
+        let mut attributes = Attributes::from_reader(buffer.as_slice()).unwrap();
+        assert_eq!(attributes.len(), 1);
+        assert_eq!(attributes.get("Known"), Some(&Variant::Bool(true)));
+
+        let unknown = attributes.unknown().unwrap();
+        assert_eq!(unknown.key(), "Unknown");
+        assert_eq!(unknown.type_id(), 0xFE);
+        assert_eq!(unknown.count(), 2);
+
+        let mut written = Vec::new();
+        attributes.to_writer(&mut written).unwrap();
+        assert_eq!(written, buffer);
+
+        // Known attributes can still be changed.
+        attributes.remove("Known");
+        written.clear();
+        attributes.to_writer(&mut written).unwrap();
+        let reread = Attributes::from_reader(written.as_slice()).unwrap();
+        assert!(reread.is_empty());
+        assert_eq!(reread.unknown(), attributes.unknown());
+    }
+
+    /// Keys of unknown attributes shouldn't be added again, or they'd be
+    /// written twice.
+    #[test]
+    fn unknown_keys_are_not_inserted() {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&2u32.to_le_bytes());
+        for (key, type_id, value) in [
+            ("Unknown", 0xFE, &[1, 2, 3, 4][..]),
+            ("AfterUnknown", 0x03, &[0][..]),
+        ] {
+            buffer.extend_from_slice(&(key.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(key.as_bytes());
+            buffer.push(type_id);
+            buffer.extend_from_slice(value);
+        }
+
+        let mut attributes = Attributes::from_reader(buffer.as_slice()).unwrap();
+        let unknown = attributes.unknown().unwrap();
+        assert!(unknown.contains_key("Unknown"));
+        assert!(unknown.contains_key("AfterUnknown"));
+        assert!(!unknown.contains_key("New"));
+
+        assert_eq!(attributes.insert("Unknown".to_owned(), true.into()), None);
+        assert!(attributes
+            .try_insert("AfterUnknown".to_owned(), true.into())
+            .is_err());
+        assert_eq!(
+            attributes
+                .try_insert("Known".to_owned(), true.into())
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            attributes
+                .try_insert("Known".to_owned(), false.into())
+                .unwrap(),
+            Some(Variant::Bool(true))
+        );
+        attributes.remove("Known");
+        let attributes = attributes.with("AfterUnknown", false).with("New", 1.0f64);
+        assert!(attributes.get("Unknown").is_none());
+        assert!(attributes.get("AfterUnknown").is_none());
+        assert_eq!(attributes.get("New"), Some(&Variant::Float64(1.0)));
+
+        let mut written = Vec::new();
+        attributes.to_writer(&mut written).unwrap();
+        let reread = Attributes::from_reader(written.as_slice()).unwrap();
+        assert_eq!(reread.len(), 1);
+        assert_eq!(reread.unknown().unwrap().count(), 2);
+    }
+
     #[test]
     fn test_attribute_removal() {
         let mut attributes = Attributes::new();
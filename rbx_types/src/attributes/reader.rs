@@ -9,27 +9,47 @@ use crate::{
     UDim, UDim2, Variant, VariantType, Vector2, Vector3,
 };
 
-use super::{type_id, AttributeError};
+use super::{type_id, AttributeError, UnknownAttributes};
 
 /// Reads through an attribute property (AttributesSerialize) and returns a map of attribute names -> values.
+///
+/// Reading stops at the first attribute with a type we don't know, since we
+/// can't tell how long it is. That attribute and the ones after it are
+/// returned as they are.
 pub(crate) fn read_attributes<R: Read>(
     mut value: R,
-) -> Result<BTreeMap<String, Variant>, AttributeError> {
+) -> Result<(BTreeMap<String, Variant>, Option<UnknownAttributes>), AttributeError> {
     let mut attributes = BTreeMap::new();
 
     let len = match read_option_u32(&mut value) {
         Ok(Some(len)) => len,
-        Ok(None) => return Ok(attributes),
+        Ok(None) => return Ok((attributes, None)),
         Err(_) => return Err(AttributeError::InvalidLength),
     };
 
-    for _ in 0..len {
+    for index in 0..len {
         let key_buf = read_string(&mut value).map_err(|_| AttributeError::NoKey)?;
         let key = String::from_utf8(key_buf).map_err(AttributeError::KeyBadUnicode)?;
 
         let type_id = read_u8(&mut value).map_err(|_| AttributeError::NoValueType)?;
-        let ty =
-            type_id::to_variant_type(type_id).ok_or(AttributeError::InvalidValueType(type_id))?;
+        let ty = match type_id::to_variant_type(type_id) {
+            Some(ty) => ty,
+            None => {
+                let mut data = Vec::new();
+                data.extend_from_slice(&(key.len() as u32).to_le_bytes());
+                data.extend_from_slice(key.as_bytes());
+                data.push(type_id);
+                value.read_to_end(&mut data)?;
+
+                let unknown = UnknownAttributes {
+                    key,
+                    type_id,
+                    count: len - index,
+                    data,
+                };
+                return Ok((attributes, Some(unknown)));
+            }
+        };
 
         let value = match ty {
             VariantType::BrickColor => {
@@ -219,7 +239,7 @@ pub(crate) fn read_attributes<R: Read>(
         attributes.insert(key, value);
     }
 
-    Ok(attributes)
+    Ok((attributes, None))
 }
 
 fn read_u8<R: Read>(mut reader: R) -> io::Result<u8> {
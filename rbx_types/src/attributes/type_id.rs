@@ -20,6 +20,8 @@ macro_rules! type_ids {
     };
 }
 
+// Every type that Studio lets attributes hold is listed here. Attributes with
+// any other ID are kept as `UnknownAttributes` when they're read.
 type_ids! {
     // ??? => 0x01,
     BinaryString => 0x02,
@@ -3,7 +3,7 @@ use std::{
     io::{self, Write},
 };
 
-use super::{type_id, AttributeError};
+use super::{type_id, AttributeError, UnknownAttributes};
 
 use crate::{
     basic_types::{Color3, UDim, Vector2},
@@ -11,16 +11,19 @@ use crate::{
     Vector3,
 };
 
-/// Writes the attribute property (AttributesSerialize) from a map of attribute names -> values.
+/// Writes the attribute property (AttributesSerialize) from a map of attribute names -> values,
+/// followed by any attributes that couldn't be read.
 pub(crate) fn write_attributes<W: Write>(
     map: &BTreeMap<String, Variant>,
+    unknown: Option<&UnknownAttributes>,
     mut writer: W,
 ) -> Result<(), AttributeError> {
-    if map.is_empty() {
+    let unknown_count = unknown.map_or(0, |unknown| unknown.count);
+    if map.is_empty() && unknown_count == 0 {
         return Ok(());
     }
 
-    writer.write_all(&(map.len() as u32).to_le_bytes())?;
+    writer.write_all(&(map.len() as u32 + unknown_count).to_le_bytes())?;
 
     for (name, variant) in map {
         write_string(&mut writer, name)?;
@@ -108,6 +111,10 @@ pub(crate) fn write_attributes<W: Write>(
         }
     }
 
+    if let Some(unknown) = unknown {
+        writer.write_all(&unknown.data)?;
+    }
+
     Ok(())
 }
 
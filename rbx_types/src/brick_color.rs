@@ -1,6 +1,6 @@
-use std::fmt;
+use std::{fmt, sync::OnceLock};
 
-use crate::Color3uint8;
+use crate::{Color3, Color3uint8};
 
 macro_rules! make_brick_color {
     ({
@@ -25,6 +25,9 @@ macro_rules! make_brick_color {
         }
 
         impl BrickColor {
+            /// Every BrickColor, in the order they're declared.
+            const ALL: &'static [BrickColor] = &[$(BrickColor::$enum,)+];
+
             /// Find the first BrickColor with the given name, if it exists.
             ///
             /// Note that some colors (Lilac, Rust, Gold, and Deep orange) have
@@ -329,6 +332,67 @@ make_brick_color!({
     [HotPink, "Hot pink", 1032, (255, 0, 191)],
 });
 
+impl BrickColor {
+    /// Finds the BrickColor that looks the most like `color`. Colors are
+    /// compared by their distance in CIELAB, which matches how different
+    /// they look more closely than comparing their channels does.
+    ///
+    /// This is similar to `BrickColor.new(Color3)` from within Roblox, which
+    /// compares channels instead. HDR channels are clamped to 0 to 1, and
+    /// when several BrickColors are equally close, the first one declared is
+    /// picked.
+    pub fn from_color3(color: Color3) -> BrickColor {
+        let clamped = Color3::new(
+            color.r.clamp(0.0, 1.0),
+            color.g.clamp(0.0, 1.0),
+            color.b.clamp(0.0, 1.0),
+        );
+        let target = clamped.to_lab();
+
+        let distance = |lab: &[f32; 3]| {
+            let (l, a, b) = (lab[0] - target[0], lab[1] - target[1], lab[2] - target[2]);
+            l * l + a * a + b * b
+        };
+
+        let mut nearest = Self::ALL[0];
+        let mut nearest_distance = f32::INFINITY;
+        for (brick_color, lab) in Self::ALL.iter().zip(palette_lab()) {
+            let distance = distance(lab);
+            if distance < nearest_distance {
+                nearest = *brick_color;
+                nearest_distance = distance;
+            }
+        }
+
+        nearest
+    }
+
+    /// Finds the BrickColor that looks the most like `color`. See
+    /// [`BrickColor::from_color3`].
+    pub fn from_color3uint8(color: Color3uint8) -> BrickColor {
+        Self::from_color3(color.into())
+    }
+
+    /// Returns the color of this BrickColor as a [`Color3`], with each
+    /// channel from 0 to 1.
+    pub fn to_color3(&self) -> Color3 {
+        self.to_color3uint8().into()
+    }
+}
+
+/// The CIELAB value of every BrickColor, in the same order as
+/// `BrickColor::ALL`. It's only computed the first time it's needed.
+fn palette_lab() -> &'static [[f32; 3]] {
+    static PALETTE_LAB: OnceLock<Vec<[f32; 3]>> = OnceLock::new();
+
+    PALETTE_LAB.get_or_init(|| {
+        BrickColor::ALL
+            .iter()
+            .map(|brick_color| brick_color.to_color3().to_lab())
+            .collect()
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -345,6 +409,30 @@ mod tests {
     fn from_number() {
         assert_eq!(BrickColor::from_number(1030), Some(BrickColor::PastelBrown));
     }
+
+    #[test]
+    fn from_color() {
+        // Every BrickColor should map back to itself, unless an earlier one
+        // has the exact same color.
+        for brick_color in BrickColor::ALL {
+            let color = brick_color.to_color3uint8();
+            let first = BrickColor::ALL
+                .iter()
+                .find(|other| other.to_color3uint8() == color)
+                .unwrap();
+
+            assert_eq!(BrickColor::from_color3uint8(color), *first);
+        }
+
+        assert_eq!(
+            BrickColor::from_color3(Color3::new(1.0, 0.0, 0.0)),
+            BrickColor::ReallyRed
+        );
+        assert_eq!(
+            BrickColor::from_color3(Color3::new(4.0, 4.0, 4.0)),
+            BrickColor::InstitutionalWhite
+        );
+    }
 }
 
 #[cfg(all(test, feature = "serde"))]
@@ -0,0 +1,212 @@
+use thiserror::Error;
+
+use crate::{Color3, Color3uint8, Error};
+
+#[derive(Debug, Error)]
+pub(crate) enum ColorError {
+    #[error("'{hex}' is not a valid hex color")]
+    InvalidHex { hex: String },
+}
+
+impl Color3 {
+    /// Creates a color from hue, saturation, and value, each from 0 to 1,
+    /// like `Color3.fromHSV`. Hues outside of that range wrap around.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let hue = hue.rem_euclid(1.0) * 6.0;
+        let sector = hue.floor();
+        let fraction = hue - sector;
+
+        let p = value * (1.0 - saturation);
+        let q = value * (1.0 - saturation * fraction);
+        let t = value * (1.0 - saturation * (1.0 - fraction));
+
+        match sector as u8 % 6 {
+            0 => Self::new(value, t, p),
+            1 => Self::new(q, value, p),
+            2 => Self::new(p, value, t),
+            3 => Self::new(p, q, value),
+            4 => Self::new(t, p, value),
+            _ => Self::new(value, p, q),
+        }
+    }
+
+    /// Returns the hue, saturation, and value of this color, each from 0 to
+    /// 1, like `Color3:ToHSV`. Grays have a hue of 0.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let Self { r, g, b } = *self;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / delta + 2.0
+        } else {
+            (r - g) / delta + 4.0
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+        (hue / 6.0, saturation, max)
+    }
+
+    /// Parses a color written as `RRGGBB` or `RGB` in hexadecimal, with or
+    /// without a leading `#`, like `Color3.fromHex`.
+    pub fn from_hex(hex: &str) -> Result<Self, Error> {
+        let invalid = || ColorError::InvalidHex {
+            hex: hex.to_owned(),
+        };
+
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(invalid().into());
+        }
+
+        let channel = |digits: &str| u8::from_str_radix(digits, 16).map(f32::from);
+        let (r, g, b) = match digits.len() {
+            // Each digit is repeated, so F is FF.
+            3 => (
+                channel(&digits[0..1]).map(|value| value * 17.0),
+                channel(&digits[1..2]).map(|value| value * 17.0),
+                channel(&digits[2..3]).map(|value| value * 17.0),
+            ),
+            6 => (
+                channel(&digits[0..2]),
+                channel(&digits[2..4]),
+                channel(&digits[4..6]),
+            ),
+            _ => return Err(invalid().into()),
+        };
+
+        match (r, g, b) {
+            (Ok(r), Ok(g), Ok(b)) => Ok(Self::new(r / 255.0, g / 255.0, b / 255.0)),
+            _ => Err(invalid().into()),
+        }
+    }
+
+    /// Writes this color as `RRGGBB` in uppercase hexadecimal, like
+    /// `Color3:ToHex`. Channels outside of 0 to 1 are clamped.
+    pub fn to_hex(&self) -> String {
+        let Color3uint8 { r, g, b } = (*self).into();
+        format!("{:02X}{:02X}{:02X}", r, g, b)
+    }
+
+    /// Converts this color from sRGB, which is what Roblox stores colors as,
+    /// to linear RGB, where channels can be blended and scaled physically.
+    pub fn to_linear(&self) -> Self {
+        fn channel(value: f32) -> f32 {
+            if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        }
+
+        Self::new(channel(self.r), channel(self.g), channel(self.b))
+    }
+
+    /// Converts this color from linear RGB back to sRGB, undoing
+    /// [`Color3::to_linear`].
+    pub fn to_srgb(&self) -> Self {
+        fn channel(value: f32) -> f32 {
+            if value <= 0.0031308 {
+                value * 12.92
+            } else {
+                1.055 * value.powf(1.0 / 2.4) - 0.055
+            }
+        }
+
+        Self::new(channel(self.r), channel(self.g), channel(self.b))
+    }
+
+    /// Converts this color to CIELAB, where the distance between two colors
+    /// is close to how different they look.
+    pub(crate) fn to_lab(self) -> [f32; 3] {
+        let Self { r, g, b } = self.to_linear();
+
+        // sRGB primaries and the D65 white point.
+        let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / 0.950_47;
+        let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+        let z = (0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b) / 1.088_83;
+
+        fn f(t: f32) -> f32 {
+            const DELTA: f32 = 6.0 / 29.0;
+
+            if t > DELTA * DELTA * DELTA {
+                t.cbrt()
+            } else {
+                t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+            }
+        }
+
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_color_eq(actual: Color3, expected: Color3) {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+
+        assert!(
+            close(actual.r, expected.r)
+                && close(actual.g, expected.g)
+                && close(actual.b, expected.b),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn hsv() {
+        assert_color_eq(Color3::from_hsv(0.0, 1.0, 1.0), Color3::new(1.0, 0.0, 0.0));
+        assert_color_eq(
+            Color3::from_hsv(1.0 / 3.0, 1.0, 0.5),
+            Color3::new(0.0, 0.5, 0.0),
+        );
+        assert_color_eq(
+            Color3::from_hsv(1.5, 0.0, 0.25),
+            Color3::new(0.25, 0.25, 0.25),
+        );
+
+        let color = Color3::new(0.2, 0.4, 0.9);
+        let (h, s, v) = color.to_hsv();
+        assert_color_eq(Color3::from_hsv(h, s, v), color);
+        assert_eq!(Color3::new(0.5, 0.5, 0.5).to_hsv(), (0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn hex() {
+        let color = Color3::from(Color3uint8::new(0x12, 0xab, 0xff));
+        assert_eq!(color.to_hex(), "12ABFF");
+        assert_eq!(Color3::from_hex("#12abff").unwrap(), color);
+        assert_eq!(Color3::from_hex("12ABFF").unwrap(), color);
+        assert_eq!(
+            Color3::from_hex("#F80").unwrap(),
+            Color3::from(Color3uint8::new(0xff, 0x88, 0x00))
+        );
+
+        for invalid in &["", "#", "12ABF", "12ABFG", "#+1+2+3", "12ABFF00"] {
+            assert!(
+                Color3::from_hex(invalid).is_err(),
+                "{} should be invalid",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn linear() {
+        let color = Color3::new(0.0, 0.5, 1.0);
+        let linear = color.to_linear();
+
+        assert_color_eq(linear, Color3::new(0.0, 0.214_041_14, 1.0));
+        assert_color_eq(linear.to_srgb(), color);
+    }
+}
@@ -1,6 +1,9 @@
 use thiserror::Error;
 
-use crate::{AttributeError, MaterialColorsError, Matrix3Error, UniqueIdError};
+use crate::{
+    color::ColorError, sequence::SequenceError, AttributeError, MaterialColorsError, Matrix3Error,
+    UniqueIdError,
+};
 
 /// Represents an error that occurred when using a fallible method.
 #[derive(Debug, Error)]
@@ -17,6 +20,14 @@ impl From<AttributeError> for Error {
     }
 }
 
+impl From<ColorError> for Error {
+    fn from(source: ColorError) -> Self {
+        Self {
+            source: Box::new(source.into()),
+        }
+    }
+}
+
 impl From<Matrix3Error> for Error {
     fn from(source: Matrix3Error) -> Self {
         Self {
@@ -33,6 +44,14 @@ impl From<MaterialColorsError> for Error {
     }
 }
 
+impl From<SequenceError> for Error {
+    fn from(source: SequenceError) -> Self {
+        Self {
+            source: Box::new(source.into()),
+        }
+    }
+}
+
 impl From<UniqueIdError> for Error {
     fn from(source: UniqueIdError) -> Self {
         Self {
@@ -46,12 +65,18 @@ enum InnerError {
     #[error(transparent)]
     Attribute(#[from] AttributeError),
 
+    #[error(transparent)]
+    Color(#[from] ColorError),
+
     #[error(transparent)]
     Matrix3(#[from] Matrix3Error),
 
     #[error(transparent)]
     MaterialColors(#[from] MaterialColorsError),
 
+    #[error(transparent)]
+    Sequence(#[from] SequenceError),
+
     #[error(transparent)]
     UniqueId(#[from] UniqueIdError),
 }
@@ -7,15 +7,19 @@ mod axes;
 mod basic_types;
 mod binary_string;
 mod brick_color;
+mod color;
 mod content;
 mod error;
 mod faces;
 mod font;
 mod lister;
 mod material_colors;
+#[cfg(feature = "math")]
+mod math;
 mod physical_properties;
 mod referent;
 mod security_capabilities;
+mod sequence;
 mod shared_string;
 mod tags;
 mod unique_id;
@@ -0,0 +1,869 @@
+//! Vector and CFrame math that behaves like the equivalent Luau operations,
+//! enabled with the `math` feature.
+//!
+//! `Matrix3` is stored as rows, the same way Roblox lays out the components
+//! returned by `CFrame:GetComponents()`. Angles are in radians.
+
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::{CFrame, Matrix3, Vector3};
+
+/// Below this, a length or sine is treated as zero when picking a fallback
+/// for degenerate inputs.
+const EPSILON: f32 = 1e-6;
+
+impl Vector3 {
+    /// The vector with every component set to 0, like `Vector3.zero`.
+    pub const fn zero() -> Self {
+        Self::new(0.0, 0.0, 0.0)
+    }
+
+    /// The vector with every component set to 1, like `Vector3.one`.
+    pub const fn one() -> Self {
+        Self::new(1.0, 1.0, 1.0)
+    }
+
+    /// The unit vector along the X axis, like `Vector3.xAxis`.
+    pub const fn x_axis() -> Self {
+        Self::new(1.0, 0.0, 0.0)
+    }
+
+    /// The unit vector along the Y axis, like `Vector3.yAxis`.
+    pub const fn y_axis() -> Self {
+        Self::new(0.0, 1.0, 0.0)
+    }
+
+    /// The unit vector along the Z axis, like `Vector3.zAxis`.
+    pub const fn z_axis() -> Self {
+        Self::new(0.0, 0.0, 1.0)
+    }
+
+    /// Returns the dot product of the two vectors, like `Vector3:Dot`.
+    pub fn dot(self, other: Self) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Returns the cross product of the two vectors, like `Vector3:Cross`.
+    pub fn cross(self, other: Self) -> Self {
+        Self::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    /// Returns the length of the vector, like `Vector3.Magnitude`.
+    pub fn magnitude(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// Returns a vector in the same direction with a magnitude of 1. Like in
+    /// Luau, the zero vector has no direction, so every component of its
+    /// unit vector is NaN.
+    pub fn unit(self) -> Self {
+        self / self.magnitude()
+    }
+
+    /// Returns the absolute value of each component, like `Vector3:Abs`.
+    pub fn abs(self) -> Self {
+        Self::new(self.x.abs(), self.y.abs(), self.z.abs())
+    }
+
+    /// Returns the smaller of each pair of components, like `Vector3:Min`.
+    pub fn min(self, other: Self) -> Self {
+        Self::new(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.z.min(other.z),
+        )
+    }
+
+    /// Returns the larger of each pair of components, like `Vector3:Max`.
+    pub fn max(self, other: Self) -> Self {
+        Self::new(
+            self.x.max(other.x),
+            self.y.max(other.y),
+            self.z.max(other.z),
+        )
+    }
+
+    /// Linearly interpolates between this vector and `goal`, where an `alpha`
+    /// of 0 is this vector and 1 is `goal`.
+    pub fn lerp(self, goal: Self, alpha: f32) -> Self {
+        self + (goal - self) * alpha
+    }
+
+    /// Returns the angle between this vector and `other`, from 0 to π.
+    pub fn angle(self, other: Self) -> f32 {
+        self.cross(other).magnitude().atan2(self.dot(other))
+    }
+
+    /// Tells whether every component of this vector is within `epsilon` of
+    /// the same component of `other`.
+    pub fn fuzzy_eq(self, other: Self, epsilon: f32) -> bool {
+        (self.x - other.x).abs() <= epsilon
+            && (self.y - other.y).abs() <= epsilon
+            && (self.z - other.z).abs() <= epsilon
+    }
+}
+
+impl Add for Vector3 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+/// Multiplies component-wise, like `Vector3 * Vector3` in Luau.
+impl Mul for Vector3 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(self.x * other.x, self.y * other.y, self.z * other.z)
+    }
+}
+
+/// Divides component-wise, like `Vector3 / Vector3` in Luau.
+impl Div for Vector3 {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        Self::new(self.x / other.x, self.y / other.y, self.z / other.z)
+    }
+}
+
+impl Mul<f32> for Vector3 {
+    type Output = Self;
+
+    fn mul(self, scalar: f32) -> Self {
+        Self::new(self.x * scalar, self.y * scalar, self.z * scalar)
+    }
+}
+
+impl Mul<Vector3> for f32 {
+    type Output = Vector3;
+
+    fn mul(self, vector: Vector3) -> Vector3 {
+        vector * self
+    }
+}
+
+impl Div<f32> for Vector3 {
+    type Output = Self;
+
+    fn div(self, scalar: f32) -> Self {
+        Self::new(self.x / scalar, self.y / scalar, self.z / scalar)
+    }
+}
+
+impl Neg for Vector3 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl AddAssign for Vector3 {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vector3 {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign<f32> for Vector3 {
+    fn mul_assign(&mut self, scalar: f32) {
+        *self = *self * scalar;
+    }
+}
+
+impl DivAssign<f32> for Vector3 {
+    fn div_assign(&mut self, scalar: f32) {
+        *self = *self / scalar;
+    }
+}
+
+impl Matrix3 {
+    /// Creates a matrix from its columns, which are the right, up, and back
+    /// vectors of a rotation.
+    pub fn from_columns(x: Vector3, y: Vector3, z: Vector3) -> Self {
+        Self::new(
+            Vector3::new(x.x, y.x, z.x),
+            Vector3::new(x.y, y.y, z.y),
+            Vector3::new(x.z, y.z, z.z),
+        )
+    }
+
+    /// Creates a rotation that's applied in Z, Y, X order, like
+    /// `CFrame.fromEulerAnglesXYZ` and `CFrame.Angles`.
+    pub fn from_euler_angles_xyz(rx: f32, ry: f32, rz: f32) -> Self {
+        rotation_x(rx) * rotation_y(ry) * rotation_z(rz)
+    }
+
+    /// Creates a rotation that's applied in Z, X, Y order, like
+    /// `CFrame.fromEulerAnglesYXZ` and `CFrame.fromOrientation`.
+    pub fn from_euler_angles_yxz(rx: f32, ry: f32, rz: f32) -> Self {
+        rotation_y(ry) * rotation_x(rx) * rotation_z(rz)
+    }
+
+    /// Creates a rotation of `angle` around `axis`, like
+    /// `CFrame.fromAxisAngle`. The axis doesn't need to be a unit vector.
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Self {
+        let Vector3 { x, y, z } = axis.unit();
+        let (sin, cos) = angle.sin_cos();
+        let t = 1.0 - cos;
+
+        Self::new(
+            Vector3::new(t * x * x + cos, t * x * y - sin * z, t * x * z + sin * y),
+            Vector3::new(t * x * y + sin * z, t * y * y + cos, t * y * z - sin * x),
+            Vector3::new(t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos),
+        )
+    }
+
+    /// Returns the angles that [`Matrix3::from_euler_angles_xyz`] would need
+    /// to create this rotation, like `CFrame:ToEulerAnglesXYZ`.
+    pub fn to_euler_angles_xyz(&self) -> (f32, f32, f32) {
+        let ry = self.x.z.clamp(-1.0, 1.0).asin();
+
+        if 1.0 - self.x.z.abs() > EPSILON {
+            let rx = (-self.y.z).atan2(self.z.z);
+            let rz = (-self.x.y).atan2(self.x.x);
+            (rx, ry, rz)
+        } else {
+            // In gimbal lock, X and Z rotate around the same axis, so all of
+            // the rotation is put into X.
+            (self.z.y.atan2(self.y.y), ry, 0.0)
+        }
+    }
+
+    /// Returns the angles that [`Matrix3::from_euler_angles_yxz`] would need
+    /// to create this rotation, like `CFrame:ToEulerAnglesYXZ` and
+    /// `CFrame:ToOrientation`.
+    pub fn to_euler_angles_yxz(&self) -> (f32, f32, f32) {
+        let rx = (-self.y.z).clamp(-1.0, 1.0).asin();
+
+        if 1.0 - self.y.z.abs() > EPSILON {
+            let ry = self.x.z.atan2(self.z.z);
+            let rz = self.y.x.atan2(self.y.y);
+            (rx, ry, rz)
+        } else {
+            // In gimbal lock, Y and Z rotate around the same axis, so all of
+            // the rotation is put into Y.
+            (rx, (-self.z.x).atan2(self.x.x), 0.0)
+        }
+    }
+
+    /// Returns a unit axis and an angle from 0 to π that
+    /// [`Matrix3::from_axis_angle`] would need to create this rotation, like
+    /// `CFrame:ToAxisAngle`. Rotations of zero return the X axis.
+    pub fn to_axis_angle(&self) -> (Vector3, f32) {
+        let [x, y, z, w] = self.to_quaternion();
+        let axis = Vector3::new(x, y, z);
+        let sin = axis.magnitude();
+
+        if sin < EPSILON {
+            (Vector3::x_axis(), 0.0)
+        } else {
+            (axis / sin, 2.0 * sin.atan2(w))
+        }
+    }
+
+    /// The right vector of the rotation, or its first column.
+    pub fn right_vector(&self) -> Vector3 {
+        Vector3::new(self.x.x, self.y.x, self.z.x)
+    }
+
+    /// The up vector of the rotation, or its second column.
+    pub fn up_vector(&self) -> Vector3 {
+        Vector3::new(self.x.y, self.y.y, self.z.y)
+    }
+
+    /// The direction that the rotation faces, which is the negated third
+    /// column.
+    pub fn look_vector(&self) -> Vector3 {
+        -Vector3::new(self.x.z, self.y.z, self.z.z)
+    }
+
+    /// Returns the determinant of the matrix, which is 1 for rotations.
+    pub fn determinant(&self) -> f32 {
+        self.x.dot(self.y.cross(self.z))
+    }
+
+    /// Returns the inverse of this matrix. Matrices with a determinant of
+    /// zero have no inverse, and return a matrix of NaN or infinity.
+    ///
+    /// For rotations, [`Matrix3::transpose`] is the same thing and is exact.
+    pub fn inverse(&self) -> Self {
+        // The columns of the adjugate are cross products of the rows.
+        let adjugate = Self::from_columns(
+            self.y.cross(self.z),
+            self.z.cross(self.x),
+            self.x.cross(self.y),
+        );
+        let inverse_determinant = 1.0 / self.determinant();
+
+        Self::new(
+            adjugate.x * inverse_determinant,
+            adjugate.y * inverse_determinant,
+            adjugate.z * inverse_determinant,
+        )
+    }
+
+    /// Turns this matrix back into a rotation after floating point error or
+    /// scaling has crept in, like `CFrame:Orthonormalize`.
+    ///
+    /// The right vector keeps its direction, the up vector is made
+    /// perpendicular to it, and the back vector is rebuilt from both.
+    pub fn orthonormalize(&self) -> Self {
+        let right = self.right_vector().unit();
+        let up = self.up_vector();
+        let up = (up - right * right.dot(up)).unit();
+
+        Self::from_columns(right, up, right.cross(up))
+    }
+
+    /// Returns this rotation as a unit quaternion in `[x, y, z, w]` order
+    /// with a non-negative `w`.
+    fn to_quaternion(self) -> [f32; 4] {
+        let trace = self.x.x + self.y.y + self.z.z;
+
+        // Builds from whichever component is largest to keep precision.
+        let quaternion = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            [
+                (self.z.y - self.y.z) / s,
+                (self.x.z - self.z.x) / s,
+                (self.y.x - self.x.y) / s,
+                0.25 * s,
+            ]
+        } else if self.x.x > self.y.y && self.x.x > self.z.z {
+            let s = (1.0 + self.x.x - self.y.y - self.z.z).sqrt() * 2.0;
+            [
+                0.25 * s,
+                (self.x.y + self.y.x) / s,
+                (self.x.z + self.z.x) / s,
+                (self.z.y - self.y.z) / s,
+            ]
+        } else if self.y.y > self.z.z {
+            let s = (1.0 + self.y.y - self.x.x - self.z.z).sqrt() * 2.0;
+            [
+                (self.x.y + self.y.x) / s,
+                0.25 * s,
+                (self.y.z + self.z.y) / s,
+                (self.x.z - self.z.x) / s,
+            ]
+        } else {
+            let s = (1.0 + self.z.z - self.x.x - self.y.y).sqrt() * 2.0;
+            [
+                (self.x.z + self.z.x) / s,
+                (self.y.z + self.z.y) / s,
+                0.25 * s,
+                (self.y.x - self.x.y) / s,
+            ]
+        };
+
+        if quaternion[3] < 0.0 {
+            quaternion.map(|component| -component)
+        } else {
+            quaternion
+        }
+    }
+}
+
+fn rotation_x(angle: f32) -> Matrix3 {
+    let (sin, cos) = angle.sin_cos();
+    Matrix3::new(
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, cos, -sin),
+        Vector3::new(0.0, sin, cos),
+    )
+}
+
+fn rotation_y(angle: f32) -> Matrix3 {
+    let (sin, cos) = angle.sin_cos();
+    Matrix3::new(
+        Vector3::new(cos, 0.0, sin),
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(-sin, 0.0, cos),
+    )
+}
+
+fn rotation_z(angle: f32) -> Matrix3 {
+    let (sin, cos) = angle.sin_cos();
+    Matrix3::new(
+        Vector3::new(cos, -sin, 0.0),
+        Vector3::new(sin, cos, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+    )
+}
+
+impl Mul for Matrix3 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let columns = other.transpose();
+        let row =
+            |row: Vector3| Vector3::new(row.dot(columns.x), row.dot(columns.y), row.dot(columns.z));
+
+        Self::new(row(self.x), row(self.y), row(self.z))
+    }
+}
+
+impl Mul<Vector3> for Matrix3 {
+    type Output = Vector3;
+
+    fn mul(self, vector: Vector3) -> Vector3 {
+        Vector3::new(self.x.dot(vector), self.y.dot(vector), self.z.dot(vector))
+    }
+}
+
+impl CFrame {
+    /// Creates a CFrame at `position` with no rotation, like `CFrame.new`
+    /// with a single `Vector3`.
+    pub fn from_position(position: Vector3) -> Self {
+        Self::new(position, Matrix3::identity())
+    }
+
+    /// Creates a CFrame from a position and the right, up, and back vectors
+    /// of its rotation, like `CFrame.fromMatrix`.
+    pub fn from_matrix(position: Vector3, x: Vector3, y: Vector3, z: Vector3) -> Self {
+        Self::new(position, Matrix3::from_columns(x, y, z))
+    }
+
+    /// Creates a CFrame at `position` facing toward `target`, like
+    /// `CFrame.lookAt`. Its up vector is as close to `up` as it can be, which
+    /// is usually [`Vector3::y_axis`].
+    ///
+    /// If `target` is straight along `up`, another up vector is picked so the
+    /// result is still a rotation. If `target` is `position`, the result
+    /// faces forward. NaN or infinite positions give a rotation of NaN
+    /// instead of panicking.
+    pub fn look_at(position: Vector3, target: Vector3, up: Vector3) -> Self {
+        let look = target - position;
+        if look.magnitude() < EPSILON {
+            return Self::from_position(position);
+        }
+        let look = look.unit();
+
+        // A non-finite look vector never passes the check, so it falls
+        // through to the last candidate and gives a rotation of NaN.
+        let candidates = [up, Vector3::z_axis(), Vector3::x_axis()].map(|up| look.cross(up));
+        let right = candidates
+            .iter()
+            .copied()
+            .find(|right| right.magnitude() >= EPSILON)
+            .unwrap_or(candidates[2])
+            .unit();
+
+        Self::from_matrix(position, right, right.cross(look), -look)
+    }
+
+    /// Creates a rotation at the origin, like `CFrame.fromEulerAnglesXYZ`
+    /// and `CFrame.Angles`.
+    pub fn from_euler_angles_xyz(rx: f32, ry: f32, rz: f32) -> Self {
+        Self::new(Vector3::zero(), Matrix3::from_euler_angles_xyz(rx, ry, rz))
+    }
+
+    /// Creates a rotation at the origin, like `CFrame.fromEulerAnglesYXZ`
+    /// and `CFrame.fromOrientation`.
+    pub fn from_euler_angles_yxz(rx: f32, ry: f32, rz: f32) -> Self {
+        Self::new(Vector3::zero(), Matrix3::from_euler_angles_yxz(rx, ry, rz))
+    }
+
+    /// Creates a rotation at the origin, like `CFrame.fromAxisAngle`.
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Self {
+        Self::new(Vector3::zero(), Matrix3::from_axis_angle(axis, angle))
+    }
+
+    /// Like `CFrame:ToEulerAnglesXYZ`. See [`Matrix3::to_euler_angles_xyz`].
+    pub fn to_euler_angles_xyz(&self) -> (f32, f32, f32) {
+        self.orientation.to_euler_angles_xyz()
+    }
+
+    /// Like `CFrame:ToEulerAnglesYXZ` and `CFrame:ToOrientation`. See
+    /// [`Matrix3::to_euler_angles_yxz`].
+    pub fn to_euler_angles_yxz(&self) -> (f32, f32, f32) {
+        self.orientation.to_euler_angles_yxz()
+    }
+
+    /// Like `CFrame:ToAxisAngle`. See [`Matrix3::to_axis_angle`].
+    pub fn to_axis_angle(&self) -> (Vector3, f32) {
+        self.orientation.to_axis_angle()
+    }
+
+    /// The right vector of the rotation, like `CFrame.RightVector`.
+    pub fn right_vector(&self) -> Vector3 {
+        self.orientation.right_vector()
+    }
+
+    /// The up vector of the rotation, like `CFrame.UpVector`.
+    pub fn up_vector(&self) -> Vector3 {
+        self.orientation.up_vector()
+    }
+
+    /// The direction that the CFrame faces, like `CFrame.LookVector`.
+    pub fn look_vector(&self) -> Vector3 {
+        self.orientation.look_vector()
+    }
+
+    /// Returns the inverse of this CFrame, like `CFrame:Inverse`. The
+    /// orientation is assumed to be a rotation, so it's transposed rather
+    /// than inverted.
+    pub fn inverse(&self) -> Self {
+        let orientation = self.orientation.transpose();
+        Self::new(orientation * -self.position, orientation)
+    }
+
+    /// Like `CFrame:Orthonormalize`. See [`Matrix3::orthonormalize`].
+    pub fn orthonormalize(&self) -> Self {
+        Self::new(self.position, self.orientation.orthonormalize())
+    }
+
+    /// Treats `other` as relative to this CFrame and returns it in world
+    /// space. This is the same as `self * other`.
+    pub fn to_world_space(&self, other: CFrame) -> CFrame {
+        *self * other
+    }
+
+    /// Returns `other` relative to this CFrame, undoing
+    /// [`CFrame::to_world_space`].
+    pub fn to_object_space(&self, other: CFrame) -> CFrame {
+        self.inverse() * other
+    }
+
+    /// Moves `point` from this CFrame's space into world space. This is the
+    /// same as `self * point`.
+    pub fn point_to_world_space(&self, point: Vector3) -> Vector3 {
+        *self * point
+    }
+
+    /// Returns `point` relative to this CFrame, undoing
+    /// [`CFrame::point_to_world_space`].
+    pub fn point_to_object_space(&self, point: Vector3) -> Vector3 {
+        self.orientation.transpose() * (point - self.position)
+    }
+
+    /// Rotates `vector` by this CFrame without moving it.
+    pub fn vector_to_world_space(&self, vector: Vector3) -> Vector3 {
+        self.orientation * vector
+    }
+
+    /// Rotates `vector` into this CFrame's space without moving it, undoing
+    /// [`CFrame::vector_to_world_space`].
+    pub fn vector_to_object_space(&self, vector: Vector3) -> Vector3 {
+        self.orientation.transpose() * vector
+    }
+
+    /// Interpolates between this CFrame and `goal`, where an `alpha` of 0 is
+    /// this CFrame and 1 is `goal`. The position moves in a straight line and
+    /// the rotation turns around a single axis, like `CFrame:Lerp`.
+    pub fn lerp(&self, goal: CFrame, alpha: f32) -> CFrame {
+        let (axis, angle) = (self.orientation.transpose() * goal.orientation).to_axis_angle();
+
+        Self::new(
+            self.position.lerp(goal.position, alpha),
+            self.orientation * Matrix3::from_axis_angle(axis, angle * alpha),
+        )
+    }
+
+    /// Tells whether every component of this CFrame is within `epsilon` of
+    /// the same component of `other`.
+    pub fn fuzzy_eq(&self, other: CFrame, epsilon: f32) -> bool {
+        self.position.fuzzy_eq(other.position, epsilon)
+            && self.orientation.x.fuzzy_eq(other.orientation.x, epsilon)
+            && self.orientation.y.fuzzy_eq(other.orientation.y, epsilon)
+            && self.orientation.z.fuzzy_eq(other.orientation.z, epsilon)
+    }
+}
+
+/// Composes two CFrames, like `CFrame * CFrame` in Luau.
+impl Mul for CFrame {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.position + self.orientation * other.position,
+            self.orientation * other.orientation,
+        )
+    }
+}
+
+/// Transforms a point from this CFrame's space to world space.
+impl Mul<Vector3> for CFrame {
+    type Output = Vector3;
+
+    fn mul(self, point: Vector3) -> Vector3 {
+        self.position + self.orientation * point
+    }
+}
+
+impl MulAssign for CFrame {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+/// Moves the CFrame without rotating it.
+impl Add<Vector3> for CFrame {
+    type Output = Self;
+
+    fn add(self, offset: Vector3) -> Self {
+        Self::new(self.position + offset, self.orientation)
+    }
+}
+
+/// Moves the CFrame without rotating it.
+impl Sub<Vector3> for CFrame {
+    type Output = Self;
+
+    fn sub(self, offset: Vector3) -> Self {
+        Self::new(self.position - offset, self.orientation)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    const TOLERANCE: f32 = 1e-5;
+
+    fn assert_cframe_eq(actual: CFrame, expected: CFrame) {
+        assert!(
+            actual.fuzzy_eq(expected, TOLERANCE),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn assert_vector_eq(actual: Vector3, expected: Vector3) {
+        assert!(
+            actual.fuzzy_eq(expected, TOLERANCE),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn assert_angles_eq(actual: (f32, f32, f32), expected: (f32, f32, f32)) {
+        assert_vector_eq(
+            Vector3::new(actual.0, actual.1, actual.2),
+            Vector3::new(expected.0, expected.1, expected.2),
+        );
+    }
+
+    #[test]
+    fn vector_ops() {
+        let a = Vector3::new(1.0, 2.0, 3.0);
+        let b = Vector3::new(4.0, -5.0, 6.0);
+
+        assert_eq!(a + b, Vector3::new(5.0, -3.0, 9.0));
+        assert_eq!(a - b, Vector3::new(-3.0, 7.0, -3.0));
+        assert_eq!(a * b, Vector3::new(4.0, -10.0, 18.0));
+        assert_eq!(2.0 * a, a * 2.0);
+        assert_eq!(-a / 2.0, Vector3::new(-0.5, -1.0, -1.5));
+        assert_eq!(a.dot(b), 12.0);
+        assert_eq!(
+            Vector3::x_axis().cross(Vector3::y_axis()),
+            Vector3::z_axis()
+        );
+        assert_eq!(Vector3::new(3.0, 0.0, 4.0).magnitude(), 5.0);
+        assert_vector_eq(Vector3::new(0.0, 3.0, 0.0).unit(), Vector3::y_axis());
+        assert!(Vector3::zero().unit().x.is_nan());
+        assert!((Vector3::x_axis().angle(Vector3::y_axis()) - FRAC_PI_2).abs() < TOLERANCE);
+        assert_eq!(a.lerp(b, 0.5), Vector3::new(2.5, -1.5, 4.5));
+    }
+
+    /// Rotations should match what Roblox produces for the same arguments.
+    #[test]
+    fn rotations_match_roblox() {
+        // CFrame.Angles(math.pi / 2, 0, 0)
+        assert_cframe_eq(
+            CFrame::from_euler_angles_xyz(FRAC_PI_2, 0.0, 0.0),
+            CFrame::new(
+                Vector3::zero(),
+                Matrix3::from_basic_rotation_id(0x03).unwrap(),
+            ),
+        );
+
+        // CFrame.fromAxisAngle(Vector3.yAxis, math.pi / 2)
+        let rotation = CFrame::from_axis_angle(Vector3::y_axis(), FRAC_PI_2);
+        assert_vector_eq(rotation.look_vector(), -Vector3::x_axis());
+        assert_vector_eq(rotation * Vector3::x_axis(), -Vector3::z_axis());
+
+        // CFrame.lookAt(Vector3.zero, Vector3.new(-1, 0, 0))
+        let look = CFrame::look_at(Vector3::zero(), -Vector3::x_axis(), Vector3::y_axis());
+        assert_cframe_eq(look, rotation);
+    }
+
+    #[test]
+    fn euler_angles_round_trip() {
+        // Each of these is in the range that both extractors return.
+        let angles = [(0.3, -1.1, 2.5), (-1.2, 0.9, -2.6), (0.0, 0.0, 0.0)];
+
+        for &(rx, ry, rz) in &angles {
+            let xyz = Matrix3::from_euler_angles_xyz(rx, ry, rz);
+            assert_angles_eq(xyz.to_euler_angles_xyz(), (rx, ry, rz));
+
+            let yxz = Matrix3::from_euler_angles_yxz(rx, ry, rz);
+            assert_angles_eq(yxz.to_euler_angles_yxz(), (rx, ry, rz));
+        }
+
+        // In gimbal lock, the angles can't be recovered, but the rotation can.
+        let locked = Matrix3::from_euler_angles_xyz(0.5, FRAC_PI_2, 0.25);
+        let (rx, ry, rz) = locked.to_euler_angles_xyz();
+        assert_cframe_eq(
+            CFrame::from_euler_angles_xyz(rx, ry, rz),
+            CFrame::new(Vector3::zero(), locked),
+        );
+
+        let locked = Matrix3::from_euler_angles_yxz(-FRAC_PI_2, 0.5, 0.25);
+        let (rx, ry, rz) = locked.to_euler_angles_yxz();
+        assert_cframe_eq(
+            CFrame::from_euler_angles_yxz(rx, ry, rz),
+            CFrame::new(Vector3::zero(), locked),
+        );
+    }
+
+    #[test]
+    fn axis_angle_round_trip() {
+        let axis = Vector3::new(1.0, 2.0, -2.0).unit();
+
+        for &angle in &[0.5, 2.0, 3.0] {
+            let (actual_axis, actual_angle) = Matrix3::from_axis_angle(axis, angle).to_axis_angle();
+            assert_vector_eq(actual_axis, axis);
+            assert!((actual_angle - angle).abs() < TOLERANCE);
+        }
+
+        assert_eq!(
+            Matrix3::identity().to_axis_angle(),
+            (Vector3::x_axis(), 0.0)
+        );
+    }
+
+    #[test]
+    fn spaces() {
+        let parent = CFrame::new(
+            Vector3::new(10.0, -4.0, 2.0),
+            Matrix3::from_euler_angles_yxz(0.2, 1.3, -0.7),
+        );
+        let child = CFrame::new(
+            Vector3::new(1.0, 2.0, 3.0),
+            Matrix3::from_axis_angle(Vector3::new(0.0, 1.0, 1.0), 0.8),
+        );
+
+        assert_cframe_eq(parent * parent.inverse(), CFrame::identity());
+        assert_cframe_eq(parent.to_object_space(parent.to_world_space(child)), child);
+
+        let point = Vector3::new(-3.0, 5.0, 1.0);
+        assert_vector_eq(
+            parent.point_to_object_space(parent.point_to_world_space(point)),
+            point,
+        );
+        assert_vector_eq(
+            parent.vector_to_world_space(point),
+            parent * point - parent.position,
+        );
+        assert_vector_eq(
+            parent.vector_to_object_space(parent.vector_to_world_space(point)),
+            point,
+        );
+        assert_cframe_eq((parent + point) - point, parent);
+    }
+
+    #[test]
+    fn matrix_inverse() {
+        let matrix = Matrix3::new(
+            Vector3::new(2.0, 0.0, 1.0),
+            Vector3::new(1.0, 3.0, 0.0),
+            Vector3::new(0.0, 1.0, 4.0),
+        );
+
+        assert_eq!(matrix.determinant(), 25.0);
+        assert_cframe_eq(
+            CFrame::new(Vector3::zero(), matrix * matrix.inverse()),
+            CFrame::identity(),
+        );
+    }
+
+    #[test]
+    fn orthonormalize() {
+        let rotation = Matrix3::from_euler_angles_xyz(0.4, -0.2, 1.0);
+        let skewed = Matrix3::from_columns(
+            rotation.right_vector() * 2.0,
+            rotation.up_vector() + rotation.right_vector() * 0.1,
+            -rotation.look_vector(),
+        );
+
+        let fixed = skewed.orthonormalize();
+        assert!((fixed.determinant() - 1.0).abs() < TOLERANCE);
+        assert_cframe_eq(
+            CFrame::new(Vector3::zero(), fixed),
+            CFrame::new(Vector3::zero(), rotation),
+        );
+    }
+
+    #[test]
+    fn look_at_degenerate() {
+        let position = Vector3::new(1.0, 2.0, 3.0);
+
+        let up = CFrame::look_at(position, position + Vector3::y_axis(), Vector3::y_axis());
+        assert_vector_eq(up.look_vector(), Vector3::y_axis());
+        assert!((up.orientation.determinant() - 1.0).abs() < TOLERANCE);
+
+        assert_eq!(
+            CFrame::look_at(position, position, Vector3::y_axis()),
+            CFrame::from_position(position)
+        );
+
+        let nan = Vector3::new(f32::NAN, 0.0, 0.0);
+        let infinite = Vector3::new(f32::INFINITY, 0.0, 0.0);
+        assert!(CFrame::look_at(position, nan, Vector3::y_axis())
+            .look_vector()
+            .x
+            .is_nan());
+        assert!(CFrame::look_at(position, infinite, Vector3::y_axis())
+            .right_vector()
+            .x
+            .is_nan());
+
+        // A NaN up vector is replaced like any other unusable one.
+        let fallback = CFrame::look_at(position, Vector3::zero(), nan);
+        assert!((fallback.orientation.determinant() - 1.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn lerp() {
+        let start = CFrame::from_position(Vector3::new(0.0, 0.0, 0.0));
+        let goal = CFrame::new(
+            Vector3::new(10.0, 0.0, 0.0),
+            Matrix3::from_axis_angle(Vector3::y_axis(), FRAC_PI_2),
+        );
+
+        assert_cframe_eq(start.lerp(goal, 0.0), start);
+        assert_cframe_eq(start.lerp(goal, 1.0), goal);
+        assert_cframe_eq(
+            start.lerp(goal, 0.5),
+            CFrame::new(
+                Vector3::new(5.0, 0.0, 0.0),
+                Matrix3::from_axis_angle(Vector3::y_axis(), FRAC_PI_2 / 2.0),
+            ),
+        );
+    }
+}
@@ -0,0 +1,533 @@
+use thiserror::Error;
+
+use crate::{
+    Color3, ColorSequence, ColorSequenceKeypoint, Error, NumberRange, NumberSequence,
+    NumberSequenceKeypoint,
+};
+
+/// Behavior shared by the keypoints of `NumberSequence` and `ColorSequence`.
+trait Keypoint: Copy {
+    fn time(&self) -> f32;
+
+    /// Interpolates every part of the keypoint except its time, which is set
+    /// to `time`.
+    fn lerp(&self, goal: &Self, alpha: f32, time: f32) -> Self;
+
+    /// The largest difference between any part of two keypoints other than
+    /// their times.
+    fn distance(&self, other: &Self) -> f32;
+}
+
+impl Keypoint for NumberSequenceKeypoint {
+    fn time(&self) -> f32 {
+        self.time
+    }
+
+    fn lerp(&self, goal: &Self, alpha: f32, time: f32) -> Self {
+        Self::new(
+            time,
+            lerp(self.value, goal.value, alpha),
+            lerp(self.envelope, goal.envelope, alpha),
+        )
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        (self.value - other.value)
+            .abs()
+            .max((self.envelope - other.envelope).abs())
+    }
+}
+
+impl Keypoint for ColorSequenceKeypoint {
+    fn time(&self) -> f32 {
+        self.time
+    }
+
+    fn lerp(&self, goal: &Self, alpha: f32, time: f32) -> Self {
+        let (from, to) = (self.color, goal.color);
+        let color = Color3::new(
+            lerp(from.r, to.r, alpha),
+            lerp(from.g, to.g, alpha),
+            lerp(from.b, to.b, alpha),
+        );
+
+        Self::new(time, color)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        let (a, b) = (self.color, other.color);
+        (a.r - b.r)
+            .abs()
+            .max((a.g - b.g).abs())
+            .max((a.b - b.b).abs())
+    }
+}
+
+/// The most keypoints that Roblox allows in a sequence.
+const MAX_KEYPOINTS: usize = 20;
+
+fn lerp(from: f32, to: f32, alpha: f32) -> f32 {
+    from + (to - from) * alpha
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum SequenceError {
+    #[error("sequences must have between 2 and 20 keypoints, but this one has {count}")]
+    KeypointCount { count: usize },
+
+    #[error("the first keypoint of a sequence must be at time 0, not {time}")]
+    StartTime { time: f32 },
+
+    #[error("the last keypoint of a sequence must be at time 1, not {time}")]
+    EndTime { time: f32 },
+
+    #[error("keypoint {index} is at time {time}, which is before the keypoint before it")]
+    OutOfOrder { index: usize, time: f32 },
+
+    #[error("keypoint {index} has an envelope of {envelope}, but envelopes must be 0 or more")]
+    InvalidEnvelope { index: usize, envelope: f32 },
+}
+
+/// Checks the rules that Roblox enforces on keypoints when constructing a
+/// sequence. Keypoints may share a time, which makes a sudden jump.
+fn validate<K: Keypoint>(keypoints: &[K]) -> Result<(), SequenceError> {
+    let count = keypoints.len();
+    if !(2..=MAX_KEYPOINTS).contains(&count) {
+        return Err(SequenceError::KeypointCount { count });
+    }
+
+    let start = keypoints[0].time();
+    if start != 0.0 {
+        return Err(SequenceError::StartTime { time: start });
+    }
+
+    for (index, pair) in keypoints.windows(2).enumerate() {
+        let time = pair[1].time();
+
+        if time.is_nan() || time < pair[0].time() {
+            return Err(SequenceError::OutOfOrder {
+                index: index + 1,
+                time,
+            });
+        }
+    }
+
+    let end = keypoints[count - 1].time();
+    if end != 1.0 {
+        return Err(SequenceError::EndTime { time: end });
+    }
+
+    Ok(())
+}
+
+/// Finds the keypoint that the sequence passes through at `time`, holding
+/// the first and last keypoints outside of them.
+fn evaluate<K: Keypoint>(keypoints: &[K], time: f32) -> Option<K> {
+    let first = keypoints.first()?;
+    if time.is_nan() || time <= first.time() {
+        return Some(first.lerp(first, 0.0, time));
+    }
+
+    for pair in keypoints.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+
+        if time < to.time() {
+            let alpha = (time - from.time()) / (to.time() - from.time());
+            return Some(from.lerp(to, alpha, time));
+        }
+    }
+
+    let last = keypoints.last()?;
+    Some(last.lerp(last, 0.0, time))
+}
+
+fn resample<K: Keypoint>(keypoints: &[K], count: usize) -> Vec<K> {
+    let count = count.clamp(2, MAX_KEYPOINTS);
+
+    (0..count)
+        .filter_map(|index| evaluate(keypoints, index as f32 / (count - 1) as f32))
+        .collect()
+}
+
+/// Removes keypoints that are within `tolerance` of the line between the
+/// keypoints that are kept around them, using the Ramer-Douglas-Peucker
+/// algorithm.
+fn simplify<K: Keypoint>(keypoints: &[K], tolerance: f32) -> Vec<K> {
+    fn mark<K: Keypoint>(keypoints: &[K], tolerance: f32, keep: &mut [bool]) {
+        let (first, last) = match keypoints {
+            [first, .., last] => (first, last),
+            _ => return,
+        };
+
+        let span = last.time() - first.time();
+        let mut furthest = None;
+        let mut furthest_distance = tolerance;
+
+        for (offset, keypoint) in keypoints[1..keypoints.len() - 1].iter().enumerate() {
+            let distance = if span > 0.0 {
+                let alpha = (keypoint.time() - first.time()) / span;
+                keypoint.distance(&first.lerp(last, alpha, keypoint.time()))
+            } else {
+                // When both ends are at the same time, there's no line
+                // between them, so keypoints have to match one of them.
+                keypoint.distance(first).min(keypoint.distance(last))
+            };
+
+            if distance > furthest_distance {
+                furthest = Some(offset + 1);
+                furthest_distance = distance;
+            }
+        }
+
+        if let Some(index) = furthest {
+            keep[index] = true;
+            mark(&keypoints[..=index], tolerance, &mut keep[..=index]);
+            mark(&keypoints[index..], tolerance, &mut keep[index..]);
+        }
+    }
+
+    let mut keep = vec![false; keypoints.len()];
+    if let Some(first) = keep.first_mut() {
+        *first = true;
+    }
+    if let Some(last) = keep.last_mut() {
+        *last = true;
+    }
+    mark(keypoints, tolerance, &mut keep);
+
+    keypoints
+        .iter()
+        .zip(keep)
+        .filter_map(|(keypoint, keep)| if keep { Some(*keypoint) } else { None })
+        .collect()
+}
+
+impl NumberSequence {
+    /// The most keypoints that Roblox allows in a sequence.
+    pub const MAX_KEYPOINTS: usize = MAX_KEYPOINTS;
+
+    /// Creates a sequence after checking that its keypoints follow the rules
+    /// that Roblox has for them: there are between 2 and 20 of them, they're
+    /// in order by time, the first is at time 0, the last is at time 1, and
+    /// every envelope is 0 or more.
+    pub fn new(keypoints: Vec<NumberSequenceKeypoint>) -> Result<Self, Error> {
+        let sequence = Self { keypoints };
+        sequence.validate()?;
+        Ok(sequence)
+    }
+
+    /// Creates a sequence that's `value` the whole way through.
+    pub fn constant(value: f32) -> Self {
+        Self::linear(value, value)
+    }
+
+    /// Creates a sequence that goes in a straight line from `start` to `end`.
+    pub fn linear(start: f32, end: f32) -> Self {
+        Self {
+            keypoints: vec![
+                NumberSequenceKeypoint::new(0.0, start, 0.0),
+                NumberSequenceKeypoint::new(1.0, end, 0.0),
+            ],
+        }
+    }
+
+    /// Checks the same rules as [`NumberSequence::new`], which is useful for
+    /// sequences that were read from a file.
+    pub fn validate(&self) -> Result<(), Error> {
+        validate(&self.keypoints)?;
+
+        for (index, keypoint) in self.keypoints.iter().enumerate() {
+            if keypoint.envelope.is_nan() || keypoint.envelope < 0.0 {
+                return Err(SequenceError::InvalidEnvelope {
+                    index,
+                    envelope: keypoint.envelope,
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the value of the sequence at `time`, interpolating linearly
+    /// between keypoints. Times before the first keypoint or after the last
+    /// one take its value, and an empty sequence is 0 everywhere.
+    pub fn evaluate(&self, time: f32) -> f32 {
+        evaluate(&self.keypoints, time).map_or(0.0, |keypoint| keypoint.value)
+    }
+
+    /// Returns the range of values that the sequence can take at `time`,
+    /// which is its value plus or minus its envelope.
+    pub fn envelope_bounds(&self, time: f32) -> NumberRange {
+        match evaluate(&self.keypoints, time) {
+            Some(keypoint) => NumberRange::new(
+                keypoint.value - keypoint.envelope,
+                keypoint.value + keypoint.envelope,
+            ),
+            None => NumberRange::new(0.0, 0.0),
+        }
+    }
+
+    /// Picks a value from [`NumberSequence::envelope_bounds`] like Roblox
+    /// does for each particle, using a `random` number from 0 to 1. See
+    /// [`NumberRange::sample`].
+    pub fn sample(&self, time: f32, random: f32) -> f32 {
+        self.envelope_bounds(time).sample(random)
+    }
+
+    /// Returns a sequence with `count` keypoints spaced evenly in time that
+    /// follow this one. `count` is clamped to between 2 and 20.
+    pub fn resample(&self, count: usize) -> Self {
+        Self {
+            keypoints: resample(&self.keypoints, count),
+        }
+    }
+
+    /// Returns a sequence without the keypoints that can be removed without
+    /// the value or envelope changing by more than `tolerance` anywhere. The
+    /// first and last keypoints are always kept.
+    pub fn simplify(&self, tolerance: f32) -> Self {
+        Self {
+            keypoints: simplify(&self.keypoints, tolerance),
+        }
+    }
+}
+
+impl ColorSequence {
+    /// The most keypoints that Roblox allows in a sequence.
+    pub const MAX_KEYPOINTS: usize = MAX_KEYPOINTS;
+
+    /// Creates a sequence after checking that its keypoints follow the rules
+    /// that Roblox has for them: there are between 2 and 20 of them, they're
+    /// in order by time, the first is at time 0, and the last is at time 1.
+    pub fn new(keypoints: Vec<ColorSequenceKeypoint>) -> Result<Self, Error> {
+        let sequence = Self { keypoints };
+        sequence.validate()?;
+        Ok(sequence)
+    }
+
+    /// Creates a sequence that's `color` the whole way through.
+    pub fn constant(color: Color3) -> Self {
+        Self::linear(color, color)
+    }
+
+    /// Creates a sequence that fades from `start` to `end`.
+    pub fn linear(start: Color3, end: Color3) -> Self {
+        Self {
+            keypoints: vec![
+                ColorSequenceKeypoint::new(0.0, start),
+                ColorSequenceKeypoint::new(1.0, end),
+            ],
+        }
+    }
+
+    /// Checks the same rules as [`ColorSequence::new`], which is useful for
+    /// sequences that were read from a file.
+    pub fn validate(&self) -> Result<(), Error> {
+        Ok(validate(&self.keypoints)?)
+    }
+
+    /// Returns the color of the sequence at `time`, interpolating each
+    /// channel linearly between keypoints. Times before the first keypoint or
+    /// after the last one take its color, and an empty sequence is black
+    /// everywhere.
+    pub fn evaluate(&self, time: f32) -> Color3 {
+        evaluate(&self.keypoints, time)
+            .map_or(Color3::new(0.0, 0.0, 0.0), |keypoint| keypoint.color)
+    }
+
+    /// Returns a sequence with `count` keypoints spaced evenly in time that
+    /// follow this one. `count` is clamped to between 2 and 20.
+    pub fn resample(&self, count: usize) -> Self {
+        Self {
+            keypoints: resample(&self.keypoints, count),
+        }
+    }
+
+    /// Returns a sequence without the keypoints that can be removed without
+    /// any channel changing by more than `tolerance` anywhere. The first and
+    /// last keypoints are always kept.
+    pub fn simplify(&self, tolerance: f32) -> Self {
+        Self {
+            keypoints: simplify(&self.keypoints, tolerance),
+        }
+    }
+}
+
+impl NumberRange {
+    /// Returns the number that's `alpha` of the way from `min` to `max`.
+    pub fn lerp(&self, alpha: f32) -> f32 {
+        lerp(self.min, self.max, alpha)
+    }
+
+    /// Tells whether `value` is between `min` and `max`, inclusive.
+    pub fn contains(&self, value: f32) -> bool {
+        self.min <= value && value <= self.max
+    }
+
+    /// Picks a number between `min` and `max` using a `random` number from
+    /// 0 to 1, which the caller chooses from whatever source of randomness
+    /// they like. Numbers outside of 0 to 1 are clamped, so the result is
+    /// always in the range.
+    pub fn sample(&self, random: f32) -> f32 {
+        self.lerp(random.clamp(0.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn keypoint(time: f32, value: f32) -> NumberSequenceKeypoint {
+        NumberSequenceKeypoint::new(time, value, 0.0)
+    }
+
+    #[test]
+    fn validation() {
+        assert!(NumberSequence::new(vec![keypoint(0.0, 1.0), keypoint(1.0, 2.0)]).is_ok());
+
+        // Keypoints can share a time to make a jump.
+        assert!(NumberSequence::new(vec![
+            keypoint(0.0, 0.0),
+            keypoint(0.5, 0.0),
+            keypoint(0.5, 1.0),
+            keypoint(1.0, 1.0),
+        ])
+        .is_ok());
+
+        let invalid = [
+            vec![keypoint(0.0, 1.0)],
+            vec![keypoint(0.1, 1.0), keypoint(1.0, 1.0)],
+            vec![keypoint(0.0, 1.0), keypoint(0.9, 1.0)],
+            vec![
+                keypoint(0.0, 1.0),
+                keypoint(0.6, 1.0),
+                keypoint(0.4, 1.0),
+                keypoint(1.0, 1.0),
+            ],
+            vec![
+                keypoint(0.0, 1.0),
+                keypoint(f32::NAN, 1.0),
+                keypoint(1.0, 1.0),
+            ],
+            vec![
+                NumberSequenceKeypoint::new(0.0, 1.0, -0.5),
+                keypoint(1.0, 1.0),
+            ],
+            (0..21)
+                .map(|index| keypoint(index as f32 / 20.0, 0.0))
+                .collect(),
+        ];
+        for keypoints in invalid.iter() {
+            assert!(
+                NumberSequence::new(keypoints.clone()).is_err(),
+                "{:?} should be invalid",
+                keypoints
+            );
+        }
+
+        let error = ColorSequence::new(vec![
+            ColorSequenceKeypoint::new(0.0, Color3::new(0.0, 0.0, 0.0)),
+            ColorSequenceKeypoint::new(0.5, Color3::new(1.0, 1.0, 1.0)),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the last keypoint of a sequence must be at time 1, not 0.5"
+        );
+    }
+
+    #[test]
+    fn evaluate() {
+        let sequence = NumberSequence {
+            keypoints: vec![
+                NumberSequenceKeypoint::new(0.0, 0.0, 1.0),
+                NumberSequenceKeypoint::new(0.5, 10.0, 3.0),
+                keypoint(0.5, 20.0),
+                keypoint(1.0, 30.0),
+            ],
+        };
+
+        assert_eq!(sequence.evaluate(-1.0), 0.0);
+        assert_eq!(sequence.evaluate(0.25), 5.0);
+        assert_eq!(sequence.evaluate(0.5), 20.0);
+        assert_eq!(sequence.evaluate(0.75), 25.0);
+        assert_eq!(sequence.evaluate(2.0), 30.0);
+        assert_eq!(sequence.envelope_bounds(0.25), NumberRange::new(3.0, 7.0));
+        assert_eq!(
+            NumberSequence {
+                keypoints: Vec::new()
+            }
+            .evaluate(0.5),
+            0.0
+        );
+
+        let colors = ColorSequence::linear(Color3::new(0.0, 0.0, 1.0), Color3::new(1.0, 0.0, 0.0));
+        assert_eq!(colors.evaluate(0.25), Color3::new(0.25, 0.0, 0.75));
+    }
+
+    #[test]
+    fn resample() {
+        let sequence = NumberSequence::linear(0.0, 4.0).resample(5);
+        assert_eq!(
+            sequence.keypoints,
+            vec![
+                keypoint(0.0, 0.0),
+                keypoint(0.25, 1.0),
+                keypoint(0.5, 2.0),
+                keypoint(0.75, 3.0),
+                keypoint(1.0, 4.0),
+            ]
+        );
+        assert!(sequence.validate().is_ok());
+
+        assert_eq!(sequence.resample(100).keypoints.len(), 20);
+        assert_eq!(sequence.resample(0), NumberSequence::linear(0.0, 4.0));
+    }
+
+    #[test]
+    fn simplify() {
+        let sequence = NumberSequence {
+            keypoints: vec![
+                keypoint(0.0, 0.0),
+                keypoint(0.25, 1.0),
+                keypoint(0.5, 2.01),
+                keypoint(0.75, 1.0),
+                keypoint(1.0, 0.0),
+            ],
+        };
+
+        assert_eq!(
+            sequence.simplify(0.1).keypoints,
+            vec![keypoint(0.0, 0.0), keypoint(0.5, 2.01), keypoint(1.0, 0.0)]
+        );
+        assert_eq!(sequence.simplify(0.0), sequence);
+        assert_eq!(sequence.simplify(10.0), NumberSequence::linear(0.0, 0.0));
+
+        // Jumps are kept, since neither side is on the line between the
+        // keypoints around them.
+        let jump = NumberSequence {
+            keypoints: vec![
+                keypoint(0.0, 0.0),
+                keypoint(0.5, 0.0),
+                keypoint(0.5, 1.0),
+                keypoint(1.0, 1.0),
+            ],
+        };
+        assert_eq!(jump.simplify(0.1), jump);
+    }
+
+    #[test]
+    fn number_range() {
+        let range = NumberRange::new(2.0, 4.0);
+        assert_eq!(range.lerp(0.25), 2.5);
+        assert!(range.contains(4.0));
+        assert!(!range.contains(4.5));
+
+        for random in [0.0, 0.5, 0.999, -1.0, 2.0] {
+            assert!(range.contains(range.sample(random)));
+        }
+        assert_eq!(range.sample(0.5), 3.0);
+        assert_eq!(NumberRange::new(3.0, 3.0).sample(0.7), 3.0);
+    }
+}
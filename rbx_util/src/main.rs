@@ -1,4 +1,5 @@
 mod convert;
+mod merge;
 mod remove_prop;
 mod view_binary;
 
@@ -8,6 +9,7 @@ use std::{path::Path, str::FromStr};
 use clap::Parser;
 
 use convert::ConvertCommand;
+use merge::MergeCommand;
 use remove_prop::RemovePropCommand;
 use view_binary::ViewBinaryCommand;
 
@@ -26,6 +28,7 @@ impl Options {
             Subcommand::ViewBinary(command) => command.run(),
             Subcommand::Convert(command) => command.run(),
             Subcommand::RemoveProp(command) => command.run(),
+            Subcommand::Merge(command) => command.run(),
         }
     }
 }
@@ -38,6 +41,8 @@ enum Subcommand {
     Convert(ConvertCommand),
     /// Removes a specific property from a specific class within a Roblox file.
     RemoveProp(RemovePropCommand),
+    /// Merges two sets of changes to a place or model made from a common base.
+    Merge(MergeCommand),
 }
 
 #[derive(Debug, Parser, Clone, Copy)]
@@ -0,0 +1,446 @@
+use std::{
+    fmt::Write as _,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use clap::{Parser, ValueEnum};
+use fs_err::File;
+use rbx_binary::FileMetadata;
+use rbx_dom_weak::{
+    types::{Attributes, Variant},
+    Conflict, ConflictKind, Diff, MatchStrategy, Patch, PatchOperation, WeakDom,
+};
+
+use crate::ModelKind;
+
+/// The attribute that conflicting instances are tagged with when
+/// `--conflict-attributes` is passed.
+const CONFLICT_ATTRIBUTE: &str = "MergeConflict";
+
+#[derive(Debug, Parser)]
+pub struct MergeCommand {
+    /// The common ancestor of both files.
+    base: PathBuf,
+    /// The file containing our changes. These win when both sides conflict.
+    ours: PathBuf,
+    /// The file containing their changes.
+    theirs: PathBuf,
+    /// The place to write the merged file to.
+    #[clap(long, short)]
+    output: PathBuf,
+    /// Where to write a report of any conflicts. If not provided, conflicts
+    /// are only logged.
+    #[clap(long)]
+    report: Option<PathBuf>,
+    /// Tag conflicting instances in the merged file with a `MergeConflict`
+    /// attribute describing the conflict.
+    #[clap(long)]
+    conflict_attributes: bool,
+    /// How instances are matched up between the three files.
+    #[clap(long, value_enum, default_value = "name-path")]
+    strategy: Strategy,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Strategy {
+    /// Match instances by their `UniqueId` property.
+    UniqueId,
+    /// Match instances by their path of names from the root.
+    NamePath,
+}
+
+impl From<Strategy> for MatchStrategy {
+    fn from(value: Strategy) -> Self {
+        match value {
+            Strategy::UniqueId => MatchStrategy::UniqueId,
+            Strategy::NamePath => MatchStrategy::NamePath,
+        }
+    }
+}
+
+impl MergeCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let output_kind = ModelKind::from_path(&self.output)?;
+
+        let (base, base_metadata) = read_dom(&self.base)?;
+        let (ours, ours_metadata) = read_dom(&self.ours)?;
+        let (theirs, theirs_metadata) = read_dom(&self.theirs)?;
+
+        let (mut dom, conflicts) = merge(base, &ours, &theirs, self.strategy.into());
+        let metadata = merge_metadata(&base_metadata, &ours_metadata, &theirs_metadata);
+
+        let mut report = String::new();
+        for conflict in &conflicts {
+            let line = describe_conflict(&dom, conflict);
+            log::warn!("{line}");
+            writeln!(report, "{line}").unwrap();
+
+            if self.conflict_attributes {
+                tag_conflict(&mut dom, &conflict.conflict, &line);
+            }
+        }
+
+        if let Some(report_path) = &self.report {
+            fs_err::write(report_path, &report)?;
+        }
+
+        let output_file = BufWriter::new(File::create(&self.output)?);
+
+        let root_ids = dom.root().children();
+        match output_kind {
+            ModelKind::Xml => {
+                let options = rbx_xml::EncodeOptions::new()
+                    .property_behavior(rbx_xml::EncodePropertyBehavior::WriteUnknown);
+
+                rbx_xml::to_writer(output_file, &dom, root_ids, options)
+                    .with_context(|| format!("Failed to write {}", self.output.display()))?;
+            }
+
+            ModelKind::Binary => {
+                rbx_binary::Serializer::new()
+                    .serialize_with_metadata(output_file, &dom, root_ids, &metadata)
+                    .with_context(|| format!("Failed to write {}", self.output.display()))?;
+            }
+        }
+        log::info!(
+            "Wrote merged {output_kind:?} file to {}",
+            self.output.display()
+        );
+
+        // A non-zero exit code tells git that the merge needs attention.
+        if !conflicts.is_empty() {
+            anyhow::bail!("Merge finished with {} conflict(s)", conflicts.len());
+        }
+
+        Ok(())
+    }
+}
+
+/// Which set of changes a conflict came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Ours,
+    Theirs,
+}
+
+#[derive(Debug)]
+struct MergeConflict {
+    side: Side,
+    conflict: Conflict,
+}
+
+/// Applies the changes from `base` to `ours` and from `base` to `theirs` on
+/// top of `base`, returning the merged DOM and every change that couldn't be
+/// applied.
+fn merge(
+    base: WeakDom,
+    ours: &WeakDom,
+    theirs: &WeakDom,
+    strategy: MatchStrategy,
+) -> (WeakDom, Vec<MergeConflict>) {
+    let mut dom = base;
+    let ours_patch = Patch::from_diff(&Diff::new(&dom, ours, strategy), &dom, ours);
+    let theirs_patch = Patch::from_diff(&Diff::new(&dom, theirs, strategy), &dom, theirs);
+    log::debug!(
+        "Found {} operations in ours and {} in theirs",
+        ours_patch.operations.len(),
+        theirs_patch.operations.len()
+    );
+
+    // Both patches are expressed in terms of the base DOM, so ours should
+    // always apply cleanly. If it doesn't, those changes are lost, so they're
+    // reported like any other conflict.
+    let mut conflicts: Vec<MergeConflict> = ours_patch
+        .apply(&mut dom)
+        .into_iter()
+        .map(|conflict| MergeConflict {
+            side: Side::Ours,
+            conflict,
+        })
+        .collect();
+
+    // Theirs conflicts wherever ours got there first.
+    conflicts.extend(
+        theirs_patch
+            .apply(&mut dom)
+            .into_iter()
+            .filter(|conflict| !already_applied(&dom, &conflict.operation))
+            .map(|conflict| MergeConflict {
+                side: Side::Theirs,
+                conflict,
+            }),
+    );
+
+    (dom, conflicts)
+}
+
+/// Merges the `META` entries and unknown chunks of binary files. Entries that
+/// ours changed win over theirs, and unknown chunks are taken from whichever
+/// side changed them, preferring ours.
+fn merge_metadata(base: &FileMetadata, ours: &FileMetadata, theirs: &FileMetadata) -> FileMetadata {
+    let mut entries = Vec::new();
+    let keys = ours
+        .entries
+        .iter()
+        .chain(&theirs.entries)
+        .chain(&base.entries)
+        .map(|(key, _)| key.as_str());
+
+    for key in keys {
+        if entries
+            .iter()
+            .any(|(existing, _): &(String, String)| existing == key)
+        {
+            continue;
+        }
+
+        let value = if ours.get(key) != base.get(key) {
+            ours.get(key)
+        } else {
+            theirs.get(key)
+        };
+
+        if let Some(value) = value {
+            entries.push((key.to_owned(), value.to_owned()));
+        }
+    }
+
+    let unknown_chunks = if ours.unknown_chunks != base.unknown_chunks {
+        ours.unknown_chunks.clone()
+    } else {
+        theirs.unknown_chunks.clone()
+    };
+
+    FileMetadata {
+        entries,
+        unknown_chunks,
+    }
+}
+
+fn read_dom(path: &Path) -> anyhow::Result<(WeakDom, FileMetadata)> {
+    let kind = ModelKind::from_path(path)?;
+    let file = BufReader::new(File::open(path)?);
+
+    log::debug!("Reading from {kind:?} file {}", path.display());
+    let result = match kind {
+        ModelKind::Xml => {
+            let options = rbx_xml::DecodeOptions::new()
+                .property_behavior(rbx_xml::DecodePropertyBehavior::ReadUnknown);
+
+            let dom = rbx_xml::from_reader(file, options)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            (dom, FileMetadata::default())
+        }
+
+        ModelKind::Binary => rbx_binary::Deserializer::new()
+            .deserialize_with_metadata(file)
+            .with_context(|| format!("Failed to read {}", path.display()))?,
+    };
+
+    Ok(result)
+}
+
+/// Returns whether the DOM already looks the way `operation` would leave it,
+/// which happens when both sides made the same change.
+fn already_applied(dom: &WeakDom, operation: &PatchOperation) -> bool {
+    let instance = dom.get_by_ref(operation.referent());
+
+    match (operation, instance) {
+        (PatchOperation::Remove { .. }, None) => true,
+        (PatchOperation::Move { new_parent, .. }, Some(instance)) => {
+            instance.parent() == *new_parent
+        }
+        (PatchOperation::Rename { new_name, .. }, Some(instance)) => instance.name == *new_name,
+        (PatchOperation::SetClass { new_class, .. }, Some(instance)) => {
+            instance.class == *new_class
+        }
+        (
+            PatchOperation::SetProperty {
+                name, new_value, ..
+            },
+            Some(instance),
+        ) => instance.properties.get(name) == new_value.as_ref(),
+        _ => false,
+    }
+}
+
+fn describe_conflict(dom: &WeakDom, merge_conflict: &MergeConflict) -> String {
+    let conflict = &merge_conflict.conflict;
+    let side = match merge_conflict.side {
+        Side::Ours => "ours",
+        Side::Theirs => "theirs",
+    };
+    let referent = conflict.operation.referent();
+    let instance = dom.get_by_ref(referent);
+    let path = match instance {
+        Some(_) => dom.full_path_of(referent, "."),
+        None => format!("{referent}"),
+    };
+
+    let change = match &conflict.operation {
+        PatchOperation::Insert { name, class, .. } => {
+            format!("{side} added {class} {name:?}")
+        }
+        PatchOperation::Remove { .. } => format!("{side} removed this instance"),
+        PatchOperation::Move { new_parent, .. } => match dom.get_by_ref(*new_parent) {
+            Some(_) => format!(
+                "{side} moved this instance into {}",
+                dom.full_path_of(*new_parent, ".")
+            ),
+            None => format!("{side} moved this instance"),
+        },
+        PatchOperation::Rename { new_name, .. } => format!("{side} renamed this to {new_name:?}"),
+        PatchOperation::SetClass { new_class, .. } => {
+            format!("{side} changed the class to {new_class}")
+        }
+        PatchOperation::SetProperty {
+            name, new_value, ..
+        } => {
+            let merged = instance.and_then(|instance| instance.properties.get(name));
+            format!("{side} set {name} to {new_value:?}, the merge has {merged:?}")
+        }
+        other => format!("{other:?}"),
+    };
+
+    let reason = match (merge_conflict.side, &conflict.kind) {
+        (Side::Ours, _) => "it couldn't be applied to the base file",
+        (Side::Theirs, ConflictKind::MissingInstance) => "ours removed the instance",
+        (Side::Theirs, ConflictKind::MissingParent) => "ours removed the parent",
+        (Side::Theirs, ConflictKind::HasChildren) => "ours added children to it",
+        (Side::Theirs, _) => "ours changed it too",
+    };
+
+    format!("{path}: {change}, but {reason}")
+}
+
+fn tag_conflict(dom: &mut WeakDom, conflict: &Conflict, description: &str) {
+    // Conflicts about instances that no longer exist are only reported, so
+    // put the tag on the closest surviving instance.
+    let referent = match &conflict.operation {
+        PatchOperation::Insert { parent, .. } => *parent,
+        operation => operation.referent(),
+    };
+    let instance = match dom.get_by_ref_mut(referent) {
+        Some(instance) => instance,
+        None => return,
+    };
+
+    let attributes = instance
+        .properties
+        .entry("Attributes".into())
+        .or_insert_with(|| Attributes::new().into());
+
+    if let Variant::Attributes(attributes) = attributes {
+        let value = match attributes.get(CONFLICT_ATTRIBUTE) {
+            Some(Variant::String(existing)) => format!("{existing}\n{description}"),
+            _ => description.to_owned(),
+        };
+        attributes.insert(CONFLICT_ATTRIBUTE.to_owned(), value.into());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rbx_dom_weak::InstanceBuilder;
+
+    use super::*;
+
+    fn sample_dom() -> WeakDom {
+        WeakDom::new(
+            InstanceBuilder::new("DataModel").with_child(
+                InstanceBuilder::new("Folder")
+                    .with_name("Root")
+                    .with_child(InstanceBuilder::new("StringValue").with_name("A"))
+                    .with_child(
+                        InstanceBuilder::new("StringValue")
+                            .with_name("B")
+                            .with_property("Value", "base"),
+                    ),
+            ),
+        )
+    }
+
+    fn find<'a>(dom: &'a WeakDom, name: &str) -> Option<&'a rbx_dom_weak::Instance> {
+        dom.descendants().find(|instance| instance.name == name)
+    }
+
+    fn set_value(dom: &mut WeakDom, name: &str, value: &str) {
+        let referent = find(dom, name).unwrap().referent();
+        dom.get_by_ref_mut(referent)
+            .unwrap()
+            .properties
+            .insert("Value".into(), value.into());
+    }
+
+    #[test]
+    fn clean_merge() {
+        let mut ours = sample_dom();
+        let referent = find(&ours, "A").unwrap().referent();
+        ours.get_by_ref_mut(referent).unwrap().name = "Renamed".to_owned();
+
+        let mut theirs = sample_dom();
+        set_value(&mut theirs, "B", "theirs");
+
+        let (dom, conflicts) = merge(sample_dom(), &ours, &theirs, MatchStrategy::NamePath);
+        assert!(conflicts.is_empty(), "{:?}", conflicts);
+
+        assert!(find(&dom, "A").is_none());
+        assert!(find(&dom, "Renamed").is_some());
+        assert_eq!(
+            find(&dom, "B").unwrap().properties.get(&"Value".into()),
+            Some(&Variant::String("theirs".to_owned()))
+        );
+    }
+
+    #[test]
+    fn conflicting_merge() {
+        let mut ours = sample_dom();
+        set_value(&mut ours, "B", "ours");
+
+        let mut theirs = sample_dom();
+        set_value(&mut theirs, "B", "theirs");
+
+        let (dom, conflicts) = merge(sample_dom(), &ours, &theirs, MatchStrategy::NamePath);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].side, Side::Theirs);
+        assert_eq!(
+            find(&dom, "B").unwrap().properties.get(&"Value".into()),
+            Some(&Variant::String("ours".to_owned()))
+        );
+
+        let description = describe_conflict(&dom, &conflicts[0]);
+        assert!(
+            description.starts_with("Root.B: theirs set Value"),
+            "{}",
+            description
+        );
+        assert!(
+            description.ends_with("but ours changed it too"),
+            "{}",
+            description
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        let metadata = |entries: &[(&str, &str)]| FileMetadata {
+            entries: entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            unknown_chunks: Vec::new(),
+        };
+
+        let merged = merge_metadata(
+            &metadata(&[("A", "1"), ("B", "1"), ("C", "1")]),
+            &metadata(&[("A", "2"), ("B", "1")]),
+            &metadata(&[("A", "3"), ("B", "3"), ("C", "1"), ("D", "3")]),
+        );
+        assert_eq!(merged.get("A"), Some("2"));
+        assert_eq!(merged.get("B"), Some("3"));
+        assert_eq!(merged.get("C"), None);
+        assert_eq!(merged.get("D"), Some("3"));
+    }
+}
@@ -10,7 +10,8 @@ use rbx_reflection::{PropertyKind, PropertySerialization, ReflectionDatabase};
 
 use crate::{
     conversion::ConvertVariant,
-    core::find_canonical_property_descriptor,
+    core::{find_canonical_property_descriptor, find_serialized_property_descriptor},
+    diagnostics::{Diagnostic, DiagnosticKind},
     error::{DecodeError, DecodeErrorKind},
     types::read_value_xml,
 };
@@ -18,6 +19,14 @@ use crate::{
 use crate::deserializer_core::{XmlEventReader, XmlReadEvent};
 
 pub fn decode_internal<R: Read>(source: R, options: DecodeOptions) -> Result<WeakDom, DecodeError> {
+    let (tree, _) = decode_with_diagnostics(source, options)?;
+    Ok(tree)
+}
+
+pub fn decode_with_diagnostics<R: Read>(
+    source: R,
+    options: DecodeOptions,
+) -> Result<(WeakDom, Vec<Diagnostic>), DecodeError> {
     let mut tree = WeakDom::new(InstanceBuilder::new("DataModel"));
 
     let root_id = tree.root_ref();
@@ -30,7 +39,9 @@ pub fn decode_internal<R: Read>(source: R, options: DecodeOptions) -> Result<Wea
     apply_shared_string_rewrites(&mut state).map_err(|e| iterator.error(e))?;
     apply_net_asset_rewrites(&mut state).map_err(|e| iterator.error(e))?;
 
-    Ok(tree)
+    let diagnostics = std::mem::take(&mut state.diagnostics);
+
+    Ok((tree, diagnostics))
 }
 
 /// Describes the strategy that rbx_xml should use when deserializing
@@ -71,6 +82,8 @@ pub enum DecodePropertyBehavior {
 pub struct DecodeOptions<'db> {
     property_behavior: DecodePropertyBehavior,
     database: &'db ReflectionDatabase<'db>,
+    strict: bool,
+    lenient: bool,
 }
 
 impl<'db> DecodeOptions<'db> {
@@ -80,6 +93,8 @@ impl<'db> DecodeOptions<'db> {
         DecodeOptions {
             property_behavior: DecodePropertyBehavior::IgnoreUnknown,
             database: rbx_reflection_database::get().unwrap(),
+            strict: false,
+            lenient: false,
         }
     }
 
@@ -100,6 +115,25 @@ impl<'db> DecodeOptions<'db> {
         DecodeOptions { database, ..self }
     }
 
+    /// Determines whether every [`Diagnostic`] should be returned as an error
+    /// instead of being recovered from. Defaults to `false`.
+    #[inline]
+    pub fn strict(self, strict: bool) -> Self {
+        DecodeOptions { strict, ..self }
+    }
+
+    /// Determines whether properties and instances that can't be decoded
+    /// should be skipped instead of failing the whole document. Each one
+    /// that's skipped is reported as a [`Diagnostic`]. Defaults to `false`.
+    ///
+    /// Documents that aren't well-formed XML can still fail to decode, since
+    /// there's no way to know where the broken part ends. Strict mode takes
+    /// precedence over this option.
+    #[inline]
+    pub fn lenient(self, lenient: bool) -> Self {
+        DecodeOptions { lenient, ..self }
+    }
+
     /// A utility function to determine whether or not we should reference the
     /// reflection database at all.
     pub(crate) fn use_reflection(&self) -> bool {
@@ -153,6 +187,10 @@ pub struct ParseState<'dom, 'db> {
     /// Contains all of the unknown types that have been found so far. Tracking
     /// them here helps ensure that we only output a warning once per type.
     unknown_type_names: HashSet<String>,
+
+    /// Everything unusual about the document that we've recovered from so
+    /// far.
+    diagnostics: Vec<Diagnostic>,
 }
 
 struct ReferentRewrite {
@@ -179,25 +217,98 @@ impl<'dom, 'db> ParseState<'dom, 'db> {
             shared_string_rewrites: Vec::new(),
             net_asset_rewrites: Vec::new(),
             unknown_type_names: HashSet::new(),
+            diagnostics: Vec::new(),
         }
     }
 
-    /// Called when the deserializer encounters an unknown property type.
-    pub fn unknown_type_visited(&mut self, id: Ref, property_name: &str, type_name: &str) {
-        if self.unknown_type_names.contains(type_name) {
-            return;
+    /// Records a problem that we were able to recover from at the reader's
+    /// current position, or returns it as an error in strict mode.
+    pub fn report<R: Read>(
+        &mut self,
+        reader: &XmlEventReader<R>,
+        kind: DiagnosticKind,
+    ) -> Result<(), DecodeError> {
+        if self.options.strict {
+            return Err(reader.error(DecodeErrorKind::Strict(kind)));
         }
 
-        self.unknown_type_names.insert(type_name.to_owned());
-        let instance = self.tree.get_by_ref(id).unwrap();
+        let diagnostic = Diagnostic::new(kind, reader.position());
+        log::debug!("{diagnostic}");
+        self.diagnostics.push(diagnostic);
 
-        log::warn!(
-            "Unknown value type name \"{name}\" in Roblox XML model file. \
-             Found in property {class}.{prop}.",
-            name = type_name,
-            class = instance.class,
-            prop = property_name,
+        Ok(())
+    }
+
+    /// Records an error that the deserializer is about to skip past if it's
+    /// in lenient mode, otherwise returns it.
+    pub fn recover(
+        &mut self,
+        error: DecodeError,
+        kind: impl FnOnce(String) -> DiagnosticKind,
+    ) -> Result<(), DecodeError> {
+        if !self.options.lenient || self.options.strict || !error.is_recoverable() {
+            return Err(error);
+        }
+
+        let diagnostic = Diagnostic::new(
+            kind(error.kind().to_string()),
+            (error.line(), error.column()),
         );
+        log::warn!("{diagnostic}");
+        self.diagnostics.push(diagnostic);
+
+        Ok(())
+    }
+
+    /// Removes an instance that was only partly decoded, along with its
+    /// descendants and anything that still refers to them.
+    pub fn remove_instance(&mut self, instance_id: Ref) {
+        let removed: HashSet<Ref> = self
+            .tree
+            .descendants_of(instance_id)
+            .map(|instance| instance.referent())
+            .collect();
+        self.tree.destroy(instance_id);
+
+        self.referents_to_ids.retain(|_, id| !removed.contains(id));
+        self.referent_rewrites
+            .retain(|rewrite| !removed.contains(&rewrite.id));
+        self.shared_string_rewrites
+            .retain(|rewrite| !removed.contains(&rewrite.id));
+        self.net_asset_rewrites
+            .retain(|rewrite| !removed.contains(&rewrite.id));
+    }
+
+    /// Called when the deserializer encounters an unknown property type.
+    pub fn unknown_type_visited<R: Read>(
+        &mut self,
+        reader: &XmlEventReader<R>,
+        id: Ref,
+        property_name: &str,
+        type_name: &str,
+    ) -> Result<(), DecodeError> {
+        let class_name = self.tree.get_by_ref(id).unwrap().class;
+
+        if !self.unknown_type_names.contains(type_name) {
+            self.unknown_type_names.insert(type_name.to_owned());
+
+            log::warn!(
+                "Unknown value type name \"{name}\" in Roblox XML model file. \
+                 Found in property {class}.{prop}.",
+                name = type_name,
+                class = class_name,
+                prop = property_name,
+            );
+        }
+
+        self.report(
+            reader,
+            DiagnosticKind::UnknownPropertyType {
+                class_name: class_name.to_string(),
+                property_name: property_name.to_owned(),
+                type_name: type_name.to_owned(),
+            },
+        )
     }
 
     /// Marks that a property on this instance needs to be rewritten once we
@@ -413,10 +524,7 @@ fn deserialize_root<R: Read>(
                     "SharedStrings" => {
                         deserialize_shared_string_dict(reader, state)?;
                     }
-                    _ => {
-                        let event = reader.expect_next().unwrap();
-                        return Err(reader.error(DecodeErrorKind::UnexpectedXmlEvent(event)));
-                    }
+                    _ => skip_unexpected_element(reader, state)?,
                 }
             }
             XmlReadEvent::EndElement { name } if name.local_name == "roblox" => {
@@ -511,10 +619,49 @@ fn deserialize_shared_string<R: Read>(
     Ok(())
 }
 
+/// Consumes an element that isn't allowed where it appears, which is an error
+/// unless we're in lenient mode.
+fn skip_unexpected_element<R: Read>(
+    reader: &mut XmlEventReader<R>,
+    state: &mut ParseState,
+) -> Result<(), DecodeError> {
+    let depth = reader.depth();
+    let event = reader.expect_next()?;
+    let error = reader.error(DecodeErrorKind::UnexpectedXmlEvent(event));
+
+    state.recover(error, |message| DiagnosticKind::UnexpectedContent {
+        message,
+    })?;
+    reader.skip_to_depth(depth)
+}
+
 fn deserialize_instance<R: Read>(
     reader: &mut XmlEventReader<R>,
     state: &mut ParseState,
     parent_id: Ref,
+) -> Result<(), DecodeError> {
+    let depth = reader.depth();
+    let mut instance_id = None;
+
+    if let Err(error) = deserialize_instance_inner(reader, state, parent_id, &mut instance_id) {
+        state.recover(error, |message| DiagnosticKind::MalformedInstance {
+            message,
+        })?;
+        reader.skip_to_depth(depth)?;
+
+        if let Some(instance_id) = instance_id {
+            state.remove_instance(instance_id);
+        }
+    }
+
+    Ok(())
+}
+
+fn deserialize_instance_inner<R: Read>(
+    reader: &mut XmlEventReader<R>,
+    state: &mut ParseState,
+    parent_id: Ref,
+    inserted_id: &mut Option<Ref>,
 ) -> Result<(), DecodeError> {
     let (class_name, referent) = {
         let attributes = reader.expect_start_with_name("Item")?;
@@ -548,6 +695,7 @@ fn deserialize_instance<R: Read>(
 
     let builder = InstanceBuilder::with_property_capacity(class_name, prop_capacity);
     let instance_id = state.tree.insert(parent_id, builder);
+    *inserted_id = Some(instance_id);
 
     if let Some(referent) = referent {
         state.referents_to_ids.insert(referent, instance_id);
@@ -564,10 +712,7 @@ fn deserialize_instance<R: Read>(
                 "Item" => {
                     deserialize_instance(reader, state, instance_id)?;
                 }
-                _ => {
-                    let event = reader.expect_next().unwrap();
-                    return Err(reader.error(DecodeErrorKind::UnexpectedXmlEvent(event)));
-                }
+                _ => skip_unexpected_element(reader, state)?,
             },
             XmlReadEvent::EndElement { name } => {
                 if name.local_name != "Item" {
@@ -586,20 +731,40 @@ fn deserialize_instance<R: Read>(
         }
     }
 
-    let instance = state.tree.get_by_ref_mut(instance_id).unwrap();
-
-    instance.name = match properties.remove(&"Name".into()) {
-        Some(value) => match value {
-            Variant::String(value) => value,
-            _ => return Err(reader.error(DecodeErrorKind::NameMustBeString(value.ty()))),
-        },
+    let name = match properties.remove(&"Name".into()) {
+        Some(Variant::String(value)) => value,
+        Some(value) => {
+            let class_name = state.tree.get_by_ref(instance_id).unwrap().class;
+            let error = reader.error(DecodeErrorKind::NameMustBeString(value.ty()));
+            state.recover(error, |message| DiagnosticKind::MalformedProperty {
+                class_name: class_name.to_string(),
+                property_name: "Name".to_owned(),
+                message,
+            })?;
+
+            class_name.to_string()
+        }
 
         // TODO: Use reflection to get default name instead. This should only
         // matter for ValueBase instances in files created by tools other than
         // Roblox Studio.
-        None => instance.class.to_string(),
+        None => {
+            let class_name = state.tree.get_by_ref(instance_id).unwrap().class;
+            state.report(
+                reader,
+                DiagnosticKind::UnnamedInstance {
+                    class_name: class_name.to_string(),
+                    referent: instance_id,
+                },
+            )?;
+
+            class_name.to_string()
+        }
     };
 
+    let instance = state.tree.get_by_ref_mut(instance_id).unwrap();
+    instance.name = name;
+
     instance.properties = properties.into_iter().collect();
 
     Ok(())
@@ -640,7 +805,14 @@ fn deserialize_properties<R: Read>(
 
                     let xml_property_name = match xml_property_name {
                         Some(value) => value,
-                        None => return Err(reader.error(DecodeErrorKind::MissingAttribute("name"))),
+                        None => {
+                            let error = reader.error(DecodeErrorKind::MissingAttribute("name"));
+                            state.recover(error, |message| DiagnosticKind::UnexpectedContent {
+                                message,
+                            })?;
+                            reader.eat_unknown_tag()?;
+                            continue;
+                        }
                     };
 
                     (name.local_name.to_owned(), xml_property_name)
@@ -651,129 +823,195 @@ fn deserialize_properties<R: Read>(
                 }
                 _ => {
                     let err = DecodeErrorKind::UnexpectedXmlEvent(reader.expect_next()?);
-                    return Err(reader.error(err));
+                    let error = reader.error(err);
+                    state.recover(error, |message| DiagnosticKind::UnexpectedContent {
+                        message,
+                    })?;
+                    continue;
                 }
             }
         };
 
-        log::trace!(
-            "Deserializing property {class_name}.{xml_property_name}, of type {xml_type_name}"
-        );
+        let depth = reader.depth();
+
+        if let Err(error) = deserialize_property(
+            reader,
+            state,
+            instance_id,
+            class_name,
+            &xml_type_name,
+            &xml_property_name,
+            props,
+        ) {
+            state.recover(error, |message| DiagnosticKind::MalformedProperty {
+                class_name: class_name.to_string(),
+                property_name: xml_property_name,
+                message,
+            })?;
+            reader.skip_to_depth(depth)?;
+        }
+    }
+}
 
-        let maybe_descriptor = if state.options.use_reflection() {
-            find_canonical_property_descriptor(
+/// Reads a single property, starting at its opening tag.
+fn deserialize_property<R: Read>(
+    reader: &mut XmlEventReader<R>,
+    state: &mut ParseState,
+    instance_id: Ref,
+    class_name: Ustr,
+    xml_type_name: &str,
+    xml_property_name: &str,
+    props: &mut HashMap<Ustr, Variant>,
+) -> Result<(), DecodeError> {
+    log::trace!("Deserializing property {class_name}.{xml_property_name}, of type {xml_type_name}");
+
+    let maybe_descriptor = if state.options.use_reflection() {
+        find_canonical_property_descriptor(&class_name, xml_property_name, state.options.database)
+    } else {
+        None
+    };
+
+    if let Some(descriptor) = maybe_descriptor {
+        let Some(value) =
+            read_value_xml(reader, state, xml_type_name, instance_id, descriptor.name)?
+        else {
+            return Ok(());
+        };
+
+        let xml_ty = value.ty();
+
+        // The property descriptor might specify a different type than the
+        // one we saw in the XML.
+        //
+        // This happens when property types are upgraded or if the
+        // serialized data type is different than the canonical one.
+        //
+        // For example:
+        // - Int/Float widening from 32-bit to 64-bit
+        // - BrickColor properties turning into Color3
+        let expected_type = descriptor.data_type.ty();
+        log::trace!("property's read type: {xml_ty:?}, canonical type: {expected_type:?}");
+
+        // Types that differ from the one the property is serialized as
+        // are still converted, but they're worth pointing out.
+        if xml_ty != expected_type
+            && find_serialized_property_descriptor(
                 &class_name,
-                &xml_property_name,
+                xml_property_name,
                 state.options.database,
             )
-        } else {
-            None
-        };
+            .is_some_and(|serialized| serialized.data_type.ty() != xml_ty)
+        {
+            state.report(
+                reader,
+                DiagnosticKind::TypeMismatch {
+                    class_name: class_name.to_string(),
+                    property_name: descriptor.name.to_string(),
+                    expected: expected_type,
+                    actual: xml_ty,
+                },
+            )?;
+        }
 
-        if let Some(descriptor) = maybe_descriptor {
-            let Some(value) =
-                read_value_xml(reader, state, &xml_type_name, instance_id, descriptor.name)?
-            else {
-                continue;
-            };
-
-            let xml_ty = value.ty();
-
-            // The property descriptor might specify a different type than the
-            // one we saw in the XML.
-            //
-            // This happens when property types are upgraded or if the
-            // serialized data type is different than the canonical one.
-            //
-            // For example:
-            // - Int/Float widening from 32-bit to 64-bit
-            // - BrickColor properties turning into Color3
-            let expected_type = descriptor.data_type.ty();
-            log::trace!("property's read type: {xml_ty:?}, canonical type: {expected_type:?}");
-
-            let value = match value.try_convert(class_name, expected_type) {
-                Ok(value) => value,
-
-                // The property descriptor disagreed, and there was no
-                // conversion available. This is always an error.
-                Err(message) => {
-                    return Err(
-                        reader.error(DecodeErrorKind::UnsupportedPropertyConversion {
-                            class_name: class_name.to_string(),
-                            property_name: descriptor.name.to_string(),
-                            expected_type,
-                            actual_type: xml_ty,
-                            message,
-                        }),
-                    );
-                }
-            };
+        let value = match value.try_convert(class_name, expected_type) {
+            Ok(value) => value,
 
-            match &descriptor.kind {
-                PropertyKind::Canonical {
-                    serialization: PropertySerialization::Migrate(migration),
-                } => {
-                    let old_property_name = descriptor.name;
-
-                    match migration.perform(&value) {
-                        Ok(migrated_value) => {
-                            for &new_property_name in migration.new_property_names() {
-                                let new_property_name = Ustr::from(new_property_name);
-                                props.entry(new_property_name).or_insert_with(|| {
-                                    log::trace!(
-                                        "Attempting to migrate property {old_property_name} to {new_property_name}"
-                                    );
-
-                                    migrated_value.clone()
-                                });
-                            }
-                        }
-                        Err(error) => {
-                            return Err(reader.error(DecodeErrorKind::MigrationError(error)));
+            // The property descriptor disagreed, and there was no
+            // conversion available. This is always an error.
+            Err(message) => {
+                return Err(
+                    reader.error(DecodeErrorKind::UnsupportedPropertyConversion {
+                        class_name: class_name.to_string(),
+                        property_name: descriptor.name.to_string(),
+                        expected_type,
+                        actual_type: xml_ty,
+                        message,
+                    }),
+                );
+            }
+        };
+
+        match &descriptor.kind {
+            PropertyKind::Canonical {
+                serialization: PropertySerialization::Migrate(migration),
+            } => {
+                let old_property_name = descriptor.name;
+
+                match migration.perform(&value) {
+                    Ok(migrated_value) => {
+                        for &new_property_name in migration.new_property_names() {
+                            let new_property_name = Ustr::from(new_property_name);
+                            props.entry(new_property_name).or_insert_with(|| {
+                                log::trace!(
+                                    "Attempting to migrate property {old_property_name} to {new_property_name}"
+                                );
+
+                                migrated_value.clone()
+                            });
                         }
+
+                        state.report(
+                            reader,
+                            DiagnosticKind::MigrationApplied {
+                                class_name: class_name.to_string(),
+                                property_name: old_property_name.to_owned(),
+                                new_property_names: migration
+                                    .new_property_names()
+                                    .iter()
+                                    .map(|&name| name.to_owned())
+                                    .collect(),
+                            },
+                        )?;
+                    }
+                    Err(error) => {
+                        return Err(reader.error(DecodeErrorKind::MigrationError(error)));
                     }
                 }
-                _ => {
-                    props.insert(descriptor.name.into(), value);
-                }
-            };
-        } else {
-            match state.options.property_behavior {
-                DecodePropertyBehavior::IgnoreUnknown => {
-                    // We don't care about this property, so we can read it and
-                    // throw it into the void.
-
-                    read_value_xml(
-                        reader,
-                        state,
-                        &xml_type_name,
-                        instance_id,
-                        &xml_property_name,
-                    )?;
-                }
-                DecodePropertyBehavior::ReadUnknown | DecodePropertyBehavior::NoReflection => {
-                    // We'll take this value as-is with no conversions on either
-                    // the name or value.
-
-                    let value = match read_value_xml(
-                        reader,
-                        state,
-                        &xml_type_name,
-                        instance_id,
-                        &xml_property_name,
-                    )? {
-                        Some(value) => value,
-                        None => continue,
-                    };
-                    props.insert(xml_property_name.into(), value);
-                }
-                DecodePropertyBehavior::ErrorOnUnknown => {
-                    return Err(reader.error(DecodeErrorKind::UnknownProperty {
+            }
+            _ => {
+                props.insert(descriptor.name.into(), value);
+            }
+        };
+    } else {
+        match state.options.property_behavior {
+            DecodePropertyBehavior::IgnoreUnknown => {
+                // We don't care about this property, so we can read it and
+                // throw it into the void.
+
+                read_value_xml(reader, state, xml_type_name, instance_id, xml_property_name)?;
+
+                state.report(
+                    reader,
+                    DiagnosticKind::UnknownProperty {
                         class_name: class_name.to_string(),
-                        property_name: xml_property_name,
-                    }));
-                }
+                        property_name: xml_property_name.to_owned(),
+                    },
+                )?;
+            }
+            DecodePropertyBehavior::ReadUnknown | DecodePropertyBehavior::NoReflection => {
+                // We'll take this value as-is with no conversions on either
+                // the name or value.
+
+                let value = match read_value_xml(
+                    reader,
+                    state,
+                    xml_type_name,
+                    instance_id,
+                    xml_property_name,
+                )? {
+                    Some(value) => value,
+                    None => return Ok(()),
+                };
+                props.insert(xml_property_name.into(), value);
+            }
+            DecodePropertyBehavior::ErrorOnUnknown => {
+                return Err(reader.error(DecodeErrorKind::UnknownProperty {
+                    class_name: class_name.to_string(),
+                    property_name: xml_property_name.to_owned(),
+                }));
             }
         }
     }
+
+    Ok(())
 }
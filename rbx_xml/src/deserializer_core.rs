@@ -17,16 +17,34 @@ pub struct XmlEventReader<R: Read> {
     reader: xml::EventReader<R>,
     peeked: Option<Result<XmlReadEvent, xml::reader::Error>>,
     finished: bool,
+
+    /// How many elements have been opened but not closed by the events
+    /// consumed so far. Peeked events aren't counted.
+    depth: usize,
 }
 
 impl<R: Read> Iterator for XmlEventReader<R> {
     type Item = XmlReadResult;
 
     fn next(&mut self) -> Option<XmlReadResult> {
-        if let Some(value) = self.peeked.take() {
-            return Some(value);
+        let value = match self.peeked.take() {
+            Some(value) => Some(value),
+            None => self.next_event(),
+        };
+
+        match &value {
+            Some(Ok(XmlReadEvent::StartElement { .. })) => self.depth += 1,
+            Some(Ok(XmlReadEvent::EndElement { .. })) => self.depth -= 1,
+            _ => {}
         }
 
+        value
+    }
+}
+
+impl<R: Read> XmlEventReader<R> {
+    /// Reads the next event from the underlying reader, skipping whitespace.
+    fn next_event(&mut self) -> Option<XmlReadResult> {
         if self.finished {
             return None;
         }
@@ -48,9 +66,7 @@ impl<R: Read> Iterator for XmlEventReader<R> {
             }
         }
     }
-}
 
-impl<R: Read> XmlEventReader<R> {
     /// Constructs a new `XmlEventReader` from a source that implements `Read`.
     pub fn from_source(source: R) -> XmlEventReader<R> {
         let reader = ParserConfig::new()
@@ -61,6 +77,7 @@ impl<R: Read> XmlEventReader<R> {
             reader,
             peeked: None,
             finished: false,
+            depth: 0,
         }
     }
 
@@ -70,7 +87,7 @@ impl<R: Read> XmlEventReader<R> {
             return self.peeked.as_ref();
         }
 
-        self.peeked = self.next();
+        self.peeked = self.next_event();
         self.peeked.as_ref()
     }
 
@@ -78,6 +95,29 @@ impl<R: Read> XmlEventReader<R> {
         NewDecodeError::new_from_reader(kind.into(), &self.reader)
     }
 
+    /// How many elements have been opened but not yet closed.
+    pub(crate) fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Consumes events until every element opened since the reader was at
+    /// the given depth has been closed.
+    pub(crate) fn skip_to_depth(&mut self, depth: usize) -> Result<(), NewDecodeError> {
+        while self.depth > depth {
+            self.expect_next()?;
+        }
+
+        Ok(())
+    }
+
+    /// The 1-based line and column that the reader is at in the document.
+    pub(crate) fn position(&self) -> (usize, usize) {
+        use xml::common::Position;
+
+        let pos = self.reader.position();
+        ((pos.row + 1) as usize, pos.column as usize)
+    }
+
     pub fn expect_next(&mut self) -> Result<XmlReadEvent, NewDecodeError> {
         match self.next() {
             Some(Ok(event)) => Ok(event),
@@ -0,0 +1,195 @@
+use std::fmt;
+
+use rbx_dom_weak::types::{Ref, VariantType};
+
+/// Something unusual about a document that the decoder was able to recover
+/// from, returned by [`from_reader_with_diagnostics`][crate::from_reader_with_diagnostics].
+///
+/// With [`DecodeOptions::strict`][crate::DecodeOptions::strict] set, each of
+/// these is a [`DecodeError`][crate::DecodeError] instead. With
+/// [`DecodeOptions::lenient`][crate::DecodeOptions::lenient] set, errors in
+/// the document that were skipped over are reported here too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    kind: DiagnosticKind,
+    location: DiagnosticLocation,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(kind: DiagnosticKind, (line, column): (usize, usize)) -> Self {
+        Self {
+            kind,
+            location: DiagnosticLocation { line, column },
+        }
+    }
+
+    /// What happened.
+    pub fn kind(&self) -> &DiagnosticKind {
+        &self.kind
+    }
+
+    /// Where in the document this was found.
+    pub fn location(&self) -> DiagnosticLocation {
+        self.location
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, output: &mut fmt::Formatter) -> fmt::Result {
+        write!(output, "{}: {}", self.location, self.kind)
+    }
+}
+
+/// Where a [`Diagnostic`] was found in a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DiagnosticLocation {
+    /// 1-based line number in the document.
+    pub line: usize,
+    /// 1-based column number in the document.
+    pub column: usize,
+}
+
+impl fmt::Display for DiagnosticLocation {
+    fn fmt(&self, output: &mut fmt::Formatter) -> fmt::Result {
+        write!(output, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// The kinds of [`Diagnostic`] that can be reported while decoding.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+    /// A property that isn't in the reflection database was skipped because
+    /// of [`DecodePropertyBehavior::IgnoreUnknown`][crate::DecodePropertyBehavior::IgnoreUnknown].
+    UnknownProperty {
+        /// The class the property is on.
+        class_name: String,
+        /// The name of the property in the document.
+        property_name: String,
+    },
+
+    /// A property was skipped because rbx_xml doesn't know how to read its
+    /// type.
+    UnknownPropertyType {
+        /// The class the property is on.
+        class_name: String,
+        /// The name of the property in the document.
+        property_name: String,
+        /// The XML tag name of the type.
+        type_name: String,
+    },
+
+    /// A property was written with a different type than the reflection
+    /// database expects. It's converted to the expected type when rbx_xml
+    /// knows how, and is kept as-is otherwise.
+    TypeMismatch {
+        /// The class the property is on.
+        class_name: String,
+        /// The name of the property.
+        property_name: String,
+        /// The type the reflection database expects.
+        expected: VariantType,
+        /// The type the property was written with.
+        actual: VariantType,
+    },
+
+    /// A property was migrated to one or more newer properties, like `Font`
+    /// to `FontFace`.
+    MigrationApplied {
+        /// The class the property is on.
+        class_name: String,
+        /// The name of the property that was migrated.
+        property_name: String,
+        /// The names of the properties it was migrated to.
+        new_property_names: Vec<String>,
+    },
+
+    /// An instance had no `Name` property, so it was named after its class.
+    UnnamedInstance {
+        /// The class of the instance.
+        class_name: String,
+        /// The instance in the decoded `WeakDom`.
+        referent: Ref,
+    },
+
+    /// A property couldn't be decoded, so it was skipped. Only reported in
+    /// lenient mode.
+    MalformedProperty {
+        /// The class the property is on.
+        class_name: String,
+        /// The name of the property in the document.
+        property_name: String,
+        /// The error that the property caused.
+        message: String,
+    },
+
+    /// An `<Item>` couldn't be decoded, so it was skipped along with its
+    /// descendants. Only reported in lenient mode.
+    MalformedInstance {
+        /// The error that the instance caused.
+        message: String,
+    },
+
+    /// Something that doesn't belong in the document, like an unknown tag or
+    /// a property without a name, was skipped. Only reported in lenient mode.
+    UnexpectedContent {
+        /// The error that the content caused.
+        message: String,
+    },
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, output: &mut fmt::Formatter) -> fmt::Result {
+        use self::DiagnosticKind::*;
+
+        match self {
+            UnknownProperty {
+                class_name,
+                property_name,
+            } => write!(
+                output,
+                "Property {class_name}.{property_name} is unknown and was skipped"
+            ),
+            UnknownPropertyType {
+                class_name,
+                property_name,
+                type_name,
+            } => write!(
+                output,
+                "Property {class_name}.{property_name} has unknown type '{type_name}' and was skipped"
+            ),
+            TypeMismatch {
+                class_name,
+                property_name,
+                expected,
+                actual,
+            } => write!(
+                output,
+                "Property {class_name}.{property_name} is expected to be of type {expected:?}, but it was of type {actual:?}"
+            ),
+            MigrationApplied {
+                class_name,
+                property_name,
+                new_property_names,
+            } => write!(
+                output,
+                "Property {class_name}.{property_name} was migrated to {}",
+                new_property_names.join(", ")
+            ),
+            UnnamedInstance { class_name, .. } => {
+                write!(output, "An instance of {class_name} has no Name property")
+            }
+            MalformedProperty {
+                class_name,
+                property_name,
+                message,
+            } => write!(
+                output,
+                "Property {class_name}.{property_name} was skipped: {message}"
+            ),
+            MalformedInstance { message } => write!(output, "Item was skipped: {message}"),
+            UnexpectedContent { message } => write!(output, "Content was skipped: {message}"),
+        }
+    }
+}
@@ -5,6 +5,8 @@ use std::{
 
 use rbx_dom_weak::types::VariantType;
 
+use crate::diagnostics::DiagnosticKind;
+
 /// An error that can occur when deserializing an XML-format model or place.
 #[derive(Debug)]
 pub struct DecodeError {
@@ -41,6 +43,24 @@ impl DecodeError {
     pub fn column(&self) -> usize {
         self.inner.column
     }
+
+    pub(crate) fn kind(&self) -> &DecodeErrorKind {
+        &self.inner.kind
+    }
+
+    /// Tells whether decoding can carry on past this error by skipping
+    /// whatever caused it. Errors from the XML parser itself leave it unable
+    /// to continue, and errors that were asked for with `ErrorOnUnknown` or
+    /// strict mode are never skipped.
+    pub(crate) fn is_recoverable(&self) -> bool {
+        !matches!(
+            self.inner.kind,
+            DecodeErrorKind::Xml(_)
+                | DecodeErrorKind::UnexpectedEof
+                | DecodeErrorKind::UnknownProperty { .. }
+                | DecodeErrorKind::Strict(_)
+        )
+    }
 }
 
 impl fmt::Display for DecodeError {
@@ -94,6 +114,7 @@ pub(crate) enum DecodeErrorKind {
         actual_type: VariantType,
         message: String,
     },
+    Strict(DiagnosticKind),
 }
 
 impl fmt::Display for DecodeErrorKind {
@@ -136,6 +157,7 @@ impl fmt::Display for DecodeErrorKind {
                 "Property {class_name}.{property_name} is expected to be of type {expected_type:?}, but it was of type {actual_type:?} \
                  When trying to convert, this error occured: {message}"
             ),
+            Strict(kind) => write!(output, "Strict mode: {kind}"),
         }
     }
 }
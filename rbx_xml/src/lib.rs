@@ -113,6 +113,7 @@ mod conversion;
 mod core;
 mod deserializer;
 mod deserializer_core;
+mod diagnostics;
 mod error;
 mod serializer;
 mod serializer_core;
@@ -127,12 +128,18 @@ use std::io::{Read, Write};
 
 use rbx_dom_weak::{types::Ref, WeakDom};
 
-use crate::{deserializer::decode_internal, serializer::encode_internal};
+use crate::{
+    deserializer::{decode_internal, decode_with_diagnostics},
+    serializer::encode_internal,
+};
 
 pub use crate::{
     deserializer::{DecodeOptions, DecodePropertyBehavior},
+    diagnostics::{Diagnostic, DiagnosticKind, DiagnosticLocation},
     error::{DecodeError, EncodeError},
-    serializer::{EncodeOptions, EncodePropertyBehavior},
+    serializer::{
+        EncodeOptions, EncodePropertyBehavior, Indentation, PropertyOrder, ReferentNaming,
+    },
 };
 
 /// Decodes an XML-format model or place from something that implements the
@@ -141,6 +148,16 @@ pub fn from_reader<R: Read>(reader: R, options: DecodeOptions) -> Result<WeakDom
     decode_internal(reader, options)
 }
 
+/// Decodes an XML-format model or place from something that implements the
+/// `std::io::Read` trait, also returning a [`Diagnostic`] for every problem
+/// with the document that was recovered from, in the order they were found.
+pub fn from_reader_with_diagnostics<R: Read>(
+    reader: R,
+    options: DecodeOptions,
+) -> Result<(WeakDom, Vec<Diagnostic>), DecodeError> {
+    decode_with_diagnostics(reader, options)
+}
+
 /// Decodes an XML-format model or place from something that implements the
 /// `std::io::Read` trait using the default decoder options.
 pub fn from_reader_default<R: Read>(reader: R) -> Result<WeakDom, DecodeError> {
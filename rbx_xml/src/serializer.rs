@@ -5,7 +5,7 @@ use rbx_dom_weak::{
     types::{Ref, SharedString, SharedStringHash, Variant},
     WeakDom,
 };
-use rbx_reflection::{PropertyKind, PropertySerialization, ReflectionDatabase};
+use rbx_reflection::{ClassDescriptor, PropertyKind, PropertySerialization, ReflectionDatabase};
 
 use crate::{
     conversion::ConvertVariant,
@@ -22,9 +22,13 @@ pub fn encode_internal<W: Write>(
     ids: &[Ref],
     options: EncodeOptions,
 ) -> Result<(), NewEncodeError> {
-    let mut writer = XmlEventWriter::from_output(output);
+    let mut writer = XmlEventWriter::from_output(output, options.indentation);
     let mut state = EmitState::new(options);
 
+    if state.options.referent_naming == ReferentNaming::Stable {
+        state.assign_stable_referents(tree, ids);
+    }
+
     writer.write(XmlWriteEvent::start_element("roblox").attr("version", "4"))?;
 
     let mut property_buffer = Vec::new();
@@ -69,11 +73,76 @@ pub enum EncodePropertyBehavior {
     NoReflection,
 }
 
+/// Describes how rbx_xml should indent the XML it writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Indentation {
+    /// Indents each level with the given number of spaces.
+    ///
+    /// The default is two spaces.
+    Spaces(usize),
+
+    /// Indents each level with one tab, like Roblox Studio does.
+    Tabs,
+
+    /// Writes the whole document without any line breaks or indentation.
+    Compact,
+}
+
+impl Default for Indentation {
+    fn default() -> Indentation {
+        Indentation::Spaces(2)
+    }
+}
+
+/// Describes the order that rbx_xml should write the properties of each
+/// instance in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PropertyOrder {
+    /// Sorts properties alphabetically by name.
+    ///
+    /// This is the default.
+    Alphabetical,
+
+    /// Groups properties by the class in the reflection database that
+    /// declares them, starting from `Instance` and ending with the instance's
+    /// own class. Properties are sorted alphabetically within each group, and
+    /// properties the database doesn't know about are written last.
+    Reflection,
+}
+
+/// Describes how rbx_xml should name the referents of the instances it
+/// writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ReferentNaming {
+    /// Numbers instances in the order they're written, starting from `0`.
+    ///
+    /// This is the default.
+    Sequential,
+
+    /// Numbers instances in the order they're written with an `RBX` prefix,
+    /// like `RBX0`.
+    Rbx,
+
+    /// Derives each referent from a hash of the instance's `UniqueId`, or of
+    /// its path from the instances being serialized if it doesn't have one.
+    ///
+    /// Instances keep the same referent when other instances are added or
+    /// removed, which keeps diffs of files in version control small.
+    Stable,
+}
+
 /// Options available for serializing an XML-format model or place.
 #[derive(Debug, Clone)]
 pub struct EncodeOptions<'db> {
     property_behavior: EncodePropertyBehavior,
     database: &'db ReflectionDatabase<'db>,
+    indentation: Indentation,
+    property_order: PropertyOrder,
+    referent_naming: ReferentNaming,
+    omit_defaults: bool,
 }
 
 impl<'db> EncodeOptions<'db> {
@@ -83,6 +152,10 @@ impl<'db> EncodeOptions<'db> {
         EncodeOptions {
             property_behavior: EncodePropertyBehavior::IgnoreUnknown,
             database: rbx_reflection_database::get().unwrap(),
+            indentation: Indentation::default(),
+            property_order: PropertyOrder::Alphabetical,
+            referent_naming: ReferentNaming::Sequential,
+            omit_defaults: false,
         }
     }
 
@@ -103,6 +176,47 @@ impl<'db> EncodeOptions<'db> {
         EncodeOptions { database, ..self }
     }
 
+    /// Determines how rbx_xml will indent the XML it writes.
+    #[inline]
+    pub fn indentation(self, indentation: Indentation) -> Self {
+        EncodeOptions {
+            indentation,
+            ..self
+        }
+    }
+
+    /// Determines the order rbx_xml will write properties in.
+    #[inline]
+    pub fn property_order(self, property_order: PropertyOrder) -> Self {
+        EncodeOptions {
+            property_order,
+            ..self
+        }
+    }
+
+    /// Determines how rbx_xml will name the referents of instances.
+    #[inline]
+    pub fn referent_naming(self, referent_naming: ReferentNaming) -> Self {
+        EncodeOptions {
+            referent_naming,
+            ..self
+        }
+    }
+
+    /// Determines whether rbx_xml will skip properties whose values are the
+    /// same as the default value for their class in the reflection database.
+    /// Roblox uses the default value for any property that isn't in a file.
+    ///
+    /// This has no effect when the property behavior is
+    /// [`EncodePropertyBehavior::NoReflection`].
+    #[inline]
+    pub fn omit_defaults(self, omit_defaults: bool) -> Self {
+        EncodeOptions {
+            omit_defaults,
+            ..self
+        }
+    }
+
     pub(crate) fn use_reflection(&self) -> bool {
         self.property_behavior != EncodePropertyBehavior::NoReflection
     }
@@ -119,7 +233,7 @@ pub struct EmitState<'db> {
 
     /// A map of IDs written so far to the generated referent that they use.
     /// This map is used to correctly emit Ref properties.
-    referent_map: HashMap<Ref, u32>,
+    referent_map: HashMap<Ref, String>,
 
     /// The referent value that will be used for emitting the next instance.
     next_referent: u32,
@@ -139,16 +253,27 @@ impl<'db> EmitState<'db> {
         }
     }
 
-    pub fn map_id(&mut self, id: Ref) -> u32 {
-        match self.referent_map.get(&id) {
-            Some(&value) => value,
-            None => {
-                let referent = self.next_referent;
-                self.referent_map.insert(id, referent);
-                self.next_referent += 1;
-                referent
+    pub fn map_id(&mut self, id: Ref) -> &str {
+        let options = &self.options;
+        let next_referent = &mut self.next_referent;
+
+        self.referent_map.entry(id).or_insert_with(|| {
+            let referent = *next_referent;
+            *next_referent += 1;
+
+            match options.referent_naming {
+                ReferentNaming::Sequential => referent.to_string(),
+                ReferentNaming::Rbx | ReferentNaming::Stable => format!("RBX{referent}"),
             }
-        }
+        })
+    }
+
+    /// Assigns a referent to every instance in the given subtrees based on
+    /// its `UniqueId` or its path with [`WeakDom::stable_ids`], instead of the
+    /// order it's written in.
+    fn assign_stable_referents(&mut self, tree: &WeakDom, ids: &[Ref]) {
+        self.referent_map
+            .extend(tree.stable_ids(ids, stable_referent));
     }
 
     pub fn add_shared_string(&mut self, value: SharedString) {
@@ -156,6 +281,19 @@ impl<'db> EmitState<'db> {
     }
 }
 
+/// Hashes a key with 128-bit FNV-1a and formats it like the referents Roblox
+/// writes. FNV-1a is used because its output will never change.
+fn stable_referent(key: &str) -> String {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    let hash = key.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u128::from(byte)).wrapping_mul(PRIME)
+    });
+
+    format!("RBX{hash:032X}")
+}
+
 /// Serialize a single instance.
 ///
 /// `property_buffer` is a Vec that can be reused between calls to
@@ -168,12 +306,12 @@ fn serialize_instance<'dom, W: Write>(
     property_buffer: &mut Vec<(&'dom str, &'dom Variant)>,
 ) -> Result<(), NewEncodeError> {
     let instance = tree.get_by_ref(id).unwrap();
-    let mapped_id = state.map_id(id);
+    let mapped_id = state.map_id(id).to_owned();
 
     writer.write(
         XmlWriteEvent::start_element("Item")
             .attr("class", &instance.class)
-            .attr("referent", &mapped_id.to_string()),
+            .attr("referent", &mapped_id),
     )?;
 
     writer.write(XmlWriteEvent::start_element("Properties"))?;
@@ -188,9 +326,32 @@ fn serialize_instance<'dom, W: Write>(
     // Move references to our properties into property_buffer so we can sort
     // them and iterate them in order.
     property_buffer.extend(instance.properties.iter().map(|(k, v)| (k.as_str(), v)));
-    property_buffer.sort_unstable_by_key(|(key, _)| *key);
+
+    let database = state.options.database;
+    let class_descriptor = if state.options.use_reflection() {
+        database.classes.get(instance.class.as_str())
+    } else {
+        None
+    };
+
+    match (state.options.property_order, class_descriptor) {
+        (PropertyOrder::Reflection, Some(class_descriptor)) => {
+            let superclasses = database.superclasses(class_descriptor).unwrap_or_default();
+            property_buffer
+                .sort_unstable_by_key(|(key, _)| (declaration_depth(&superclasses, key), *key));
+        }
+        _ => property_buffer.sort_unstable_by_key(|(key, _)| *key),
+    }
 
     for (property_name, value) in property_buffer.drain(..) {
+        if state.options.omit_defaults {
+            if let Some(class_descriptor) = class_descriptor {
+                if database.find_default_property(class_descriptor, property_name) == Some(value) {
+                    continue;
+                }
+            }
+        }
+
         let maybe_serialized_descriptor = if state.options.use_reflection() {
             find_serialized_property_descriptor(
                 &instance.class,
@@ -268,6 +429,17 @@ fn serialize_instance<'dom, W: Write>(
     Ok(())
 }
 
+/// Returns how far from `Instance` the class that declares a property is, given
+/// a list of a class and its superclasses. Unknown properties are treated as
+/// being declared furthest away.
+fn declaration_depth(superclasses: &[&ClassDescriptor], property_name: &str) -> usize {
+    superclasses
+        .iter()
+        .rev()
+        .position(|class| class.properties.contains_key(property_name))
+        .unwrap_or(usize::MAX)
+}
+
 fn serialize_shared_strings<W: Write>(
     writer: &mut XmlEventWriter<W>,
     state: &mut EmitState,
@@ -7,6 +7,7 @@ pub use xml::writer::XmlEvent as XmlWriteEvent;
 use crate::{
     core::XmlType,
     error::{EncodeError as NewEncodeError, EncodeErrorKind},
+    serializer::Indentation,
 };
 
 /// A wrapper around an xml-rs `EventWriter` as well as other state kept around
@@ -17,13 +18,21 @@ pub struct XmlEventWriter<W> {
 }
 
 impl<W: Write> XmlEventWriter<W> {
-    /// Constructs an `XmlEventWriter` from an output that implements `Write`.
-    pub fn from_output(output: W) -> XmlEventWriter<W> {
-        let inner = EmitterConfig::new()
-            .perform_indent(true)
+    /// Constructs an `XmlEventWriter` from an output that implements `Write`,
+    /// indenting in the given style.
+    pub fn from_output(output: W, indentation: Indentation) -> XmlEventWriter<W> {
+        let config = EmitterConfig::new()
             .write_document_declaration(false)
-            .normalize_empty_elements(false)
-            .create_writer(output);
+            .normalize_empty_elements(false);
+
+        let config = match indentation {
+            Indentation::Spaces(count) => {
+                config.perform_indent(true).indent_string(" ".repeat(count))
+            }
+            Indentation::Tabs => config.perform_indent(true).indent_string("\t"),
+            Indentation::Compact => config.perform_indent(false),
+        };
+        let inner = config.create_writer(output);
 
         XmlEventWriter {
             inner,
@@ -0,0 +1,247 @@
+//! Tests for the problems reported by `from_reader_with_diagnostics` and
+//! strict mode.
+
+use rbx_dom_weak::types::{Ref, VariantType};
+
+use crate::{DecodeOptions, DecodePropertyBehavior, DiagnosticKind};
+
+const DOCUMENT: &str = r#"
+<roblox version="4">
+    <Item class="Folder" referent="0">
+        <Properties>
+            <bogus name="Weird">1</bogus>
+            <string name="NotARealProperty">Hello</string>
+        </Properties>
+        <Item class="IntValue" referent="1">
+            <Properties>
+                <string name="Name">Value</string>
+                <int name="Value">5</int>
+            </Properties>
+        </Item>
+        <Item class="UICorner" referent="2">
+            <Properties>
+                <string name="Name">Corner</string>
+                <UDim name="CornerRadius"><S>0.5</S><O>12</O></UDim>
+            </Properties>
+        </Item>
+    </Item>
+</roblox>
+"#;
+
+#[test]
+fn diagnostics() {
+    let (dom, diagnostics) =
+        crate::from_reader_with_diagnostics(DOCUMENT.as_bytes(), DecodeOptions::new()).unwrap();
+    let folder = dom.get_by_ref(dom.root().children()[0]).unwrap();
+
+    let kinds: Vec<_> = diagnostics.iter().map(|d| d.kind().clone()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            DiagnosticKind::UnknownPropertyType {
+                class_name: "Folder".to_owned(),
+                property_name: "Weird".to_owned(),
+                type_name: "bogus".to_owned(),
+            },
+            DiagnosticKind::UnknownProperty {
+                class_name: "Folder".to_owned(),
+                property_name: "Weird".to_owned(),
+            },
+            DiagnosticKind::UnknownProperty {
+                class_name: "Folder".to_owned(),
+                property_name: "NotARealProperty".to_owned(),
+            },
+            DiagnosticKind::TypeMismatch {
+                class_name: "IntValue".to_owned(),
+                property_name: "Value".to_owned(),
+                expected: VariantType::Int64,
+                actual: VariantType::Int32,
+            },
+            DiagnosticKind::MigrationApplied {
+                class_name: "UICorner".to_owned(),
+                property_name: "CornerRadius".to_owned(),
+                new_property_names: vec![
+                    "BottomLeftRadius".to_owned(),
+                    "BottomRightRadius".to_owned(),
+                    "TopLeftRadius".to_owned(),
+                    "TopRightRadius".to_owned(),
+                ],
+            },
+            DiagnosticKind::UnnamedInstance {
+                class_name: "Folder".to_owned(),
+                referent: folder.referent(),
+            },
+        ]
+    );
+
+    // Diagnostics point at where they were found.
+    assert_eq!(diagnostics[0].location().line, 5);
+    assert_eq!(diagnostics[5].location().line, 20);
+    assert_eq!(folder.name, "Folder");
+}
+
+#[test]
+fn strict() {
+    let error = crate::from_str(DOCUMENT, DecodeOptions::new().strict(true)).unwrap_err();
+    assert_eq!(error.line(), 5);
+
+    let named = r#"
+        <roblox version="4">
+            <Item class="Folder" referent="0">
+                <Properties>
+                    <string name="Name">Folder</string>
+                </Properties>
+            </Item>
+        </roblox>
+    "#;
+    crate::from_str(named, DecodeOptions::new().strict(true)).unwrap();
+
+    // Unknown properties that are read as-is aren't a problem.
+    let unknown = r#"
+        <roblox version="4">
+            <Item class="Folder" referent="0">
+                <Properties>
+                    <string name="Name">Folder</string>
+                    <string name="NotARealProperty">Hello</string>
+                </Properties>
+            </Item>
+        </roblox>
+    "#;
+    crate::from_str(
+        unknown,
+        DecodeOptions::new()
+            .strict(true)
+            .property_behavior(DecodePropertyBehavior::ReadUnknown),
+    )
+    .unwrap();
+}
+
+const BROKEN_DOCUMENT: &str = r#"
+<roblox version="4">
+    <Item class="IntValue" referent="0">
+        <Properties>
+            <string name="Name">Broken</string>
+            <int64 name="Value">not a number</int64>
+            <string>No name</string>
+            <bool name="Archivable">false</bool>
+        </Properties>
+        <Garbage><Nested /></Garbage>
+        <Item referent="1">
+            <Properties>
+                <string name="Name">No class</string>
+            </Properties>
+            <Item class="Folder" referent="2" />
+        </Item>
+    </Item>
+    <Item class="Folder" referent="3">
+        <Properties>
+            <string name="Name">Intact</string>
+        </Properties>
+    </Item>
+</roblox>
+"#;
+
+#[test]
+fn lenient() {
+    let error = crate::from_str_default(BROKEN_DOCUMENT).unwrap_err();
+    assert_eq!(error.line(), 6);
+
+    let (dom, diagnostics) = crate::from_reader_with_diagnostics(
+        BROKEN_DOCUMENT.as_bytes(),
+        DecodeOptions::new().lenient(true),
+    )
+    .unwrap();
+
+    let names: Vec<_> = dom
+        .descendants()
+        .skip(1)
+        .map(|instance| instance.name.as_str())
+        .collect();
+    assert_eq!(names, ["Broken", "Intact"]);
+
+    let broken = dom.get_by_ref(dom.root().children()[0]).unwrap();
+    assert!(!broken.properties.contains_key(&"Value".into()));
+    assert_eq!(
+        broken.properties.get(&"Archivable".into()),
+        Some(&false.into())
+    );
+
+    let skipped: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| match diagnostic.kind() {
+            DiagnosticKind::MalformedProperty { property_name, .. } => {
+                format!("property {property_name}")
+            }
+            DiagnosticKind::MalformedInstance { .. } => "instance".to_owned(),
+            DiagnosticKind::UnexpectedContent { .. } => "content".to_owned(),
+            other => panic!("unexpected diagnostic {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        skipped,
+        ["property Value", "content", "content", "instance"]
+    );
+
+    let lines: Vec<_> = diagnostics.iter().map(|d| d.location().line).collect();
+    assert_eq!(lines, [6, 7, 10, 11]);
+
+    // Strict mode never skips anything.
+    crate::from_str(
+        BROKEN_DOCUMENT,
+        DecodeOptions::new().lenient(true).strict(true),
+    )
+    .unwrap_err();
+}
+
+/// Instances that were skipped partway through shouldn't leave anything
+/// behind, including their descendants and Refs that point to them.
+#[test]
+fn lenient_removes_partial_instances() {
+    let document = r#"
+<roblox version="4">
+    <Item class="Folder" referent="0">
+        <Properties>
+            <string name="Name">Partial</string>
+        </Properties>
+        <Item class="ObjectValue" referent="1">
+            <Properties>
+                <string name="Name">Child</string>
+                <Ref name="Value">2</Ref>
+            </Properties>
+        </Item>
+        Stray text
+    </Item>
+    <Item class="ObjectValue" referent="2">
+        <Properties>
+            <string name="Name">Pointer</string>
+            <Ref name="Value">1</Ref>
+        </Properties>
+    </Item>
+</roblox>
+"#;
+
+    let (dom, diagnostics) = crate::from_reader_with_diagnostics(
+        document.as_bytes(),
+        DecodeOptions::new().lenient(true),
+    )
+    .unwrap();
+
+    let names: Vec<_> = dom
+        .descendants()
+        .skip(1)
+        .map(|instance| instance.name.as_str())
+        .collect();
+    assert_eq!(names, ["Pointer"]);
+
+    let pointer = dom.get_by_ref(dom.root().children()[0]).unwrap();
+    assert_eq!(
+        pointer.properties.get(&"Value".into()),
+        Some(&Ref::none().into())
+    );
+
+    assert_eq!(diagnostics.len(), 1);
+    assert!(matches!(
+        diagnostics[0].kind(),
+        DiagnosticKind::MalformedInstance { .. }
+    ));
+}
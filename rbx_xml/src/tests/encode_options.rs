@@ -0,0 +1,192 @@
+//! Tests for the output options in `EncodeOptions`.
+
+use rbx_dom_weak::{
+    types::{Enum, UniqueId},
+    InstanceBuilder, WeakDom,
+};
+
+use crate::{EncodeOptions, Indentation, PropertyOrder, ReferentNaming};
+
+fn encode(dom: &WeakDom, options: EncodeOptions) -> String {
+    let mut output = Vec::new();
+    crate::to_writer(&mut output, dom, dom.root().children(), options).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+/// Returns the referent written for the `Item` with the given name.
+fn referent_of(xml: &str, name: &str) -> String {
+    let name_position = xml
+        .find(&format!("<string name=\"Name\">{name}</string>"))
+        .unwrap();
+    let item = &xml[..name_position];
+    let start = item.rfind("referent=\"").unwrap() + "referent=\"".len();
+
+    item[start..].split('"').next().unwrap().to_owned()
+}
+
+/// Returns every referent written for an `Item`.
+fn referents(xml: &str) -> Vec<&str> {
+    xml.split("referent=\"")
+        .skip(1)
+        .map(|rest| rest.split('"').next().unwrap())
+        .collect()
+}
+
+fn part_dom() -> WeakDom {
+    WeakDom::new(
+        InstanceBuilder::new("DataModel").with_child(
+            InstanceBuilder::new("Part")
+                .with_property("Transparency", 0.5f32)
+                .with_property("Anchored", false)
+                .with_property("CastShadow", false),
+        ),
+    )
+}
+
+#[test]
+fn indentation() {
+    let dom = part_dom();
+
+    let default = encode(&dom, EncodeOptions::new());
+    assert!(default.contains("\n  <Item"));
+
+    let tabs = encode(&dom, EncodeOptions::new().indentation(Indentation::Tabs));
+    assert!(tabs.contains("\n\t<Item"));
+    assert!(tabs.contains("\n\t\t<Properties>"));
+
+    let compact = encode(&dom, EncodeOptions::new().indentation(Indentation::Compact));
+    assert!(!compact.contains('\n'));
+
+    // Indentation never changes what's decoded.
+    for xml in [tabs, compact] {
+        let decoded = crate::from_str_default(xml).unwrap();
+        assert_eq!(decoded.descendants().count(), 2);
+    }
+}
+
+#[test]
+fn property_order() {
+    let dom = WeakDom::new(
+        InstanceBuilder::new("DataModel").with_child(
+            InstanceBuilder::new("Part")
+                .with_property("Shape", Enum::from_u32(0))
+                .with_property("Transparency", 0.5f32)
+                .with_property("Anchored", true),
+        ),
+    );
+    let position = |xml: &str, name: &str| xml.find(&format!("name=\"{name}\"")).unwrap();
+
+    let alphabetical = encode(&dom, EncodeOptions::new());
+    assert!(position(&alphabetical, "Anchored") < position(&alphabetical, "shape"));
+    assert!(position(&alphabetical, "shape") < position(&alphabetical, "Transparency"));
+
+    // `Shape` is declared by `Part`, which comes after `BasePart`.
+    let reflection = encode(
+        &dom,
+        EncodeOptions::new().property_order(PropertyOrder::Reflection),
+    );
+    assert!(position(&reflection, "Anchored") < position(&reflection, "Transparency"));
+    assert!(position(&reflection, "Transparency") < position(&reflection, "shape"));
+}
+
+#[test]
+fn referent_naming() {
+    let dom = part_dom();
+
+    let sequential = encode(&dom, EncodeOptions::new());
+    assert!(sequential.contains("referent=\"0\""));
+
+    let rbx = encode(
+        &dom,
+        EncodeOptions::new().referent_naming(ReferentNaming::Rbx),
+    );
+    assert!(rbx.contains("referent=\"RBX0\""));
+}
+
+/// Builds a model with a part that the model refers to and a part with a
+/// `UniqueId`, optionally with another part before them.
+fn model_dom(extra: Option<&str>) -> WeakDom {
+    let target = InstanceBuilder::new("Part").with_name("Target");
+    let target_ref = target.referent();
+
+    let mut children = Vec::new();
+    if let Some(name) = extra {
+        children.push(InstanceBuilder::new("Part").with_name(name));
+    }
+    children.push(target);
+    children.push(
+        InstanceBuilder::new("Part")
+            .with_name("Tagged")
+            .with_property("UniqueId", UniqueId::new(1, 2, 3)),
+    );
+
+    WeakDom::new(
+        InstanceBuilder::new("DataModel").with_child(
+            InstanceBuilder::new("Model")
+                .with_name("Model")
+                .with_property("PrimaryPart", target_ref)
+                .with_children(children),
+        ),
+    )
+}
+
+#[test]
+fn stable_referents() {
+    let options = || EncodeOptions::new().referent_naming(ReferentNaming::Stable);
+
+    let before = encode(&model_dom(None), options());
+    assert_eq!(before, encode(&model_dom(None), options()));
+    assert!(referent_of(&before, "Target").starts_with("RBX"));
+
+    // Refs are written with the same referents.
+    assert!(before.contains(&format!(
+        "<Ref name=\"PrimaryPart\">{}</Ref>",
+        referent_of(&before, "Target")
+    )));
+
+    // Adding an instance before the others doesn't change their referents.
+    let after = encode(&model_dom(Some("First")), options());
+    for name in ["Model", "Target", "Tagged"] {
+        assert_eq!(referent_of(&before, name), referent_of(&after, name));
+    }
+
+    // Instances with a UniqueId keep their referent when they're renamed,
+    // even if another instance takes their name.
+    let mut dom = model_dom(Some("Tagged"));
+    let tagged = dom
+        .descendants()
+        .find(|instance| instance.properties.contains_key(&"UniqueId".into()))
+        .unwrap()
+        .referent();
+    dom.get_by_ref_mut(tagged).unwrap().name = "Renamed".to_owned();
+    assert_eq!(
+        referent_of(&before, "Tagged"),
+        referent_of(&encode(&dom, options()), "Renamed")
+    );
+
+    // Copies of instances, including their UniqueId, still get distinct
+    // referents.
+    let model = dom.root().children()[0];
+    let copy = dom.clone_within(model);
+    dom.transfer_within(copy, dom.root_ref());
+    let copies = encode(&dom, options());
+
+    let mut written = referents(&copies);
+    assert_eq!(written.len(), 8);
+    written.sort_unstable();
+    written.dedup();
+    assert_eq!(written.len(), 8);
+}
+
+#[test]
+fn omit_defaults() {
+    let dom = part_dom();
+
+    let full = encode(&dom, EncodeOptions::new());
+    assert!(full.contains("name=\"Anchored\""));
+
+    let minimal = encode(&dom, EncodeOptions::new().omit_defaults(true));
+    assert!(!minimal.contains("name=\"Anchored\""));
+    assert!(minimal.contains("name=\"CastShadow\""));
+    assert!(minimal.contains("name=\"Transparency\""));
+}
@@ -1,6 +1,8 @@
 //! Tests to ensure the functionality of the parser.
 mod basic;
+mod diagnostics;
 mod edge_cases;
+mod encode_options;
 mod formatting;
 mod models;
 mod places;
@@ -92,7 +92,7 @@ macro_rules! declare_rbx_types {
                 self::net_asset_ref::XML_TAG_NAME => read_net_asset_ref(reader, instance_id, property_name, state).map(Some),
 
                 _ => {
-                    state.unknown_type_visited(instance_id, property_name, xml_type_name);
+                    state.unknown_type_visited(reader, instance_id, property_name, xml_type_name)?;
                     reader.eat_unknown_tag()?;
 
                     Ok(None)